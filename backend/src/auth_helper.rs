@@ -0,0 +1,284 @@
+use argon2::{
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+    password_hash::{
+        SaltString,
+        rand_core::{OsRng, RngCore},
+    },
+};
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{StatusCode, header::AUTHORIZATION, request::Parts},
+    response::IntoResponse,
+};
+use chrono::{Local, NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use surrealdb::{RecordId, Surreal, engine::local::Db};
+use taskflow_types::{AuthSession, Credentials, User};
+
+use crate::error::{AppError, AppResult, is_index_violation};
+use crate::extract::Json;
+
+// How long a login stays valid
+const SESSION_DAYS: i64 = 30;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UserDB {
+    pub id: Option<RecordId>,
+    pub username: String,
+    pub password_hash: String,
+    pub created_at: NaiveDateTime,
+}
+
+// The record key is the bearer token itself, so a lookup is a single select
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SessionDB {
+    pub id: Option<RecordId>,
+    pub user: RecordId,
+    pub expires_at: NaiveDateTime,
+}
+
+// The signed in user, resolved from the `Authorization: Bearer <token>` header.
+// Adding it to a handler's arguments is what makes the route require a login.
+#[derive(Clone)]
+pub struct AuthUser {
+    pub id: RecordId,
+    pub username: String,
+    session: RecordId,
+}
+
+fn bearer_token(parts: &Parts) -> AppResult<&str> {
+    parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or(AppError::Unauthorized("Missing bearer token".to_string()))
+}
+
+impl FromRequestParts<Surreal<Db>> for AuthUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, conn: &Surreal<Db>) -> AppResult<Self> {
+        let token = bearer_token(parts)?;
+        let session: Option<SessionDB> = conn.select(("session", token)).await?;
+        let session = session
+            .filter(|session| session.expires_at > Local::now().naive_local())
+            .ok_or(AppError::Unauthorized(
+                "Session is invalid or has expired".to_string(),
+            ))?;
+        let user: Option<UserDB> = conn.select(session.user.clone()).await?;
+        let user = user.ok_or(AppError::Unauthorized(
+            "The account of this session no longer exists".to_string(),
+        ))?;
+        Ok(AuthUser {
+            id: session.user,
+            username: user.username,
+            session: RecordId::from_table_key("session", token),
+        })
+    }
+}
+
+// The configured admin token, handed to the extractor below as a request extension
+#[derive(Clone)]
+pub struct AdminToken(pub Option<String>);
+
+// Requests carrying the admin token as their bearer token. Accounts have no say here, the
+// /admin endpoints reach the data of every user.
+pub struct AdminAuth;
+
+impl FromRequestParts<Surreal<Db>> for AdminAuth {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _conn: &Surreal<Db>) -> AppResult<Self> {
+        let Some(AdminToken(Some(admin_token))) = parts.extensions.get::<AdminToken>() else {
+            return Err(AppError::Unauthorized(
+                "Admin endpoints are disabled, no admin token is configured".to_string(),
+            ));
+        };
+        // Compared in constant time, how long a wrong guess takes tells nothing about the token
+        let token = bearer_token(parts)?;
+        if !bool::from(token.as_bytes().ct_eq(admin_token.as_bytes())) {
+            return Err(AppError::Unauthorized("Invalid admin token".to_string()));
+        }
+        Ok(AdminAuth)
+    }
+}
+
+pub async fn signup(
+    State(conn): State<Surreal<Db>>,
+    Json(credentials): Json<Credentials>,
+) -> AppResult<impl IntoResponse> {
+    credentials.validate().map_err(AppError::BadRequest)?;
+    if find_user(&conn, &credentials.username).await?.is_some() {
+        return Err(username_taken(&credentials.username));
+    }
+    let mut first_user_dbresp = conn.query("SELECT VALUE id FROM user LIMIT 1").await?;
+    let existing: Vec<RecordId> = first_user_dbresp.take(0)?;
+
+    let user_db = UserDB {
+        id: None,
+        username: credentials.username,
+        password_hash: hash_password(&credentials.password)?,
+        created_at: Local::now().naive_local(),
+    };
+    let (user_id, username) = create_user(&conn, user_db).await?;
+    if existing.is_empty() {
+        adopt_unowned_records(&conn, &user_id).await?;
+    }
+    let session = start_session(&conn, user_id, username).await?;
+    Ok((StatusCode::CREATED, Json(session)))
+}
+
+pub async fn login(
+    State(conn): State<Surreal<Db>>,
+    Json(credentials): Json<Credentials>,
+) -> AppResult<impl IntoResponse> {
+    // Same answer for unknown users and wrong passwords, so usernames can't be probed
+    let invalid = || AppError::Unauthorized("Invalid username or password".to_string());
+    let user = find_user(&conn, &credentials.username)
+        .await?
+        .ok_or_else(invalid)?;
+    if !verify_password(&credentials.password, &user.password_hash) {
+        return Err(invalid());
+    }
+    let user_id = user
+        .id
+        .ok_or(AppError::Internal("Stored user has no id".to_string()))?;
+    let session = start_session(&conn, user_id, user.username).await?;
+    Ok(Json(session))
+}
+
+pub async fn logout(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+) -> AppResult<impl IntoResponse> {
+    let _: Option<SessionDB> = conn.delete(user.session).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn get_me(user: AuthUser) -> AppResult<impl IntoResponse> {
+    Ok(Json(User {
+        id: user.id.to_string(),
+        username: user.username,
+    }))
+}
+
+// The unique index on usernames settles two sign ups racing for the same name
+async fn create_user(conn: &Surreal<Db>, user_db: UserDB) -> AppResult<(RecordId, String)> {
+    let username = user_db.username.clone();
+    let created: Option<UserDB> = conn.create("user").content(user_db).await.map_err(|err| {
+        if is_index_violation(&err) {
+            username_taken(&username)
+        } else {
+            err.into()
+        }
+    })?;
+    created
+        .and_then(|user| Some((user.id?, user.username)))
+        .ok_or(AppError::Internal("Created user has no id".to_string()))
+}
+
+fn username_taken(username: &str) -> AppError {
+    AppError::Conflict(format!("Username '{}' is already taken", username))
+}
+
+async fn find_user(conn: &Surreal<Db>, username: &str) -> AppResult<Option<UserDB>> {
+    let mut user_dbresp = conn
+        .query("SELECT * FROM user WHERE username = $username LIMIT 1")
+        .bind(("username", username.to_string()))
+        .await?;
+    let users: Vec<UserDB> = user_dbresp.take(0)?;
+    Ok(users.into_iter().next())
+}
+
+async fn start_session(
+    conn: &Surreal<Db>,
+    user_id: RecordId,
+    username: String,
+) -> AppResult<AuthSession> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let expires_at = Local::now().naive_local() + TimeDelta::days(SESSION_DAYS);
+    let session = SessionDB {
+        id: None,
+        user: user_id.clone(),
+        expires_at,
+    };
+    let _: Option<SessionDB> = conn
+        .create(("session", token.as_str()))
+        .content(session)
+        .await?;
+    Ok(AuthSession {
+        token,
+        user: User {
+            id: user_id.to_string(),
+            username,
+        },
+        expires_at,
+    })
+}
+
+fn hash_password(password: &str) -> AppResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| AppError::Internal(format!("Couldn't hash password: {}", err)))
+}
+
+fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+// Data from before accounts existed has no owner, the first account to sign up takes it over
+async fn adopt_unowned_records(conn: &Surreal<Db>, owner: &RecordId) -> AppResult<()> {
+    conn.query(
+        "UPDATE Tasks SET owner = $owner WHERE owner = NONE;
+         UPDATE static_schedule SET owner = $owner WHERE owner = NONE;
+         UPDATE day_schedule SET owner = $owner WHERE owner = NONE;
+         UPDATE committed_day SET owner = $owner WHERE owner = NONE;
+         UPDATE allocation SET owner = $owner WHERE owner = NONE;",
+    )
+    .bind(("owner", owner.clone()))
+    .await?
+    .check()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db::{test_db, test_user};
+
+    #[tokio::test]
+    async fn taken_usernames_conflict_at_the_index() {
+        let conn = test_db().await;
+        test_user(&conn, "ada").await;
+        let user_db = UserDB {
+            id: None,
+            username: "ada".to_string(),
+            password_hash: String::new(),
+            created_at: Local::now().naive_local(),
+        };
+        assert!(matches!(
+            create_user(&conn, user_db).await,
+            Err(AppError::Conflict(_))
+        ));
+        let mut count_dbresp = conn
+            .query("SELECT VALUE id FROM user WHERE username = 'ada'")
+            .await
+            .unwrap();
+        let ids: Vec<RecordId> = count_dbresp.take(0).unwrap();
+        assert_eq!(ids.len(), 1);
+    }
+}
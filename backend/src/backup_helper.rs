@@ -0,0 +1,427 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{extract::State, response::IntoResponse};
+use chrono::Local;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use surrealdb::{RecordId, Surreal, engine::local::Db, method::Query as DbQuery};
+use taskflow_types::{BACKUP_VERSION, Backup, RestoreMode, RestoreQuery, RestoreReport};
+
+use crate::auth_helper::{AdminAuth, UserDB};
+use crate::error::{AppError, AppResult, is_index_violation};
+use crate::extract::{Json, Query};
+use crate::notification_helper::NotificationDB;
+use crate::schedule_helper::{
+    AllocationDB, BACKFILL_COMMITTED_DAYS, CommittedDayDB, RoutineDB, SchedBlockDB,
+};
+use crate::tag_helper::TagDB;
+use crate::task_helper::TaskDB;
+
+// Archives are far bigger than the bodies of the other endpoints
+pub const MAX_BACKUP_BYTES: usize = 64 * 1024 * 1024;
+
+// Tables in the order they are restored, the relations between records come last.
+// Sessions are left out, restoring logs everyone out. A new table is added here and to
+// `dump_table` and `stage_table`.
+const TABLES: [&str; 11] = [
+    "user",
+    "tag",
+    "Tasks",
+    "static_schedule",
+    "committed_day",
+    "day_schedule",
+    "allocation",
+    "notification",
+    "subtask",
+    "depends_on",
+    "tagged",
+];
+
+// One edge of any relation table, none of them carry fields of their own
+#[derive(Serialize, Deserialize)]
+struct EdgeDB {
+    id: RecordId,
+    #[serde(rename = "in")]
+    from: RecordId,
+    #[serde(rename = "out")]
+    to: RecordId,
+}
+
+#[derive(Deserialize)]
+struct RecordKey {
+    id: Option<RecordId>,
+}
+
+async fn dump<T: Serialize + DeserializeOwned>(
+    conn: &Surreal<Db>,
+    table: &str,
+) -> AppResult<Vec<Value>> {
+    let mut records_dbresp = conn
+        .query("SELECT * FROM type::table($table)")
+        .bind(("table", table.to_string()))
+        .await?;
+    let records: Vec<T> = records_dbresp.take(0)?;
+    records
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<_, _>>()
+        .map_err(|err| AppError::Internal(format!("Couldn't export {}: {}", table, err)))
+}
+
+async fn dump_table(conn: &Surreal<Db>, table: &str) -> AppResult<Vec<Value>> {
+    match table {
+        "user" => dump::<UserDB>(conn, table).await,
+        "tag" => dump::<TagDB>(conn, table).await,
+        "Tasks" => dump::<TaskDB>(conn, table).await,
+        "static_schedule" => dump::<RoutineDB>(conn, table).await,
+        "committed_day" => dump::<CommittedDayDB>(conn, table).await,
+        "day_schedule" => dump::<SchedBlockDB>(conn, table).await,
+        "allocation" => dump::<AllocationDB>(conn, table).await,
+        "notification" => dump::<NotificationDB>(conn, table).await,
+        _ => dump::<EdgeDB>(conn, table).await,
+    }
+}
+
+// Every table of every user, password hashes included
+pub async fn export_backup(
+    State(conn): State<Surreal<Db>>,
+    _admin: AdminAuth,
+) -> AppResult<impl IntoResponse> {
+    Ok(Json(backup_of(&conn).await?))
+}
+
+async fn backup_of(conn: &Surreal<Db>) -> AppResult<Backup> {
+    let mut tables = BTreeMap::new();
+    for table in TABLES {
+        tables.insert(table.to_string(), dump_table(conn, table).await?);
+    }
+    Ok(Backup {
+        version: BACKUP_VERSION,
+        exported_at: Local::now().naive_local(),
+        tables,
+    })
+}
+
+// Records have to keep the id they were exported with, and it has to belong to the table
+fn check_records<T: DeserializeOwned>(
+    table: &str,
+    records: Vec<Value>,
+) -> AppResult<(Vec<RecordId>, Vec<T>)> {
+    let invalid =
+        |err: String| AppError::BadRequest(format!("Invalid record in {}: {}", table, err));
+    let mut ids = Vec::with_capacity(records.len());
+    let mut typed = Vec::with_capacity(records.len());
+    for record in records {
+        let RecordKey { id } =
+            serde_json::from_value(record.clone()).map_err(|err| invalid(err.to_string()))?;
+        let id = id.ok_or_else(|| invalid("a record has no id".to_string()))?;
+        if id.table() != table {
+            return Err(invalid(format!("{} belongs to another table", id)));
+        }
+        ids.push(id);
+        typed.push(serde_json::from_value::<T>(record).map_err(|err| invalid(err.to_string()))?);
+    }
+    Ok((ids, typed))
+}
+
+// Checks the records of one table and adds the statements writing them to the restore
+fn stage<'a, T: Serialize + DeserializeOwned + 'static>(
+    query: DbQuery<'a, Db>,
+    index: usize,
+    table: &str,
+    records: Vec<Value>,
+    mode: RestoreMode,
+    relation: bool,
+) -> AppResult<DbQuery<'a, Db>> {
+    let (ids, typed) = check_records::<T>(table, records)?;
+    let insert = if relation {
+        "INSERT RELATION INTO"
+    } else {
+        "INSERT INTO"
+    };
+    let statements = match mode {
+        RestoreMode::Merge => format!(
+            "DELETE {} WHERE id IN $ids_{}; {} {} $records_{};",
+            table, index, insert, table, index
+        ),
+        RestoreMode::Replace => format!("{} {} $records_{};", insert, table, index),
+    };
+    Ok(query
+        .query(statements)
+        .bind((format!("ids_{}", index), ids))
+        .bind((format!("records_{}", index), typed)))
+}
+
+fn stage_table<'a>(
+    query: DbQuery<'a, Db>,
+    index: usize,
+    table: &str,
+    records: Vec<Value>,
+    mode: RestoreMode,
+) -> AppResult<DbQuery<'a, Db>> {
+    match table {
+        "user" => stage::<UserDB>(query, index, table, records, mode, false),
+        "tag" => stage::<TagDB>(query, index, table, records, mode, false),
+        "Tasks" => stage::<TaskDB>(query, index, table, records, mode, false),
+        "static_schedule" => stage::<RoutineDB>(query, index, table, records, mode, false),
+        "committed_day" => stage::<CommittedDayDB>(query, index, table, records, mode, false),
+        "day_schedule" => stage::<SchedBlockDB>(query, index, table, records, mode, false),
+        "allocation" => stage::<AllocationDB>(query, index, table, records, mode, false),
+        "notification" => stage::<NotificationDB>(query, index, table, records, mode, false),
+        _ => stage::<EdgeDB>(query, index, table, records, mode, true),
+    }
+}
+
+// Merged accounts must not take a username that another account already has here
+async fn ensure_usernames_free(conn: &Surreal<Db>, users: &[Value]) -> AppResult<()> {
+    let mut existing_dbresp = conn.query("SELECT * FROM user").await?;
+    let existing: Vec<UserDB> = existing_dbresp.take(0)?;
+    let existing: HashMap<String, Option<RecordId>> = existing
+        .into_iter()
+        .map(|user| (user.username, user.id))
+        .collect();
+    for user in users {
+        let Ok(user) = serde_json::from_value::<UserDB>(user.clone()) else {
+            continue;
+        };
+        if let Some(id) = existing.get(&user.username)
+            && *id != user.id
+        {
+            return Err(AppError::Conflict(format!(
+                "Username '{}' belongs to another account in this database",
+                user.username
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Restores an archive of `export_backup` in one transaction, so a rejected archive leaves the
+// database as it was
+pub async fn import_backup(
+    State(conn): State<Surreal<Db>>,
+    _admin: AdminAuth,
+    Query(query): Query<RestoreQuery>,
+    Json(backup): Json<Backup>,
+) -> AppResult<impl IntoResponse> {
+    Ok(Json(restore(&conn, query.mode, backup).await?))
+}
+
+async fn restore(
+    conn: &Surreal<Db>,
+    mode: RestoreMode,
+    mut backup: Backup,
+) -> AppResult<RestoreReport> {
+    backup.check_version().map_err(AppError::BadRequest)?;
+    backup.upgrade();
+    if let Some(unknown) = backup
+        .tables
+        .keys()
+        .find(|table| !TABLES.contains(&table.as_str()))
+    {
+        return Err(AppError::BadRequest(format!(
+            "The archive has an unknown table '{}'",
+            unknown
+        )));
+    }
+    if mode == RestoreMode::Merge
+        && let Some(users) = backup.tables.get("user")
+    {
+        ensure_usernames_free(conn, users).await?;
+    }
+
+    let mut tables = backup.tables;
+    let mut restore = conn.query("BEGIN TRANSACTION;");
+    if mode == RestoreMode::Replace {
+        restore = restore.query("DELETE session;");
+        for table in TABLES {
+            restore = restore.query(format!("DELETE {};", table));
+        }
+    }
+    let mut restored = BTreeMap::new();
+    for (index, table) in TABLES.into_iter().enumerate() {
+        let records = tables.remove(table).unwrap_or_default();
+        if records.is_empty() {
+            continue;
+        }
+        restored.insert(table.to_string(), records.len());
+        restore = stage_table(restore, index, table, records, mode)?;
+    }
+    // Archives of older builds have no markers of their committed days
+    let restore = restore.query(BACKFILL_COMMITTED_DAYS);
+    // Two accounts with one username, or two tags with one name, only show up at the indexes
+    let errors = restore.query("COMMIT TRANSACTION;").await?.take_errors();
+    if let Some(err) = errors.values().find(|err| is_index_violation(err)) {
+        return Err(AppError::Conflict(format!(
+            "Records of the archive collide: {}",
+            err
+        )));
+    }
+    if let Some(err) = errors.into_values().next() {
+        return Err(err.into());
+    }
+    Ok(RestoreReport { mode, restored })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+    use crate::migrations::run_migrations;
+    use crate::test_db::{test_db, test_user, unmigrated_db};
+
+    fn user(id: RecordId, username: &str) -> Value {
+        serde_json::to_value(UserDB {
+            id: Some(id),
+            username: username.to_string(),
+            password_hash: String::new(),
+            created_at: NaiveDateTime::default(),
+        })
+        .unwrap()
+    }
+
+    fn edge(id: RecordId) -> Value {
+        serde_json::to_value(EdgeDB {
+            id,
+            from: RecordId::from_table_key("Tasks", "a"),
+            to: RecordId::from_table_key("Tasks", "b"),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn records_keep_ids_of_their_table() {
+        let records = vec![
+            user(RecordId::from_table_key("user", "ada"), "ada"),
+            user(RecordId::from_table_key("user", "bob"), "bob"),
+        ];
+        let (ids, users) = check_records::<UserDB>("user", records).unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                RecordId::from_table_key("user", "ada"),
+                RecordId::from_table_key("user", "bob")
+            ]
+        );
+        assert_eq!(users[1].username, "bob");
+
+        let (ids, _) = check_records::<EdgeDB>(
+            "subtask",
+            vec![edge(RecordId::from_table_key("subtask", "e"))],
+        )
+        .unwrap();
+        assert_eq!(ids, vec![RecordId::from_table_key("subtask", "e")]);
+    }
+
+    #[test]
+    fn records_of_other_tables_or_without_ids_are_refused() {
+        let foreign = user(RecordId::from_table_key("Tasks", "ada"), "ada");
+        let mut without_id = user(RecordId::from_table_key("user", "ada"), "ada");
+        without_id["id"] = Value::Null;
+        let mut wrong_shape = user(RecordId::from_table_key("user", "ada"), "ada");
+        wrong_shape["username"] = Value::from(7);
+        for record in [foreign, without_id, wrong_shape, Value::from("user:ada")] {
+            assert!(
+                matches!(
+                    check_records::<UserDB>("user", vec![record.clone()]),
+                    Err(AppError::BadRequest(_))
+                ),
+                "{}",
+                record
+            );
+        }
+        assert!(matches!(
+            check_records::<EdgeDB>(
+                "tagged",
+                vec![edge(RecordId::from_table_key("subtask", "e"))]
+            ),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn merged_accounts_keep_their_own_usernames() {
+        let conn = test_db().await;
+        let ada = test_user(&conn, "ada").await;
+        let other = RecordId::from_table_key("user", "other");
+
+        // The account itself and new names are fine
+        ensure_usernames_free(
+            &conn,
+            &[user(ada.clone(), "ada"), user(other.clone(), "bob")],
+        )
+        .await
+        .unwrap();
+        // Another account taking a name in use is not
+        assert!(matches!(
+            ensure_usernames_free(&conn, &[user(other, "ada")]).await,
+            Err(AppError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn upgraded_databases_export_and_restore() {
+        let conn = unmigrated_db().await;
+        // A day of the old hourly scheduler, a task from before accounts existed and a day
+        // committed before days had markers
+        conn.query(
+            "CREATE day_schedule CONTENT { date: '2025-03-03', title: ['Gym', 'Work'] };
+             CREATE Tasks:old CONTENT {
+                 name: 'Taxes', description: '', due_by: '2025-04-30T17:00:00', imp_lvl: 8,
+                 req_time: '02:00:00', time_alloted: '00:00:00', is_done: false
+             };
+             CREATE day_schedule CONTENT {
+                 date: '2025-03-04', start: '2025-03-04T09:00:00', end: '2025-03-04T10:00:00',
+                 title: 'Taxes', kind: 'Task', source: Tasks:old, strategy: 'eisenhower'
+             };",
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+        run_migrations(&conn).await.unwrap();
+
+        let mut backup = backup_of(&conn).await.unwrap();
+        assert_eq!(backup.tables["day_schedule"].len(), 1);
+        assert_eq!(backup.tables["Tasks"].len(), 1);
+        assert!(backup.tables["Tasks"][0].get("owner").is_none());
+        let days = &backup.tables["committed_day"];
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0]["date"], "2025-03-04");
+        assert_eq!(days[0]["strategy"], "eisenhower");
+
+        // An archive of an older build has the blocks but not the marker
+        backup.tables.remove("committed_day");
+        let target = test_db().await;
+        let restored = restore(&target, RestoreMode::Replace, backup)
+            .await
+            .unwrap();
+        assert_eq!(restored.restored["Tasks"], 1);
+        let days = backup_of(&target).await.unwrap().tables["committed_day"].clone();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0]["strategy"], "eisenhower");
+    }
+
+    #[tokio::test]
+    async fn colliding_usernames_in_a_replace_are_conflicts() {
+        let conn = test_db().await;
+        let ada = test_user(&conn, "ada").await;
+        let mut backup = backup_of(&conn).await.unwrap();
+        backup.tables.insert(
+            "user".to_string(),
+            vec![
+                user(RecordId::from_table_key("user", "one"), "bob"),
+                user(RecordId::from_table_key("user", "two"), "bob"),
+            ],
+        );
+        assert!(matches!(
+            restore(&conn, RestoreMode::Replace, backup).await,
+            Err(AppError::Conflict(_))
+        ));
+        // The transaction left the database as it was
+        let mut users_dbresp = conn.query("SELECT VALUE id FROM user").await.unwrap();
+        let users: Vec<RecordId> = users_dbresp.take(0).unwrap();
+        assert_eq!(users, vec![ada]);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::State,
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::IntoResponse,
+};
+use chrono::{Local, Months, Utc};
+use surrealdb::{RecordId, Surreal, engine::local::Db};
+use taskflow_types::{ImportItem, ImportQuery, ImportReport, ImportStatus, Task};
+
+use crate::auth_helper::AuthUser;
+use crate::error::{AppError, AppResult};
+use crate::extract::{Json, Query};
+use crate::icalendar::{
+    CalendarExport, CalendarItem, Rejection, build_calendar, read_calendar, uid,
+};
+use crate::schedule_helper::{
+    RoutineDB, get_committed_blocks, get_user_routines, refresh_schedules,
+};
+use crate::tag_helper::get_user_tags;
+use crate::task_helper::{TaskDB, get_user_tasks};
+
+// Everything of the user as one iCalendar file: committed blocks and routines as events,
+// tasks as to-dos
+pub async fn export_calendar(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+) -> AppResult<impl IntoResponse> {
+    let blocks = get_committed_blocks(&conn, &user.id).await?;
+    let routines = get_user_routines(&conn, &user.id).await?;
+    let mut tasks: Vec<Task> = get_user_tasks(&conn, &user.id)
+        .await?
+        .into_iter()
+        .map(Task::from)
+        .collect();
+    tasks.sort_by_key(|task| task.due_by);
+    let tag_names: HashMap<String, String> = get_user_tags(&conn, &user.id, None)
+        .await?
+        .into_iter()
+        .filter_map(|tag| Some((tag.id.clone()?, tag.display_name())))
+        .collect();
+    let export = CalendarExport {
+        blocks: &blocks,
+        routines: &routines,
+        tasks: &tasks,
+        tag_names: &tag_names,
+    };
+    Ok((
+        [
+            (CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (CONTENT_DISPOSITION, "attachment; filename=\"taskflow.ics\""),
+        ],
+        build_calendar(&export, Utc::now()),
+    ))
+}
+
+// UIDs that are already in the account: the ones imported before and the ones the export gives
+// the user's own records
+async fn known_uids(conn: &Surreal<Db>, owner: &RecordId) -> AppResult<HashSet<String>> {
+    let mut known_dbresp = conn
+        .query(
+            "SELECT VALUE ical_uid FROM Tasks WHERE owner = $owner AND ical_uid != NONE;
+             SELECT VALUE ical_uid FROM static_schedule WHERE owner = $owner AND ical_uid != NONE;
+             SELECT VALUE id FROM Tasks WHERE owner = $owner;
+             SELECT VALUE id FROM static_schedule WHERE owner = $owner;
+             SELECT VALUE id FROM day_schedule WHERE owner = $owner;",
+        )
+        .bind(("owner", owner.clone()))
+        .await?;
+    let mut known: HashSet<String> = HashSet::new();
+    for index in 0..2 {
+        let imported: Vec<String> = known_dbresp.take(index)?;
+        known.extend(imported);
+    }
+    for index in 2..5 {
+        let own: Vec<RecordId> = known_dbresp.take(index)?;
+        known.extend(own.iter().map(|id| uid(&id.to_string())));
+    }
+    Ok(known)
+}
+
+// Events become routines and to-dos become tasks. A dry run only reports what would happen, items
+// that can't be expressed are reported with the reason and the rest is imported anyway.
+pub async fn import_calendar(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Query(query): Query<ImportQuery>,
+    body: String,
+) -> AppResult<impl IntoResponse> {
+    let today = Local::now().date_naive();
+    let open_until = query
+        .open_until
+        .unwrap_or_else(|| today.checked_add_months(Months::new(12)).unwrap_or(today));
+    let candidates = read_calendar(&body, today, open_until).map_err(AppError::BadRequest)?;
+    let mut known = known_uids(&conn, &user.id).await?;
+    let mut items = Vec::new();
+    let mut imported = 0;
+    for candidate in candidates {
+        let mut item = ImportItem {
+            uid: candidate.uid,
+            summary: candidate.summary,
+            target: candidate.target,
+            status: ImportStatus::Ready,
+            message: None,
+            warnings: candidate.warnings,
+            id: None,
+        };
+        match candidate.mapped {
+            Err(Rejection::Skipped(reason)) => {
+                item.status = ImportStatus::Skipped;
+                item.message = Some(reason);
+            }
+            Err(Rejection::Failed(reason)) => {
+                item.status = ImportStatus::Failed;
+                item.message = Some(reason);
+            }
+            // Repeats within the file count as duplicates as well
+            Ok(_)
+                if item
+                    .uid
+                    .as_ref()
+                    .is_some_and(|uid| !known.insert(uid.clone())) =>
+            {
+                item.status = ImportStatus::Duplicate;
+                item.message = Some("Already imported".to_string());
+            }
+            Ok(_) if query.dry_run => {}
+            Ok(CalendarItem::Routine(routine)) => {
+                let mut routine_db = RoutineDB::new(routine, user.id.clone());
+                routine_db.id = None;
+                routine_db.ical_uid = item.uid.clone();
+                let created: Option<RoutineDB> =
+                    conn.create("static_schedule").content(routine_db).await?;
+                item.id = created
+                    .and_then(|routine| routine.id)
+                    .map(|id| id.to_string());
+                item.status = ImportStatus::Imported;
+                imported += 1;
+            }
+            Ok(CalendarItem::Task(task)) => {
+                let mut task_db = TaskDB::new(task, user.id.clone());
+                task_db.ical_uid = item.uid.clone();
+                let created: Option<TaskDB> = conn.create("Tasks").content(task_db).await?;
+                item.id = created.map(Task::from).and_then(|task| task.id);
+                item.status = ImportStatus::Imported;
+                imported += 1;
+            }
+        }
+        items.push(item);
+    }
+    if imported > 0 {
+        refresh_schedules(&conn, &user.id).await?;
+    }
+    Ok(Json(ImportReport {
+        dry_run: query.dry_run,
+        items,
+    }))
+}
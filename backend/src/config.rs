@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use serde::Deserialize;
+
+// Where a config file is looked for when none is given explicitly
+const DEFAULT_CONFIG_FILE: &str = "taskflow.toml";
+
+// Settings read once at startup. Later sources win: defaults, TOML file, environment, CLI flags.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bind_addr: String,
+    pub db_path: PathBuf,
+    pub namespace: String,
+    pub database: String,
+    // Bearer token of the /admin endpoints, they are disabled without one
+    pub admin_token: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind_addr: "localhost:3000".to_string(),
+            db_path: PathBuf::from("TaskManagerApp"),
+            namespace: "core".to_string(),
+            database: "main".to_string(),
+            admin_token: None,
+        }
+    }
+}
+
+#[derive(Parser)]
+#[command(about = "TaskFlow backend server")]
+struct Cli {
+    /// TOML config file, defaults to ./taskflow.toml when that exists
+    #[arg(short, long, env = "TASKFLOW_CONFIG")]
+    config: Option<PathBuf>,
+    /// Address to listen on, e.g. 0.0.0.0:3000
+    #[arg(long, env = "TASKFLOW_BIND_ADDR")]
+    bind_addr: Option<String>,
+    /// Directory of the RocksDB database
+    #[arg(long, env = "TASKFLOW_DB_PATH")]
+    db_path: Option<PathBuf>,
+    /// SurrealDB namespace
+    #[arg(long, env = "TASKFLOW_NAMESPACE")]
+    namespace: Option<String>,
+    /// SurrealDB database inside the namespace
+    #[arg(long, env = "TASKFLOW_DATABASE")]
+    database: Option<String>,
+    /// Token that unlocks the backup and restore endpoints
+    #[arg(long, env = "TASKFLOW_ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: Option<String>,
+}
+
+impl Config {
+    pub fn load() -> Result<Self, String> {
+        Self::layered(Cli::parse())
+    }
+
+    fn layered(cli: Cli) -> Result<Self, String> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Self::default(),
+        };
+        // clap already folds the environment variables into these
+        if let Some(bind_addr) = cli.bind_addr {
+            config.bind_addr = bind_addr;
+        }
+        if let Some(db_path) = cli.db_path {
+            config.db_path = db_path;
+        }
+        if let Some(namespace) = cli.namespace {
+            config.namespace = namespace;
+        }
+        if let Some(database) = cli.database {
+            config.database = database;
+        }
+        if let Some(admin_token) = cli.admin_token {
+            config.admin_token = Some(admin_token);
+        }
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read config file {}: {}", path.display(), err))?;
+        toml::from_str(&contents)
+            .map_err(|err| format!("Invalid config file {}: {}", path.display(), err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_sources_win() {
+        let file = std::env::temp_dir().join(format!("taskflow-{}.toml", std::process::id()));
+        std::fs::write(
+            &file,
+            "bind_addr = \"0.0.0.0:8080\"\ndb_path = \"from-file\"\ndatabase = \"from-file\"\n",
+        )
+        .unwrap();
+        // SAFETY: no other test reads or writes the TASKFLOW_ variables
+        unsafe {
+            std::env::set_var("TASKFLOW_DB_PATH", "from-env");
+            std::env::set_var("TASKFLOW_DATABASE", "from-env");
+        }
+        let cli = Cli::try_parse_from([
+            "taskflow",
+            "--config",
+            file.to_str().unwrap(),
+            "--database",
+            "from-cli",
+        ])
+        .unwrap();
+        let config = Config::layered(cli);
+        unsafe {
+            std::env::remove_var("TASKFLOW_DB_PATH");
+            std::env::remove_var("TASKFLOW_DATABASE");
+        }
+        std::fs::remove_file(&file).unwrap();
+        let config = config.unwrap();
+
+        // Set nowhere, by the file, by the file and the environment, and by all three
+        assert_eq!(config.namespace, Config::default().namespace);
+        assert_eq!(config.admin_token, None);
+        assert_eq!(config.bind_addr, "0.0.0.0:8080");
+        assert_eq!(config.db_path, PathBuf::from("from-env"));
+        assert_eq!(config.database, "from-cli");
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::State,
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::IntoResponse,
+};
+use surrealdb::{Surreal, engine::local::Db};
+use taskflow_types::{CsvExportQuery, CsvImportQuery, CsvImportReport, RejectedRow, Task};
+
+use crate::auth_helper::AuthUser;
+use crate::error::{AppError, AppResult};
+use crate::extract::{Json, Query};
+use crate::schedule_helper::refresh_schedules;
+use crate::tag_helper::get_user_tags;
+use crate::task_csv::{read_tasks, write_tasks};
+use crate::task_helper::{TaskDB, get_user_tasks};
+
+// Every task of the user as CSV, `columns` picks which fields and in what order
+pub async fn export_tasks_csv(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Query(query): Query<CsvExportQuery>,
+) -> AppResult<impl IntoResponse> {
+    let columns = query.columns().map_err(AppError::BadRequest)?;
+    let mut tasks: Vec<Task> = get_user_tasks(&conn, &user.id)
+        .await?
+        .into_iter()
+        .map(Task::from)
+        .collect();
+    tasks.sort_by_key(|task| task.due_by);
+    let tag_names: HashMap<String, String> = get_user_tags(&conn, &user.id, None)
+        .await?
+        .into_iter()
+        .filter_map(|tag| Some((tag.id.clone()?, tag.display_name())))
+        .collect();
+    let csv = write_tasks(&tasks, &columns, &tag_names).map_err(AppError::Internal)?;
+    Ok((
+        [
+            (CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (CONTENT_DISPOSITION, "attachment; filename=\"tasks.csv\""),
+        ],
+        csv,
+    ))
+}
+
+// Rows that can be read become tasks, the others are reported with their line and the reason.
+// A preview reads the file without creating anything.
+pub async fn import_tasks_csv(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Query(query): Query<CsvImportQuery>,
+    body: String,
+) -> AppResult<impl IntoResponse> {
+    let read = read_tasks(&body, &query).map_err(AppError::BadRequest)?;
+    let mut tasks = Vec::new();
+    let mut rejected = Vec::new();
+    for (line, task) in read.rows {
+        match task {
+            Ok(task) => tasks.push(task),
+            Err(reason) => rejected.push(RejectedRow { line, reason }),
+        }
+    }
+    if !query.preview && !tasks.is_empty() {
+        let mut created_tasks = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let created: Option<TaskDB> = conn
+                .create("Tasks")
+                .content(TaskDB::new(task, user.id.clone()))
+                .await?;
+            created_tasks.push(created.map(Task::from).ok_or(AppError::Internal(
+                "Created task was not returned".to_string(),
+            ))?);
+        }
+        tasks = created_tasks;
+        refresh_schedules(&conn, &user.id).await?;
+    }
+    Ok(Json(CsvImportReport {
+        preview: query.preview,
+        columns: read.columns,
+        tasks,
+        rejected,
+    }))
+}
@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use chrono::NaiveTime;
+use serde::Deserialize;
+use surrealdb::{RecordId, Surreal, engine::local::Db};
+use taskflow_types::{BlockedTask, Task, TaskDependencies};
+
+use crate::auth_helper::AuthUser;
+use crate::error::{AppError, AppResult};
+use crate::extract::{Json, Path};
+use crate::schedule_helper::refresh_schedules;
+use crate::subtask_helper::{SubtaskLink, descendants_of, get_subtask_links};
+use crate::task_helper::{get_owned_task_db, get_user_tasks, task_record_id};
+
+// One edge of the `depends_on` relation, stored as task->depends_on->prerequisite
+#[derive(Clone, Deserialize)]
+pub struct DependencyLink {
+    pub task: RecordId,
+    pub prerequisite: RecordId,
+}
+
+// Ids of the prerequisites each task is still waiting for
+pub type Prerequisites = HashMap<String, Vec<String>>;
+
+pub async fn get_dependency_links(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+) -> AppResult<Vec<DependencyLink>> {
+    let mut links_dbresp = conn
+        .query("SELECT in AS task, out AS prerequisite FROM depends_on WHERE in.owner = $owner")
+        .bind(("owner", owner.clone()))
+        .await?;
+    let links: Vec<DependencyLink> = links_dbresp.take(0)?;
+    Ok(links)
+}
+
+// What waiting for a task comes down to. Nothing once it is done, and nothing once every
+// subtask of an auto-completing task is done, since `roll_up_completion` finishes it then.
+// A task booked through its subtasks is waited for through the ones still open, like the
+// scheduler books it. Any other task is waited for itself.
+fn open_parts(
+    id: &str,
+    tasks: &HashMap<String, Task>,
+    children: &HashMap<String, Vec<String>>,
+    open: &mut Vec<String>,
+) {
+    let Some(task) = tasks.get(id).filter(|task| !task.is_done) else {
+        return;
+    };
+    let subtasks: Vec<&Task> = children
+        .get(id)
+        .into_iter()
+        .flatten()
+        .filter_map(|child| tasks.get(child))
+        .collect();
+    if subtasks.is_empty() {
+        open.push(id.to_string());
+        return;
+    }
+    let before = open.len();
+    for child in children.get(id).into_iter().flatten() {
+        open_parts(child, tasks, children, open);
+    }
+    let booked_through_subtasks = subtasks
+        .iter()
+        .any(|subtask| subtask.req_time > NaiveTime::MIN);
+    if open.len() == before {
+        if !task.auto_complete {
+            open.push(id.to_string());
+        }
+    } else if !booked_through_subtasks {
+        open.truncate(before);
+        open.push(id.to_string());
+    }
+}
+
+// Prerequisites that are not done yet. Subtasks also wait for whatever their parents wait for.
+pub fn open_prerequisites(
+    tasks: &HashMap<String, Task>,
+    links: &[DependencyLink],
+    subtask_links: &[SubtaskLink],
+) -> Prerequisites {
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    for link in subtask_links {
+        children
+            .entry(link.parent.to_string())
+            .or_default()
+            .push(link.child.to_string());
+    }
+    let mut direct: Prerequisites = HashMap::new();
+    for link in links {
+        let mut waiting_on = Vec::new();
+        open_parts(
+            &link.prerequisite.to_string(),
+            tasks,
+            &children,
+            &mut waiting_on,
+        );
+        if !waiting_on.is_empty() {
+            direct
+                .entry(link.task.to_string())
+                .or_default()
+                .extend(waiting_on);
+        }
+    }
+    let parents: HashMap<String, String> = subtask_links
+        .iter()
+        .map(|link| (link.child.to_string(), link.parent.to_string()))
+        .collect();
+    let mut open = direct.clone();
+    for child in parents.keys() {
+        let mut ancestor = parents.get(child);
+        while let Some(parent) = ancestor {
+            if let Some(waiting_on) = direct.get(parent) {
+                open.entry(child.clone())
+                    .or_default()
+                    .extend(waiting_on.iter().cloned());
+            }
+            ancestor = parents.get(parent);
+        }
+    }
+    open
+}
+
+// Adding task->prerequisite closes a loop when the prerequisite already waits for the task.
+// Like in `open_prerequisites`, a subtask also waits for whatever its parent waits for.
+fn creates_cycle(
+    links: &[DependencyLink],
+    subtask_links: &[SubtaskLink],
+    task: &RecordId,
+    prerequisite: &RecordId,
+) -> bool {
+    let mut prerequisites_of: HashMap<String, Vec<String>> = HashMap::new();
+    for link in links {
+        prerequisites_of
+            .entry(link.task.to_string())
+            .or_default()
+            .push(link.prerequisite.to_string());
+    }
+    for link in subtask_links {
+        prerequisites_of
+            .entry(link.child.to_string())
+            .or_default()
+            .push(link.parent.to_string());
+    }
+    let target = task.to_string();
+    let mut seen = HashSet::new();
+    let mut pending = vec![prerequisite.to_string()];
+    while let Some(current) = pending.pop() {
+        if current == target {
+            return true;
+        }
+        if seen.insert(current.clone()) {
+            pending.extend(
+                prerequisites_of
+                    .get(&current)
+                    .into_iter()
+                    .flatten()
+                    .cloned(),
+            );
+        }
+    }
+    false
+}
+
+async fn get_tasks_by_id(conn: &Surreal<Db>, owner: &RecordId) -> AppResult<HashMap<String, Task>> {
+    let tasks = get_user_tasks(conn, owner)
+        .await?
+        .into_iter()
+        .map(Task::from)
+        .filter_map(|task| Some((task.id.clone()?, task)))
+        .collect();
+    Ok(tasks)
+}
+
+async fn dependencies_of(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    task: &RecordId,
+) -> AppResult<TaskDependencies> {
+    let tasks = get_tasks_by_id(conn, owner).await?;
+    let links = get_dependency_links(conn, owner).await?;
+    let open = open_prerequisites(&tasks, &links, &get_subtask_links(conn, owner).await?);
+    let lookup = |id: &RecordId| tasks.get(&id.to_string()).cloned();
+    Ok(TaskDependencies {
+        prerequisites: links
+            .iter()
+            .filter(|link| &link.task == task)
+            .filter_map(|link| lookup(&link.prerequisite))
+            .collect(),
+        dependents: links
+            .iter()
+            .filter(|link| &link.prerequisite == task)
+            .filter_map(|link| lookup(&link.task))
+            .collect(),
+        blocked: open.contains_key(&task.to_string()),
+    })
+}
+
+pub async fn get_dependencies(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let (record_id, _) = get_owned_task_db(&conn, &id, &user.id).await?;
+    Ok(Json(dependencies_of(&conn, &user.id, &record_id).await?))
+}
+
+// Adding an edge that already exists changes nothing
+pub async fn add_dependency(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path((id, prerequisite_id)): Path<(String, String)>,
+) -> AppResult<impl IntoResponse> {
+    let (task, _) = get_owned_task_db(&conn, &id, &user.id).await?;
+    let (prerequisite, _) = get_owned_task_db(&conn, &prerequisite_id, &user.id).await?;
+    if task == prerequisite {
+        return Err(AppError::BadRequest(
+            "A task cannot depend on itself".to_string(),
+        ));
+    }
+    // Subtasks already inherit their parent's prerequisites, an edge along the tree would
+    // make a task wait for itself
+    if descendants_of(&conn, &user.id, &task)
+        .await?
+        .contains(&prerequisite)
+        || descendants_of(&conn, &user.id, &prerequisite)
+            .await?
+            .contains(&task)
+    {
+        return Err(AppError::BadRequest(
+            "A task cannot depend on its own subtask or parent".to_string(),
+        ));
+    }
+    let links = get_dependency_links(&conn, &user.id).await?;
+    let exists = links
+        .iter()
+        .any(|link| link.task == task && link.prerequisite == prerequisite);
+    if !exists {
+        let subtask_links = get_subtask_links(&conn, &user.id).await?;
+        if creates_cycle(&links, &subtask_links, &task, &prerequisite) {
+            return Err(AppError::Conflict(format!(
+                "Task '{}' already waits for '{}', the dependency would create a cycle",
+                prerequisite_id, id
+            )));
+        }
+        conn.query("RELATE $task->depends_on->$prerequisite")
+            .bind(("task", task.clone()))
+            .bind(("prerequisite", prerequisite))
+            .await?
+            .check()?;
+        refresh_schedules(&conn, &user.id).await?;
+    }
+    Ok(Json(dependencies_of(&conn, &user.id, &task).await?))
+}
+
+pub async fn remove_dependency(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path((id, prerequisite_id)): Path<(String, String)>,
+) -> AppResult<impl IntoResponse> {
+    let (task, _) = get_owned_task_db(&conn, &id, &user.id).await?;
+    conn.query("DELETE depends_on WHERE in = $task AND out = $prerequisite")
+        .bind(("task", task))
+        .bind(("prerequisite", task_record_id(&prerequisite_id)))
+        .await?
+        .check()?;
+    refresh_schedules(&conn, &user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Open tasks that still wait for at least one prerequisite, soonest due first
+pub async fn get_blocked_tasks(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+) -> AppResult<impl IntoResponse> {
+    let tasks = get_tasks_by_id(&conn, &user.id).await?;
+    let links = get_dependency_links(&conn, &user.id).await?;
+    let subtask_links = get_subtask_links(&conn, &user.id).await?;
+    let mut blocked: Vec<BlockedTask> = open_prerequisites(&tasks, &links, &subtask_links)
+        .into_iter()
+        .filter_map(|(id, waiting_on)| {
+            let task = tasks.get(&id).filter(|task| !task.is_done)?.clone();
+            let waiting_on = waiting_on
+                .iter()
+                .filter_map(|id| tasks.get(id).cloned())
+                .collect();
+            Some(BlockedTask { task, waiting_on })
+        })
+        .collect();
+    blocked.sort_by_key(|blocked| blocked.task.due_by);
+    Ok(Json(blocked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn id(key: &str) -> RecordId {
+        RecordId::from_table_key("Tasks", key)
+    }
+
+    fn depends(task: &str, prerequisite: &str) -> DependencyLink {
+        DependencyLink {
+            task: id(task),
+            prerequisite: id(prerequisite),
+        }
+    }
+
+    fn subtask(parent: &str, child: &str) -> SubtaskLink {
+        SubtaskLink {
+            parent: id(parent),
+            child: id(child),
+        }
+    }
+
+    fn task(key: &str, req_hours: u32, is_done: bool, auto_complete: bool) -> (String, Task) {
+        let task = Task {
+            id: Some(id(key).to_string()),
+            name: key.to_string(),
+            description: String::new(),
+            due_by: NaiveDateTime::parse_from_str("2025-03-14 17:00", "%Y-%m-%d %H:%M").unwrap(),
+            imp_lvl: 5,
+            req_time: NaiveTime::from_hms_opt(req_hours, 0, 0).unwrap(),
+            time_alloted: NaiveTime::MIN,
+            is_done,
+            auto_complete,
+            recurrence: None,
+            tags: Vec::new(),
+            reminders: Vec::new(),
+        };
+        (id(key).to_string(), task)
+    }
+
+    fn waiting_on(prerequisites: &Prerequisites, key: &str) -> Vec<String> {
+        let mut ids = prerequisites
+            .get(&id(key).to_string())
+            .cloned()
+            .unwrap_or_default();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn parents_are_waited_for_like_the_roll_up_finishes_them() {
+        // T waits for P, which is booked through its subtasks A and B
+        let links = [depends("t", "p")];
+        let subtask_links = [subtask("p", "a"), subtask("p", "b")];
+        let open = |done_a: bool, done_b: bool, auto_complete: bool| {
+            let tasks = HashMap::from([
+                task("t", 1, false, false),
+                task("p", 0, false, auto_complete),
+                task("a", 1, done_a, false),
+                task("b", 1, done_b, false),
+            ]);
+            open_prerequisites(&tasks, &links, &subtask_links)
+        };
+        // The open subtasks are what T waits for, whether or not P completes itself
+        for auto_complete in [true, false] {
+            assert_eq!(
+                waiting_on(&open(false, false, auto_complete), "t"),
+                vec![id("a").to_string(), id("b").to_string()]
+            );
+            assert_eq!(
+                waiting_on(&open(true, false, auto_complete), "t"),
+                vec![id("b").to_string()]
+            );
+        }
+        // With the last subtask done the roll-up finishes P, a manual P still has to be ticked
+        assert!(waiting_on(&open(true, true, true), "t").is_empty());
+        assert_eq!(
+            waiting_on(&open(true, true, false), "t"),
+            vec![id("p").to_string()]
+        );
+
+        // Checklist items don't take the booking away from their parent
+        let tasks = HashMap::from([
+            task("t", 1, false, false),
+            task("p", 2, false, true),
+            task("a", 0, false, false),
+        ]);
+        let open = open_prerequisites(&tasks, &links, &[subtask("p", "a")]);
+        assert_eq!(waiting_on(&open, "t"), vec![id("p").to_string()]);
+    }
+
+    #[test]
+    fn direct_and_transitive_cycles() {
+        let links = [depends("a", "b"), depends("b", "c")];
+        assert!(creates_cycle(&links, &[], &id("c"), &id("a")));
+        assert!(creates_cycle(&links, &[], &id("b"), &id("a")));
+        assert!(!creates_cycle(&links, &[], &id("a"), &id("c")));
+        assert!(!creates_cycle(&links, &[], &id("d"), &id("a")));
+    }
+
+    #[test]
+    fn subtasks_inherit_their_parents_prerequisites() {
+        // P waits for X, so its subtask C waits for X too and X cannot wait for C
+        let links = [depends("p", "x")];
+        let subtask_links = [subtask("p", "c")];
+        assert!(creates_cycle(&links, &subtask_links, &id("x"), &id("c")));
+        // Further down the tree as well
+        let subtask_links = [subtask("p", "c"), subtask("c", "g")];
+        assert!(creates_cycle(&links, &subtask_links, &id("x"), &id("g")));
+        // The parent doesn't wait for what its subtasks wait for
+        let links = [depends("c", "x")];
+        assert!(!creates_cycle(&links, &subtask_links, &id("x"), &id("p")));
+    }
+}
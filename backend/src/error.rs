@@ -0,0 +1,126 @@
+use axum::{
+    Json,
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::StatusCode,
+    response::IntoResponse,
+    response::Response,
+};
+use serde_json::json;
+
+// Error type shared by every handler, turned into a JSON body with a matching status code
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    Unauthorized(String),
+    NotFound(String),
+    Conflict(String),
+    // A request the Json, Query or Path extractor couldn't read, with the status axum gives it
+    Rejected(StatusCode, String),
+    Database(surrealdb::Error),
+    Internal(String),
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn invalid_id(id: &str) -> Self {
+        AppError::BadRequest(format!("Invalid record id '{}'", id))
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::BadRequest(reason) => write!(f, "{}", reason),
+            AppError::Unauthorized(reason) => write!(f, "{}", reason),
+            AppError::NotFound(what) => write!(f, "{} not found", what),
+            AppError::Conflict(reason) => write!(f, "{}", reason),
+            AppError::Rejected(_, reason) => write!(f, "{}", reason),
+            AppError::Database(err) => write!(f, "Database error: {}", err),
+            AppError::Internal(reason) => write!(f, "Internal error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<surrealdb::Error> for AppError {
+    fn from(err: surrealdb::Error) -> Self {
+        AppError::Database(err)
+    }
+}
+
+impl From<chrono::ParseError> for AppError {
+    fn from(err: chrono::ParseError) -> Self {
+        AppError::BadRequest(format!("Invalid date: {}", err))
+    }
+}
+
+// Whether a statement failed on a THROW of the query itself
+pub fn is_thrown(err: &surrealdb::Error) -> bool {
+    matches!(err, surrealdb::Error::Db(surrealdb::error::Db::Thrown(_)))
+}
+
+// Whether a write failed because a UNIQUE index already holds the value
+pub fn is_index_violation(err: &surrealdb::Error) -> bool {
+    matches!(
+        err,
+        surrealdb::Error::Db(surrealdb::error::Db::IndexExists { .. })
+    )
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        AppError::Rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        AppError::Rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        AppError::Rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Rejected(status, _) => *status,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        if status == StatusCode::INTERNAL_SERVER_ERROR {
+            eprintln!("{}", self);
+        }
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn extractor_rejections_answer_with_json() {
+        let rejection = Json::<serde_json::Value>::from_bytes(b"{\"name\":").unwrap_err();
+        let response = AppError::from(rejection).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(
+            body["error"]
+                .as_str()
+                .is_some_and(|error| !error.is_empty())
+        );
+    }
+}
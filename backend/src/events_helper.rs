@@ -0,0 +1,228 @@
+use std::{collections::BTreeSet, convert::Infallible, time::Duration};
+
+use axum::{
+    extract::State,
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures::{StreamExt, stream};
+use surrealdb::{Action, Notification, QueryStream, RecordId, Surreal, engine::local::Db};
+use taskflow_types::{LiveEvent, Task};
+use tokio::sync::mpsc;
+
+use crate::auth_helper::AuthUser;
+use crate::error::AppResult;
+use crate::notification_helper::NotificationDB;
+use crate::schedule_helper::SchedBlockDB;
+use crate::task_helper::{TaskDB, select_task};
+
+// Events a slow client may fall behind by before the stream waits for it
+const EVENT_BUFFER: usize = 64;
+
+// Replanning rewrites every block of a day one by one, the dates are announced once the
+// rewriting has been quiet for this long
+const SCHEDULE_SETTLE: Duration = Duration::from_millis(300);
+
+// Created and updated tasks are read again, the live record lacks their tags
+async fn task_event(
+    conn: &Surreal<Db>,
+    change: Notification<TaskDB>,
+) -> AppResult<Option<LiveEvent>> {
+    let task = match (change.action, &change.data.id) {
+        (Action::Create | Action::Update, Some(record_id)) => select_task(conn, record_id)
+            .await?
+            .map(Task::from)
+            .unwrap_or_else(|| Task::from(change.data)),
+        _ => Task::from(change.data),
+    };
+    let event = match change.action {
+        Action::Create => LiveEvent::TaskCreated { task },
+        Action::Update => LiveEvent::TaskUpdated { task },
+        Action::Delete => LiveEvent::TaskDeleted {
+            id: task.id.unwrap_or_default(),
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
+
+// The live queries of one event stream, dropping them kills the queries
+struct LiveQueries {
+    tasks: QueryStream<Notification<TaskDB>>,
+    blocks: QueryStream<Notification<SchedBlockDB>>,
+    notifications: QueryStream<Notification<NotificationDB>>,
+}
+
+// Only changes to records of the owner reach the stream
+async fn live_queries(conn: &Surreal<Db>, owner: &RecordId) -> AppResult<LiveQueries> {
+    let mut live_dbresp = conn
+        .query(
+            "LIVE SELECT * FROM Tasks WHERE owner = $owner;
+             LIVE SELECT * FROM day_schedule WHERE owner = $owner;
+             LIVE SELECT * FROM notification WHERE owner = $owner;",
+        )
+        .bind(("owner", owner.clone()))
+        .await?;
+    Ok(LiveQueries {
+        tasks: live_dbresp.stream(0)?,
+        blocks: live_dbresp.stream(1)?,
+        notifications: live_dbresp.stream(2)?,
+    })
+}
+
+fn sse_event(event: &LiveEvent) -> Event {
+    Event::default()
+        .event(event.name())
+        .json_data(event)
+        .unwrap_or_else(|_| Event::default().comment("unserializable event"))
+}
+
+// Turns the live queries into events until the client goes away
+async fn forward_changes(
+    conn: Surreal<Db>,
+    mut live: LiveQueries,
+    events: mpsc::Sender<LiveEvent>,
+) -> AppResult<()> {
+    let mut regenerated = BTreeSet::new();
+    loop {
+        let event = tokio::select! {
+            change = live.tasks.next() => {
+                let Some(change) = change else { return Ok(()) };
+                match task_event(&conn, change?).await? {
+                    Some(event) => event,
+                    None => continue,
+                }
+            }
+            change = live.blocks.next() => {
+                let Some(change) = change else { return Ok(()) };
+                regenerated.insert(change?.data.date);
+                continue;
+            }
+            change = live.notifications.next() => {
+                let Some(change) = change else { return Ok(()) };
+                let change = change?;
+                if !matches!(change.action, Action::Create) {
+                    continue;
+                }
+                LiveEvent::NotificationCreated {
+                    notification: change.data.into(),
+                }
+            }
+            _ = tokio::time::sleep(SCHEDULE_SETTLE), if !regenerated.is_empty() => {
+                LiveEvent::ScheduleRegenerated {
+                    dates: std::mem::take(&mut regenerated).into_iter().collect(),
+                }
+            }
+            _ = events.closed() => return Ok(()),
+        };
+        if events.send(event).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+// Server-sent events for every change to the user's tasks and stored day plans, whoever made
+// it, and for every reminder that fires. Each event is named after its `type` and carries a LiveEvent as JSON.
+pub async fn stream_events(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+) -> AppResult<impl IntoResponse> {
+    let live = live_queries(&conn, &user.id).await?;
+
+    let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+    tokio::spawn(async move {
+        if let Err(err) = forward_changes(conn, live, sender).await {
+            eprintln!("Event stream of {} ended: {}", user.id, err);
+        }
+    });
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        Some((Ok::<_, Infallible>(sse_event(&event)), receiver))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+    use crate::test_db::{test_db, test_user};
+
+    fn task(name: &str) -> Task {
+        Task {
+            id: None,
+            name: name.to_string(),
+            description: String::new(),
+            due_by: NaiveDateTime::parse_from_str("2025-03-14 17:00", "%Y-%m-%d %H:%M").unwrap(),
+            imp_lvl: 5,
+            req_time: NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: None,
+            tags: Vec::new(),
+            reminders: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_carry_only_changes_of_their_owner() {
+        let conn = test_db().await;
+        let ada = test_user(&conn, "ada").await;
+        let bob = test_user(&conn, "bob").await;
+        let live = live_queries(&conn, &bob).await.unwrap();
+        let (sender, mut receiver) = mpsc::channel(EVENT_BUFFER);
+        tokio::spawn(forward_changes(conn.clone(), live, sender));
+
+        for (name, owner) in [("Ada's", &ada), ("Bob's", &bob)] {
+            let _: Option<TaskDB> = conn
+                .create("Tasks")
+                .content(TaskDB::new(task(name), owner.clone()))
+                .await
+                .unwrap();
+        }
+        // Ada's task was created first, so it would have arrived first had it leaked
+        let event = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let LiveEvent::TaskCreated { task } = event else {
+            panic!("Expected a created task, got {:?}", event);
+        };
+        assert_eq!(task.name, "Bob's");
+        tokio::time::sleep(SCHEDULE_SETTLE).await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn events_are_named_after_their_type_and_carry_json() {
+        let events = [
+            LiveEvent::TaskDeleted {
+                id: "Tasks:abc".to_string(),
+            },
+            LiveEvent::ScheduleRegenerated {
+                dates: vec![NaiveDate::from_ymd_opt(2025, 3, 10).unwrap()],
+            },
+        ];
+        let sse = Sse::new(stream::iter(
+            events
+                .iter()
+                .map(|event| Ok::<_, Infallible>(sse_event(event)))
+                .collect::<Vec<_>>(),
+        ));
+        let body = to_bytes(sse.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(body.to_vec()).unwrap(),
+            "event: task_deleted\n\
+             data: {\"type\":\"task_deleted\",\"id\":\"Tasks:abc\"}\n\n\
+             event: schedule_regenerated\n\
+             data: {\"type\":\"schedule_regenerated\",\"dates\":[\"2025-03-10\"]}\n\n"
+        );
+    }
+}
@@ -0,0 +1,1129 @@
+use std::collections::HashMap;
+use std::io::BufReader;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use ical::IcalParser;
+use ical::property::Property;
+use taskflow_types::{BlockKind, ImportTarget, Recurrence, Routine, SchedBlock, Task, Weekday};
+
+use crate::timeline::minute_of_day;
+
+// Content lines may be at most 75 octets long, longer ones continue on lines starting with a space
+const MAX_LINE_OCTETS: usize = 75;
+// Domain part of every UID, the rest is the record id
+const UID_DOMAIN: &str = "taskflow";
+// Most occurrences a counted series may have, its last day is found by walking the series
+const MAX_RRULE_COUNT: u32 = 10_000;
+
+// Writes an RFC 5545 calendar line by line, with CRLF line ends and folding done on the way
+pub struct CalendarWriter {
+    out: String,
+}
+
+impl CalendarWriter {
+    pub fn new(name: &str) -> Self {
+        let mut writer = Self { out: String::new() };
+        writer.begin("VCALENDAR");
+        writer.value("VERSION", "2.0");
+        writer.value("PRODID", "-//TaskFlow//TaskFlow Calendar//EN");
+        writer.value("CALSCALE", "GREGORIAN");
+        writer.value("METHOD", "PUBLISH");
+        writer.text("X-WR-CALNAME", name);
+        writer
+    }
+
+    pub fn begin(&mut self, component: &str) {
+        self.value("BEGIN", component);
+    }
+
+    pub fn end(&mut self, component: &str) {
+        self.value("END", component);
+    }
+
+    // A value that is written as is, like dates or recurrence rules
+    pub fn value(&mut self, name: &str, value: &str) {
+        self.line(&format!("{}:{}", name, value));
+    }
+
+    // Free text, which needs backslashes, separators and line breaks escaped
+    pub fn text(&mut self, name: &str, text: &str) {
+        self.value(name, &escape_text(text));
+    }
+
+    pub fn finish(mut self) -> String {
+        self.end("VCALENDAR");
+        self.out
+    }
+
+    fn line(&mut self, line: &str) {
+        let mut rest = line;
+        let mut limit = MAX_LINE_OCTETS;
+        loop {
+            let mut cut = rest.len().min(limit);
+            while !rest.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            self.out.push_str(&rest[..cut]);
+            self.out.push_str("\r\n");
+            rest = &rest[cut..];
+            if rest.is_empty() {
+                break;
+            }
+            // The leading space of a continuation line counts towards its length
+            self.out.push(' ');
+            limit = MAX_LINE_OCTETS - 1;
+        }
+    }
+}
+
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Times are written without a zone, as floating local times like the ones TaskFlow keeps
+pub fn date_time(value: NaiveDateTime) -> String {
+    value.format("%Y%m%dT%H%M%S").to_string()
+}
+
+fn utc_stamp(value: DateTime<Utc>) -> String {
+    value.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Durations like PT1H30M, the required time of a task is at most a day long
+pub fn duration(time: NaiveTime) -> String {
+    let minutes = minute_of_day(time);
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("PT{}M", minutes),
+        (hours, 0) => format!("PT{}H", hours),
+        (hours, minutes) => format!("PT{}H{}M", hours, minutes),
+    }
+}
+
+pub fn uid(id: &str) -> String {
+    format!("{}@{}", id, UID_DOMAIN)
+}
+
+// Importance 10 is the most urgent in TaskFlow, iCalendar counts PRIORITY 1 as the highest
+fn priority(imp_lvl: u8) -> u8 {
+    11u8.saturating_sub(imp_lvl).clamp(1, 9)
+}
+
+// What goes into an export, tags are looked up by id to name the categories of tasks
+pub struct CalendarExport<'a> {
+    pub blocks: &'a [SchedBlock],
+    pub routines: &'a [Routine],
+    pub tasks: &'a [Task],
+    pub tag_names: &'a HashMap<String, String>,
+}
+
+pub fn build_calendar(export: &CalendarExport, stamp: DateTime<Utc>) -> String {
+    let stamp = utc_stamp(stamp);
+    let mut writer = CalendarWriter::new("TaskFlow");
+    for routine in export.routines {
+        write_routine(&mut writer, routine, &stamp);
+    }
+    // Routines come as recurring events of their own, their blocks are only exported once
+    // the routine is gone, so committed days still show what took place
+    let routine_ids: Vec<&String> = export
+        .routines
+        .iter()
+        .filter_map(|routine| routine.id.as_ref())
+        .collect();
+    for block in export.blocks {
+        let covered = block.kind == BlockKind::Routine
+            && block
+                .source
+                .as_ref()
+                .is_some_and(|source| routine_ids.contains(&source));
+        if !covered {
+            write_block(&mut writer, block, &stamp);
+        }
+    }
+    for task in export.tasks {
+        write_task(&mut writer, task, export.tag_names, &stamp);
+    }
+    writer.finish()
+}
+
+fn write_block(writer: &mut CalendarWriter, block: &SchedBlock, stamp: &str) {
+    let Some(id) = &block.id else {
+        return;
+    };
+    writer.begin("VEVENT");
+    writer.text("UID", &uid(id));
+    writer.value("DTSTAMP", stamp);
+    writer.value("DTSTART", &date_time(block.start));
+    writer.value("DTEND", &date_time(block.end));
+    writer.text("SUMMARY", &block.title);
+    let category = match block.kind {
+        BlockKind::Routine => "Routine",
+        BlockKind::Task => "Task",
+    };
+    writer.value("CATEGORIES", category);
+    // Ties the time slot to the to-do it was booked for
+    if let Some(source) = &block.source {
+        writer.text("RELATED-TO", &uid(source));
+    }
+    writer.end("VEVENT");
+}
+
+// The first occurrence opens the series, the weekdays and the end date make up the rule
+fn write_routine(writer: &mut CalendarWriter, routine: &Routine, stamp: &str) {
+    let Some(id) = &routine.id else {
+        return;
+    };
+    let Some(first) = first_occurrence(routine) else {
+        return;
+    };
+    let by_day: Vec<&str> = Weekday::ALL
+        .into_iter()
+        .filter(|day| routine.weekdays.contains(day))
+        .map(Weekday::rrule_code)
+        .collect();
+    let until = routine
+        .end_date
+        .and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+    writer.begin("VEVENT");
+    writer.text("UID", &uid(id));
+    writer.value("DTSTAMP", stamp);
+    writer.value("DTSTART", &date_time(first.and_time(routine.start_time)));
+    writer.value("DTEND", &date_time(first.and_time(routine.end_time)));
+    writer.value(
+        "RRULE",
+        &format!(
+            "FREQ=WEEKLY;BYDAY={};UNTIL={}",
+            by_day.join(","),
+            date_time(until)
+        ),
+    );
+    writer.text("SUMMARY", &routine.title);
+    writer.value("CATEGORIES", "Routine");
+    writer.end("VEVENT");
+}
+
+fn first_occurrence(routine: &Routine) -> Option<NaiveDate> {
+    (0..7)
+        .map(|offset| routine.start_date + TimeDelta::days(offset))
+        .find(|date| routine.occurs_on(*date))
+}
+
+fn write_task(
+    writer: &mut CalendarWriter,
+    task: &Task,
+    tag_names: &HashMap<String, String>,
+    stamp: &str,
+) {
+    let Some(id) = &task.id else {
+        return;
+    };
+    writer.begin("VTODO");
+    writer.text("UID", &uid(id));
+    writer.value("DTSTAMP", stamp);
+    writer.value("DUE", &date_time(task.due_by));
+    writer.text("SUMMARY", &task.name);
+    if !task.description.is_empty() {
+        writer.text("DESCRIPTION", &task.description);
+    }
+    writer.value("PRIORITY", &priority(task.imp_lvl).to_string());
+    if task.is_done {
+        writer.value("STATUS", "COMPLETED");
+        writer.value("PERCENT-COMPLETE", "100");
+    } else {
+        writer.value("STATUS", "NEEDS-ACTION");
+    }
+    if let Some(recurrence) = &task.recurrence {
+        writer.value("RRULE", &recurrence.to_string());
+    }
+    let categories: Vec<String> = task
+        .tags
+        .iter()
+        .filter_map(|tag| tag_names.get(tag))
+        .map(|name| escape_text(name))
+        .collect();
+    if !categories.is_empty() {
+        writer.value("CATEGORIES", &categories.join(","));
+    }
+    // Calendar apps ignore these, they keep what iCalendar has no place for
+    writer.value("X-TASKFLOW-IMPORTANCE", &task.imp_lvl.to_string());
+    writer.value("X-TASKFLOW-REQUIRED-TIME", &duration(task.req_time));
+    writer.end("VTODO");
+}
+
+pub fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(escaped) => text.push(escaped),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+// One VEVENT or VTODO of an imported file and what it maps to
+pub struct Candidate {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub target: ImportTarget,
+    pub warnings: Vec<String>,
+    pub mapped: Result<CalendarItem, Rejection>,
+}
+
+pub enum CalendarItem {
+    Routine(Routine),
+    Task(Task),
+}
+
+pub enum Rejection {
+    Skipped(String),
+    Failed(String),
+}
+
+// Reads every calendar in the file. Events become routines and to-dos become tasks, `today`
+// decides what is over and `open_until` is where series without an end stop.
+pub fn read_calendar(
+    text: &str,
+    today: NaiveDate,
+    open_until: NaiveDate,
+) -> Result<Vec<Candidate>, String> {
+    let mut candidates = Vec::new();
+    for calendar in IcalParser::new(BufReader::new(text.as_bytes())) {
+        let calendar = calendar.map_err(|err| format!("Not a valid iCalendar file: {}", err))?;
+        for event in &calendar.events {
+            let component = Component(&event.properties);
+            let mut warnings = Vec::new();
+            let mapped = event_to_routine(&component, today, open_until, &mut warnings)
+                .map(CalendarItem::Routine);
+            candidates.push(component.candidate(ImportTarget::Routine, warnings, mapped));
+        }
+        for todo in &calendar.todos {
+            let component = Component(&todo.properties);
+            let mut warnings = Vec::new();
+            let mapped = todo_to_task(&component, &mut warnings).map(CalendarItem::Task);
+            candidates.push(component.candidate(ImportTarget::Task, warnings, mapped));
+        }
+    }
+    Ok(candidates)
+}
+
+struct Component<'a>(&'a [Property]);
+
+impl Component<'_> {
+    fn property(&self, name: &str) -> Option<&Property> {
+        self.0.iter().find(|property| property.name == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.property(name)
+            .and_then(|property| property.value.as_deref())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.value(name).map(unescape_text)
+    }
+
+    fn candidate(
+        &self,
+        target: ImportTarget,
+        warnings: Vec<String>,
+        mapped: Result<CalendarItem, Rejection>,
+    ) -> Candidate {
+        Candidate {
+            uid: self.text("UID"),
+            summary: self
+                .text("SUMMARY")
+                .unwrap_or_else(|| "(no title)".to_string()),
+            target,
+            warnings,
+            mapped,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.value("STATUS")
+            .is_some_and(|status| status.eq_ignore_ascii_case("CANCELLED"))
+    }
+
+    // TaskFlow's own importance wins over the coarser PRIORITY of other apps
+    fn importance(&self) -> u8 {
+        if let Some(imp_lvl) = self
+            .value("X-TASKFLOW-IMPORTANCE")
+            .and_then(|value| value.parse::<u8>().ok())
+            .filter(|imp_lvl| (1..=10).contains(imp_lvl))
+        {
+            return imp_lvl;
+        }
+        match self
+            .value("PRIORITY")
+            .and_then(|value| value.parse::<u8>().ok())
+        {
+            Some(priority @ 1..=9) => 11 - priority,
+            _ => 5,
+        }
+    }
+}
+
+// A DATE or DATE-TIME value, with whether it was a plain date
+fn parse_date_time(
+    property: &Property,
+    warnings: &mut Vec<String>,
+) -> Result<(NaiveDateTime, bool), String> {
+    let value = property.value.as_deref().unwrap_or_default().trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return Ok((date.and_time(NaiveTime::MIN), true));
+    }
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map_err(|_| format!("Invalid {} '{}'", property.name, value))?;
+        return Ok((utc.and_utc().with_timezone(&Local).naive_local(), false));
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .map_err(|_| format!("Invalid {} '{}'", property.name, value))?;
+    if let Some(zone) = param(property, "TZID") {
+        let warning = format!("Times in the {} time zone were read as local times", zone);
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+    Ok((local, false))
+}
+
+fn param<'a>(property: &'a Property, name: &str) -> Option<&'a str> {
+    property
+        .params
+        .iter()
+        .flatten()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, values)| values.first())
+        .map(String::as_str)
+}
+
+// ISO 8601 durations as iCalendar writes them, e.g. PT1H30M, P1D or P2W
+pub fn parse_duration(value: &str) -> Option<TimeDelta> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut rest = value.strip_prefix(['P', 'p'])?;
+    let mut total = TimeDelta::zero();
+    let mut in_time = false;
+    let mut parts = 0;
+    while !rest.is_empty() {
+        if let Some(time) = rest.strip_prefix(['T', 't']) {
+            in_time = true;
+            rest = time;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        let unit = rest[digits..].chars().next()?.to_ascii_uppercase();
+        // Amounts too large for a TimeDelta make the whole duration invalid
+        let part = match (in_time, unit) {
+            (false, 'W') => TimeDelta::try_weeks(amount),
+            (false, 'D') => TimeDelta::try_days(amount),
+            (true, 'H') => TimeDelta::try_hours(amount),
+            (true, 'M') => TimeDelta::try_minutes(amount),
+            (true, 'S') => TimeDelta::try_seconds(amount),
+            _ => return None,
+        }?;
+        total = total.checked_add(&part)?;
+        parts += 1;
+        rest = &rest[digits + 1..];
+    }
+    (parts > 0).then_some(if negative { -total } else { total })
+}
+
+// A recurring event becomes a routine on its weekdays between its first day and the end of the
+// series. A one-off event that is still ahead becomes a routine of a single day.
+fn event_to_routine(
+    event: &Component,
+    today: NaiveDate,
+    open_until: NaiveDate,
+    warnings: &mut Vec<String>,
+) -> Result<Routine, Rejection> {
+    use Rejection::{Failed, Skipped};
+    if event.is_cancelled() {
+        return Err(Skipped("The event is cancelled".to_string()));
+    }
+    // Exported time slots of tasks, the to-dos come along as tasks and get planned again
+    if event.property("RELATED-TO").is_some() {
+        return Err(Skipped(
+            "Time booked for a to-do, the to-do itself is imported as a task".to_string(),
+        ));
+    }
+    if event.property("RECURRENCE-ID").is_some() {
+        return Err(Skipped(
+            "Changes a single occurrence of a series, routines have no exceptions".to_string(),
+        ));
+    }
+    let start = event
+        .property("DTSTART")
+        .ok_or_else(|| Failed("The event has no DTSTART".to_string()))?;
+    let (start, all_day) = parse_date_time(start, warnings).map_err(Failed)?;
+    if all_day {
+        return Err(Failed(
+            "All-day events have no time range a routine could block".to_string(),
+        ));
+    }
+    let end = match (event.property("DTEND"), event.value("DURATION")) {
+        (Some(end), _) => parse_date_time(end, warnings).map_err(Failed)?.0,
+        (None, Some(duration)) => parse_duration(duration)
+            .and_then(|duration| start.checked_add_signed(duration))
+            .ok_or_else(|| Failed(format!("Invalid DURATION '{}'", duration)))?,
+        (None, None) => start,
+    };
+    // An event ending at midnight runs until the end of its day
+    let end = if end.time() == NaiveTime::MIN && start.date().succ_opt() == Some(end.date()) {
+        start
+            .date()
+            .and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap())
+    } else {
+        end
+    };
+    if end.date() != start.date() || end <= start {
+        return Err(Failed(
+            "Routines take place within one day, the event ends on another day or not after it starts"
+                .to_string(),
+        ));
+    }
+
+    let (weekdays, end_date) = match event.value("RRULE") {
+        Some(rule) => weekly_series(rule, start.date(), open_until, warnings).map_err(Failed)?,
+        None => (vec![Weekday::from(start.weekday())], start.date()),
+    };
+    if end_date < today {
+        return Err(Skipped(format!("The event ended on {}", end_date)));
+    }
+    for name in ["EXDATE", "RDATE"] {
+        if event.property(name).is_some() {
+            warnings.push(format!(
+                "{} was dropped, routines take place on every matching day",
+                name
+            ));
+        }
+    }
+    let routine = Routine {
+        id: None,
+        title: event
+            .text("SUMMARY")
+            .unwrap_or_else(|| "(no title)".to_string()),
+        start_date: start.date(),
+        end_date,
+        imp: event.importance(),
+        start_time: start.time(),
+        end_time: end.time(),
+        weekdays,
+        reminders: Vec::new(),
+    };
+    routine.validate().map_err(Failed)?;
+    Ok(routine)
+}
+
+// Weekdays and last day of a DAILY or WEEKLY rule, the only ones a routine can follow
+fn weekly_series(
+    rule: &str,
+    first: NaiveDate,
+    open_until: NaiveDate,
+    warnings: &mut Vec<String>,
+) -> Result<(Vec<Weekday>, NaiveDate), String> {
+    let cannot = |what: &str| format!("The rule '{}' can't be a routine: {}", rule, what);
+    let (mut freq, mut by_day, mut until, mut count) = (None, None, None, None);
+    for part in rule
+        .split(';')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| cannot(&format!("'{}' is not a rule part", part)))?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => freq = Some(value.to_ascii_uppercase()),
+            "INTERVAL" if value.trim() == "1" => {}
+            "INTERVAL" => return Err(cannot("routines repeat every week, not every few")),
+            "BYDAY" => {
+                let days = value
+                    .split(',')
+                    .map(|code| Weekday::from_rrule_code(code.trim()))
+                    .collect::<Option<Vec<Weekday>>>()
+                    .ok_or_else(|| cannot("weekdays like 1MO or -1FR need a monthly rule"))?;
+                by_day = Some(days);
+            }
+            "UNTIL" => {
+                let date = value.get(..8).unwrap_or(value);
+                until = Some(
+                    NaiveDate::parse_from_str(date, "%Y%m%d")
+                        .map_err(|_| cannot(&format!("invalid UNTIL '{}'", value)))?,
+                );
+            }
+            "COUNT" => {
+                let parsed = value
+                    .parse::<u32>()
+                    .map_err(|_| cannot(&format!("invalid COUNT '{}'", value)))?;
+                if parsed > MAX_RRULE_COUNT {
+                    return Err(cannot(&format!(
+                        "COUNT can be at most {}, got {}",
+                        MAX_RRULE_COUNT, parsed
+                    )));
+                }
+                count = Some(parsed);
+            }
+            "WKST" => {}
+            other => return Err(cannot(&format!("{} is not supported", other))),
+        }
+    }
+    let weekdays = match (freq.as_deref(), by_day) {
+        (Some("DAILY"), None) => Weekday::ALL.to_vec(),
+        (Some("DAILY" | "WEEKLY"), Some(days)) => days,
+        (Some("WEEKLY"), None) => vec![Weekday::from(first.weekday())],
+        (Some(other), _) => return Err(cannot(&format!("{} repeats are not supported", other))),
+        (None, _) => return Err(cannot("FREQ is missing")),
+    };
+    let end_date = match (until, count) {
+        (Some(until), _) => until,
+        // The last of `count` occurrences, counting from the first day
+        (None, Some(count)) => {
+            let mut date = first;
+            let mut seen = 0;
+            loop {
+                if weekdays.contains(&Weekday::from(date.weekday())) || date == first {
+                    seen += 1;
+                }
+                if seen >= count {
+                    break date;
+                }
+                date = date
+                    .checked_add_signed(TimeDelta::days(1))
+                    .ok_or_else(|| cannot("the series runs past the end of the calendar"))?;
+            }
+        }
+        (None, None) => {
+            warnings.push(format!(
+                "The series has no end, it was imported until {}",
+                open_until
+            ));
+            open_until
+        }
+    };
+    Ok((weekdays, end_date))
+}
+
+fn todo_to_task(todo: &Component, warnings: &mut Vec<String>) -> Result<Task, Rejection> {
+    use Rejection::{Failed, Skipped};
+    if todo.is_cancelled() {
+        return Err(Skipped("The to-do is cancelled".to_string()));
+    }
+    let due = todo
+        .property("DUE")
+        .ok_or_else(|| Failed("Tasks need a due date, the to-do has no DUE".to_string()))?;
+    let (due_by, all_day) = parse_date_time(due, warnings).map_err(Failed)?;
+    // A task due on a day is due by the end of it
+    let due_by = if all_day {
+        due_by
+            .date()
+            .and_time(NaiveTime::from_hms_opt(23, 59, 0).unwrap())
+    } else {
+        due_by
+    };
+    let required = ["X-TASKFLOW-REQUIRED-TIME", "ESTIMATED-DURATION", "DURATION"]
+        .into_iter()
+        .find_map(|name| todo.value(name));
+    let req_time = match required {
+        Some(value) => parse_duration(value)
+            .filter(|duration| *duration > TimeDelta::zero() && *duration < TimeDelta::days(1))
+            .map(|duration| NaiveTime::MIN + duration)
+            .ok_or_else(|| {
+                Failed(format!(
+                    "The required time '{}' is not between a minute and a day",
+                    value
+                ))
+            })?,
+        None => {
+            warnings.push("No required time given, one hour was assumed".to_string());
+            NaiveTime::from_hms_opt(1, 0, 0).unwrap()
+        }
+    };
+    let recurrence = todo
+        .value("RRULE")
+        .map(|rule| {
+            rule.parse::<Recurrence>()
+                .map_err(|err| Failed(format!("The rule '{}' can't be expressed: {}", rule, err)))
+        })
+        .transpose()?;
+    let is_done = todo.property("COMPLETED").is_some()
+        || todo
+            .value("STATUS")
+            .is_some_and(|status| status.eq_ignore_ascii_case("COMPLETED"));
+    let task = Task {
+        id: None,
+        name: todo
+            .text("SUMMARY")
+            .unwrap_or_else(|| "(no title)".to_string()),
+        description: todo.text("DESCRIPTION").unwrap_or_default(),
+        due_by,
+        imp_lvl: todo.importance(),
+        req_time,
+        time_alloted: NaiveTime::MIN,
+        is_done,
+        auto_complete: false,
+        recurrence,
+        tags: Vec::new(),
+        reminders: Vec::new(),
+    };
+    task.validate().map_err(Failed)?;
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use ical::parser::ical::component::IcalCalendar;
+
+    use super::*;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn parse(text: &str) -> IcalCalendar {
+        let mut calendars = IcalParser::new(BufReader::new(text.as_bytes()));
+        let calendar = calendars
+            .next()
+            .expect("no calendar")
+            .expect("invalid calendar");
+        assert!(calendars.next().is_none());
+        calendar
+    }
+
+    fn value<'a>(properties: &'a [Property], name: &str) -> Option<&'a str> {
+        properties
+            .iter()
+            .find(|property| property.name == name)
+            .and_then(|property| property.value.as_deref())
+    }
+
+    fn sample_task() -> Task {
+        Task {
+            id: Some("Tasks:report".to_string()),
+            name: "Report; draft, review and send".to_string(),
+            description: "Numbers from Q3\nthen a summary for the team that is long enough to \
+                          need folding over several content lines"
+                .to_string(),
+            due_by: at("2025-03-14", "17:00"),
+            imp_lvl: 8,
+            req_time: NaiveTime::from_hms_opt(1, 30, 0).unwrap(),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: Some(Recurrence::weekly([Weekday::Friday])),
+            tags: vec!["tag:work".to_string()],
+            reminders: Vec::new(),
+        }
+    }
+
+    fn sample_routine() -> Routine {
+        Routine {
+            id: Some("static_schedule:gym".to_string()),
+            title: "Gym".to_string(),
+            // A Saturday, so the series starts on the Monday after
+            start_date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2025, 6, 30).unwrap(),
+            imp: 5,
+            start_time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+            weekdays: vec![Weekday::Wednesday, Weekday::Monday],
+            reminders: Vec::new(),
+        }
+    }
+
+    fn sample_blocks() -> Vec<SchedBlock> {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        vec![
+            SchedBlock {
+                id: Some("day_schedule:a".to_string()),
+                date,
+                start: at("2025-03-10", "07:00"),
+                end: at("2025-03-10", "08:00"),
+                title: "Gym".to_string(),
+                kind: BlockKind::Routine,
+                source: Some("static_schedule:gym".to_string()),
+            },
+            SchedBlock {
+                id: Some("day_schedule:b".to_string()),
+                date,
+                start: at("2025-03-10", "09:00"),
+                end: at("2025-03-10", "10:30"),
+                title: "Report; draft, review and send".to_string(),
+                kind: BlockKind::Task,
+                source: Some("Tasks:report".to_string()),
+            },
+        ]
+    }
+
+    fn export() -> String {
+        let tag_names = HashMap::from([("tag:work".to_string(), "#work".to_string())]);
+        let blocks = sample_blocks();
+        let routines = [sample_routine()];
+        let tasks = [sample_task()];
+        let export = CalendarExport {
+            blocks: &blocks,
+            routines: &routines,
+            tasks: &tasks,
+            tag_names: &tag_names,
+        };
+        let stamp = DateTime::from_timestamp(1_741_000_000, 0).unwrap();
+        build_calendar(&export, stamp)
+    }
+
+    #[test]
+    fn lines_end_in_crlf_and_stay_within_75_octets() {
+        let text = export();
+        assert!(text.ends_with("END:VCALENDAR\r\n"));
+        for line in text.split_terminator("\r\n") {
+            assert!(!line.contains('\n'), "bare line feed in {:?}", line);
+            assert!(line.len() <= MAX_LINE_OCTETS, "too long: {:?}", line);
+        }
+        assert!(
+            text.contains("\r\n "),
+            "the description should have been folded"
+        );
+    }
+
+    #[test]
+    fn folding_never_splits_a_character() {
+        let mut writer = CalendarWriter::new("TaskFlow");
+        writer.text("SUMMARY", &"é".repeat(100));
+        let text = writer.finish();
+        let unfolded = text.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("SUMMARY:{}\r\n", "é".repeat(100))));
+    }
+
+    #[test]
+    fn round_trips_through_a_parser() {
+        let calendar = parse(&export());
+        assert_eq!(value(&calendar.properties, "VERSION"), Some("2.0"));
+
+        // The routine block is covered by the routine's own series
+        assert_eq!(calendar.events.len(), 2);
+        let routine = &calendar.events[0].properties;
+        assert_eq!(value(routine, "UID"), Some("static_schedule:gym@taskflow"));
+        assert_eq!(value(routine, "DTSTART"), Some("20250303T070000"));
+        assert_eq!(value(routine, "DTEND"), Some("20250303T080000"));
+        assert_eq!(
+            value(routine, "RRULE"),
+            Some("FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20250630T235959")
+        );
+        let block = &calendar.events[1].properties;
+        assert_eq!(value(block, "DTSTART"), Some("20250310T090000"));
+        assert_eq!(value(block, "DTEND"), Some("20250310T103000"));
+        assert_eq!(
+            value(block, "SUMMARY"),
+            Some("Report\\; draft\\, review and send")
+        );
+        assert_eq!(value(block, "RELATED-TO"), Some("Tasks:report@taskflow"));
+
+        assert_eq!(calendar.todos.len(), 1);
+        let todo = &calendar.todos[0].properties;
+        assert_eq!(value(todo, "UID"), Some("Tasks:report@taskflow"));
+        assert_eq!(value(todo, "DUE"), Some("20250314T170000"));
+        assert_eq!(value(todo, "DTSTAMP"), Some("20250303T110640Z"));
+        assert_eq!(value(todo, "PRIORITY"), Some("3"));
+        assert_eq!(value(todo, "STATUS"), Some("NEEDS-ACTION"));
+        assert_eq!(value(todo, "RRULE"), Some("FREQ=WEEKLY;BYDAY=FR"));
+        assert_eq!(value(todo, "CATEGORIES"), Some("#work"));
+        assert_eq!(value(todo, "X-TASKFLOW-REQUIRED-TIME"), Some("PT1H30M"));
+        assert_eq!(
+            value(todo, "DESCRIPTION"),
+            Some(escape_text(&sample_task().description).as_str())
+        );
+    }
+
+    #[test]
+    fn routines_without_a_matching_day_are_left_out() {
+        let mut routine = sample_routine();
+        routine.end_date = routine.start_date;
+        let mut writer = CalendarWriter::new("TaskFlow");
+        write_routine(&mut writer, &routine, "20250101T000000Z");
+        assert!(parse(&writer.finish()).events.is_empty());
+    }
+
+    fn wrap(components: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//test//EN\r\n{}END:VCALENDAR\r\n",
+            components.replace('\n', "\r\n")
+        )
+    }
+
+    fn event(rule: &str) -> String {
+        wrap(&format!(
+            "BEGIN:VEVENT\nUID:e1\nSUMMARY:Standup\nDTSTART:20250304T091500\n\
+             DTEND:20250304T093000\n{}END:VEVENT\n",
+            rule
+        ))
+    }
+
+    fn read(text: &str) -> Vec<Candidate> {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let open_until = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap();
+        read_calendar(text, today, open_until).unwrap()
+    }
+
+    fn routine_of(candidate: &Candidate) -> &Routine {
+        match &candidate.mapped {
+            Ok(CalendarItem::Routine(routine)) => routine,
+            _ => panic!("{} was not mapped to a routine", candidate.summary),
+        }
+    }
+
+    fn task_of(candidate: &Candidate) -> &Task {
+        match &candidate.mapped {
+            Ok(CalendarItem::Task(task)) => task,
+            _ => panic!("{} was not mapped to a task", candidate.summary),
+        }
+    }
+
+    fn failure(candidate: &Candidate) -> &str {
+        match &candidate.mapped {
+            Err(Rejection::Failed(reason)) => reason,
+            _ => panic!("{} did not fail", candidate.summary),
+        }
+    }
+
+    fn skipped(candidate: &Candidate) -> bool {
+        matches!(candidate.mapped, Err(Rejection::Skipped(_)))
+    }
+
+    #[test]
+    fn exports_import_back() {
+        let candidates = read(&export());
+        assert_eq!(candidates.len(), 3);
+
+        let routine = routine_of(&candidates[0]);
+        assert_eq!(
+            candidates[0].uid.as_deref(),
+            Some("static_schedule:gym@taskflow")
+        );
+        assert_eq!(routine.title, "Gym");
+        assert_eq!(routine.weekdays, vec![Weekday::Monday, Weekday::Wednesday]);
+        assert_eq!(
+            routine.start_date,
+            NaiveDate::from_ymd_opt(2025, 3, 3).unwrap()
+        );
+        assert_eq!(
+            routine.end_date,
+            NaiveDate::from_ymd_opt(2025, 6, 30).unwrap()
+        );
+        assert_eq!(
+            routine.start_time,
+            NaiveTime::from_hms_opt(7, 0, 0).unwrap()
+        );
+        assert_eq!(routine.end_time, NaiveTime::from_hms_opt(8, 0, 0).unwrap());
+
+        // The booked slot of the task is left to the scheduler
+        assert!(skipped(&candidates[1]));
+
+        let original = sample_task();
+        let task = task_of(&candidates[2]);
+        assert_eq!(candidates[2].target, ImportTarget::Task);
+        assert_eq!(task.name, original.name);
+        assert_eq!(task.description, original.description);
+        assert_eq!(task.due_by, original.due_by);
+        assert_eq!(task.imp_lvl, original.imp_lvl);
+        assert_eq!(task.req_time, original.req_time);
+        assert_eq!(task.recurrence, original.recurrence);
+        assert!(candidates[2].warnings.is_empty());
+    }
+
+    #[test]
+    fn events_may_end_at_midnight_but_not_past_the_calendar() {
+        let timed = |times: &str| {
+            read(&wrap(&format!(
+                "BEGIN:VEVENT\nUID:e1\nSUMMARY:Late shift\n{}END:VEVENT\n",
+                times
+            )))
+        };
+        let midnight = timed("DTSTART:20250304T180000\nDTEND:20250305T000000\n");
+        let routine = routine_of(&midnight[0]);
+        assert_eq!(
+            routine.end_time,
+            NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+        );
+        assert_eq!(
+            routine.end_date,
+            NaiveDate::from_ymd_opt(2025, 3, 4).unwrap()
+        );
+        let by_duration = timed("DTSTART:20250304T180000\nDURATION:PT6H\n");
+        assert_eq!(routine_of(&by_duration[0]).end_time, routine.end_time);
+
+        let next_morning = timed("DTSTART:20250304T180000\nDTEND:20250305T010000\n");
+        assert!(failure(&next_morning[0]).contains("within one day"));
+
+        let endless = timed("DTSTART:20250304T180000\nDURATION:P100000000W\n");
+        assert!(failure(&endless[0]).contains("Invalid DURATION"));
+    }
+
+    #[test]
+    fn series_with_too_many_occurrences_fail() {
+        let candidates = read(&event("RRULE:FREQ=WEEKLY;BYDAY=TU;COUNT=4294967295\n"));
+        assert!(failure(&candidates[0]).contains("COUNT can be at most"));
+
+        let candidates = read(&event("RRULE:FREQ=WEEKLY;BYDAY=TU;COUNT=10000\n"));
+        let routine = routine_of(&candidates[0]);
+        assert_eq!(
+            routine.end_date,
+            NaiveDate::from_ymd_opt(2025, 3, 4).unwrap() + TimeDelta::weeks(9_999)
+        );
+    }
+
+    #[test]
+    fn series_end_by_count_until_or_the_import_limit() {
+        let counted = read(&event("RRULE:FREQ=WEEKLY;BYDAY=TU,TH;COUNT=4\n"));
+        let routine = routine_of(&counted[0]);
+        assert_eq!(routine.weekdays, vec![Weekday::Tuesday, Weekday::Thursday]);
+        assert_eq!(
+            routine.end_date,
+            NaiveDate::from_ymd_opt(2025, 3, 13).unwrap()
+        );
+
+        let until = read(&event("RRULE:FREQ=WEEKLY;UNTIL=20250401T000000Z\n"));
+        let routine = routine_of(&until[0]);
+        assert_eq!(routine.weekdays, vec![Weekday::Tuesday]);
+        assert_eq!(
+            routine.end_date,
+            NaiveDate::from_ymd_opt(2025, 4, 1).unwrap()
+        );
+
+        let open = read(&event("RRULE:FREQ=DAILY\n"));
+        let routine = routine_of(&open[0]);
+        assert_eq!(routine.weekdays.len(), 7);
+        assert_eq!(
+            routine.end_date,
+            NaiveDate::from_ymd_opt(2025, 12, 31).unwrap()
+        );
+        assert_eq!(open[0].warnings.len(), 1);
+
+        let single = read(&event(""));
+        let routine = routine_of(&single[0]);
+        assert_eq!(routine.start_date, routine.end_date);
+        assert_eq!(routine.weekdays, vec![Weekday::Tuesday]);
+    }
+
+    #[test]
+    fn rules_a_routine_cannot_follow_fail_with_the_reason() {
+        for rule in [
+            "RRULE:FREQ=MONTHLY;BYDAY=1MO\n",
+            "RRULE:FREQ=WEEKLY;INTERVAL=2\n",
+            "RRULE:FREQ=WEEKLY;BYDAY=-1FR\n",
+            "RRULE:FREQ=WEEKLY;BYSETPOS=1\n",
+        ] {
+            let candidates = read(&event(rule));
+            assert!(
+                failure(&candidates[0]).contains("can't be a routine"),
+                "{}",
+                rule
+            );
+        }
+        let all_day = wrap(
+            "BEGIN:VEVENT\nUID:e2\nSUMMARY:Holiday\nDTSTART;VALUE=DATE:20250310\nEND:VEVENT\n",
+        );
+        assert!(failure(&read(&all_day)[0]).contains("All-day"));
+        let overnight = wrap(
+            "BEGIN:VEVENT\nUID:e3\nSUMMARY:Shift\nDTSTART:20250310T220000\n\
+             DTEND:20250311T060000\nEND:VEVENT\n",
+        );
+        assert!(failure(&read(&overnight)[0]).contains("within one day"));
+    }
+
+    #[test]
+    fn finished_and_cancelled_items_are_skipped() {
+        let past = wrap(
+            "BEGIN:VEVENT\nUID:e4\nSUMMARY:Old\nDTSTART:20250210T090000\n\
+             DTEND:20250210T100000\nEND:VEVENT\n\
+             BEGIN:VTODO\nUID:t1\nSUMMARY:Dropped\nDUE:20250310T090000\n\
+             STATUS:CANCELLED\nEND:VTODO\n",
+        );
+        let candidates = read(&past);
+        assert!(candidates.iter().all(skipped));
+    }
+
+    #[test]
+    fn todos_fill_in_what_they_leave_out() {
+        let todos = wrap(
+            "BEGIN:VTODO\nUID:t2\nSUMMARY:Taxes\\, finally\nDUE;VALUE=DATE:20250415\n\
+             PRIORITY:1\nSTATUS:COMPLETED\nEND:VTODO\n\
+             BEGIN:VTODO\nUID:t3\nSUMMARY:Someday\nEND:VTODO\n\
+             BEGIN:VTODO\nUID:t4\nSUMMARY:Yearly\nDUE:20250415T090000\n\
+             RRULE:FREQ=YEARLY\nEND:VTODO\n",
+        );
+        let candidates = read(&todos);
+        let task = task_of(&candidates[0]);
+        assert_eq!(task.name, "Taxes, finally");
+        assert_eq!(task.due_by, at("2025-04-15", "23:59"));
+        assert_eq!(task.imp_lvl, 10);
+        assert_eq!(task.req_time, NaiveTime::from_hms_opt(1, 0, 0).unwrap());
+        assert!(task.is_done);
+        assert_eq!(candidates[0].warnings.len(), 1);
+        assert!(failure(&candidates[1]).contains("DUE"));
+        assert!(failure(&candidates[2]).contains("can't be expressed"));
+    }
+
+    #[test]
+    fn files_that_are_no_calendar_are_rejected() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        assert!(read_calendar("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\n", today, today).is_err());
+    }
+
+    #[test]
+    fn durations_and_text_read_back() {
+        assert_eq!(parse_duration("PT1H30M"), Some(TimeDelta::minutes(90)));
+        assert_eq!(parse_duration("P1DT2H"), Some(TimeDelta::hours(26)));
+        assert_eq!(parse_duration("P2W"), Some(TimeDelta::weeks(2)));
+        assert_eq!(parse_duration("PT"), None);
+        assert_eq!(parse_duration("1H"), None);
+        for oversized in [
+            "P99999999999W",
+            "P9999999999999D",
+            "PT9999999999999999H",
+            "PT9999999999999999M",
+            "PT9999999999999999S",
+            "P99999999999999999999D",
+            "P15250284452W15250284452W",
+        ] {
+            assert_eq!(parse_duration(oversized), None, "{}", oversized);
+        }
+        assert_eq!(
+            unescape_text(&escape_text("a; b, c\\d\ne")),
+            "a; b, c\\d\ne"
+        );
+    }
+
+    #[test]
+    fn durations_and_priorities() {
+        assert_eq!(
+            duration(NaiveTime::from_hms_opt(0, 45, 0).unwrap()),
+            "PT45M"
+        );
+        assert_eq!(duration(NaiveTime::from_hms_opt(2, 0, 0).unwrap()), "PT2H");
+        assert_eq!(priority(10), 1);
+        assert_eq!(priority(1), 9);
+        assert_eq!(priority(5), 6);
+    }
+}
@@ -1,38 +1,68 @@
-use axum::{
-    Router,
-    routing::{get, post},
-    serve,
-};
+use axum::{Extension, serve};
 use surrealdb::{Surreal, engine::local::RocksDb};
 use tokio::net::TcpListener;
 
+mod config;
+use config::Config;
+
+mod error;
+mod extract;
+mod migrations;
+mod reminders;
+
+mod auth_helper;
+use auth_helper::AdminToken;
+mod backup_helper;
+mod calendar_helper;
+mod csv_helper;
+mod dependency_helper;
+mod events_helper;
+mod notification_helper;
+mod subtask_helper;
+mod tag_helper;
 mod task_helper;
-use task_helper::{add_task, delete_task, get_task, get_task_by_id, mark_done, mark_undone};
+
+mod icalendar;
+mod priority;
+mod task_csv;
+mod timeline;
 
 mod schedule_helper;
-use schedule_helper::{add_schedule, get_schedule_by_day};
 
-// TODO: Login/Signup system
+mod routes;
+
+#[cfg(test)]
+mod test_db;
 
 #[tokio::main]
 async fn main() {
-    let db_conn = Surreal::new::<RocksDb>("TaskManagerApp").await.unwrap();
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
+    let db_conn = Surreal::new::<RocksDb>(config.db_path.as_path())
+        .await
+        .expect("Couldn't open the database");
+    // Handlers share this connection, so the namespace and database only need picking once
+    db_conn
+        .use_ns(&config.namespace)
+        .use_db(&config.database)
+        .await
+        .expect("Couldn't select the namespace and database");
+    migrations::run_migrations(&db_conn)
+        .await
+        .expect("Couldn't migrate the database");
+    reminders::spawn_reminder_job(db_conn.clone());
 
     // router for managing various requests
-    let router = Router::new()
-        .route("/get_tasks", get(get_task))
-        .route("/get_task/{id}", get(get_task_by_id))
-        .route("/add_task", post(add_task))
-        .route("/mark_done", post(mark_done))
-        .route("/mark_undone", post(mark_undone))
-        .route("/delete", post(delete_task))
-        .route("/add_sched", post(add_schedule))
-        .route("/get_schedule/{day_str}", get(get_schedule_by_day))
+    let router = routes::router()
         .with_state(db_conn)
+        .layer(Extension(AdminToken(config.admin_token.clone())))
         .layer(tower_http::cors::CorsLayer::permissive());
 
-    let addr = TcpListener::bind("localhost:3000")
+    let addr = TcpListener::bind(&config.bind_addr)
         .await
-        .expect("Couldn't connect to port 3000");
+        .unwrap_or_else(|err| panic!("Couldn't bind to {}: {}", config.bind_addr, err));
     serve(addr, router).await.unwrap()
 }
@@ -0,0 +1,237 @@
+use surrealdb::{Surreal, engine::local::Db};
+
+use crate::error::{AppError, AppResult};
+
+// One step of the database layout. Applied migrations are never edited, a change to the
+// schema or to stored records is a new migration at the end of the list.
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub statements: &'static str,
+}
+
+pub const MIGRATIONS: [Migration; 7] = [
+    // Indexes behind the task list filters, every lookup starts from the owner, and the unique
+    // indexes that keep the relations between tasks and tags consistent. Databases from before
+    // the migrations already have them.
+    Migration {
+        version: 1,
+        name: "indexes",
+        statements: "
+            DEFINE INDEX IF NOT EXISTS tasks_owner_due ON Tasks FIELDS owner, due_by;
+            DEFINE INDEX IF NOT EXISTS tasks_owner_done ON Tasks FIELDS owner, is_done;
+            DEFINE INDEX IF NOT EXISTS tasks_owner_imp ON Tasks FIELDS owner, imp_lvl;
+            DEFINE INDEX IF NOT EXISTS tasks_owner_name ON Tasks FIELDS owner, name;
+            DEFINE INDEX IF NOT EXISTS subtask_parent ON subtask FIELDS in;
+            DEFINE INDEX IF NOT EXISTS subtask_child ON subtask FIELDS out UNIQUE;
+            DEFINE INDEX IF NOT EXISTS depends_on_edge ON depends_on FIELDS in, out UNIQUE;
+            DEFINE INDEX IF NOT EXISTS depends_on_prerequisite ON depends_on FIELDS out;
+            DEFINE INDEX IF NOT EXISTS tag_owner_kind_name ON tag FIELDS owner, kind, name UNIQUE;
+            DEFINE INDEX IF NOT EXISTS tagged_edge ON tagged FIELDS in, out UNIQUE;
+            DEFINE INDEX IF NOT EXISTS tagged_tag ON tagged FIELDS out;",
+    },
+    // Routines stored their importance as whatever the form held, anything that isn't a
+    // number becomes the default importance of 5
+    Migration {
+        version: 2,
+        name: "routine_imp_number",
+        statements: "
+            UPDATE static_schedule
+                SET imp = IF string::is::numeric(imp)
+                    THEN math::max([1, math::min([10, <int> imp])])
+                    ELSE 5
+                    END
+                WHERE type::is::string(imp);",
+    },
+    // Tables stay schemaless so a new optional field doesn't need a migration, but the fields
+    // every record relies on are typed and checked like the API validates them. Owners are
+    // optional because data from before accounts existed waits for the first sign up.
+    Migration {
+        version: 3,
+        name: "schema",
+        statements: "
+            UPDATE Tasks SET auto_complete = false WHERE auto_complete = NONE;
+
+            DEFINE TABLE OVERWRITE user SCHEMALESS;
+            DEFINE FIELD OVERWRITE username ON user TYPE string;
+            DEFINE FIELD OVERWRITE password_hash ON user TYPE string;
+            DEFINE INDEX IF NOT EXISTS user_username ON user FIELDS username UNIQUE;
+
+            DEFINE TABLE OVERWRITE session SCHEMALESS;
+            DEFINE FIELD OVERWRITE user ON session TYPE record<user>;
+
+            DEFINE TABLE OVERWRITE Tasks SCHEMALESS;
+            DEFINE FIELD OVERWRITE name ON Tasks TYPE string
+                ASSERT string::len(string::trim($value)) > 0;
+            DEFINE FIELD OVERWRITE description ON Tasks TYPE string;
+            DEFINE FIELD OVERWRITE due_by ON Tasks TYPE string;
+            DEFINE FIELD OVERWRITE imp_lvl ON Tasks TYPE int ASSERT $value >= 1 AND $value <= 10;
+            DEFINE FIELD OVERWRITE req_time ON Tasks TYPE string;
+            DEFINE FIELD OVERWRITE time_alloted ON Tasks TYPE string;
+            DEFINE FIELD OVERWRITE is_done ON Tasks TYPE bool;
+            DEFINE FIELD OVERWRITE auto_complete ON Tasks TYPE bool DEFAULT false;
+            DEFINE FIELD OVERWRITE owner ON Tasks TYPE option<record<user>>;
+
+            DEFINE TABLE OVERWRITE static_schedule SCHEMALESS;
+            DEFINE FIELD OVERWRITE title ON static_schedule TYPE string
+                ASSERT string::len(string::trim($value)) > 0;
+            DEFINE FIELD OVERWRITE imp ON static_schedule TYPE int
+                ASSERT $value >= 1 AND $value <= 10;
+            DEFINE FIELD OVERWRITE weekdays ON static_schedule TYPE array<string>;
+            DEFINE FIELD OVERWRITE owner ON static_schedule TYPE option<record<user>>;
+
+            DEFINE TABLE OVERWRITE day_schedule SCHEMALESS;
+            DEFINE FIELD OVERWRITE owner ON day_schedule TYPE option<record<user>>;
+
+            DEFINE TABLE OVERWRITE allocation SCHEMALESS;
+            DEFINE FIELD OVERWRITE task ON allocation TYPE record<Tasks>;
+            DEFINE FIELD OVERWRITE minutes ON allocation TYPE int;
+            DEFINE FIELD OVERWRITE owner ON allocation TYPE option<record<user>>;
+
+            DEFINE TABLE OVERWRITE tag SCHEMALESS;
+            DEFINE FIELD OVERWRITE name ON tag TYPE string
+                ASSERT string::len(string::trim($value)) > 0;
+            DEFINE FIELD OVERWRITE kind ON tag TYPE string
+                ASSERT $value IN ['tag', 'project', 'context'];
+            DEFINE FIELD OVERWRITE owner ON tag TYPE record<user>;
+
+            DEFINE TABLE OVERWRITE subtask TYPE RELATION IN Tasks OUT Tasks SCHEMALESS;
+            DEFINE TABLE OVERWRITE depends_on TYPE RELATION IN Tasks OUT Tasks SCHEMALESS;
+            DEFINE TABLE OVERWRITE tagged TYPE RELATION IN Tasks OUT tag SCHEMALESS;",
+    },
+    // Fired reminders, listed newest first per user
+    Migration {
+        version: 4,
+        name: "notifications",
+        statements: "
+            DEFINE TABLE OVERWRITE notification SCHEMALESS;
+            DEFINE FIELD OVERWRITE kind ON notification TYPE string
+                ASSERT $value IN ['task_due', 'routine_start'];
+            DEFINE FIELD OVERWRITE source ON notification TYPE record<Tasks | static_schedule>;
+            DEFINE FIELD OVERWRITE read ON notification TYPE bool DEFAULT false;
+            DEFINE FIELD OVERWRITE owner ON notification TYPE record<user>;
+            DEFINE INDEX IF NOT EXISTS notification_owner_remind_at
+                ON notification FIELDS owner, remind_at;",
+    },
+    // Day plans of the old hourly scheduler have no start and a list of titles. Nothing reads
+    // them since plans are made of timed blocks, and they couldn't be exported or restored.
+    Migration {
+        version: 5,
+        name: "drop_hourly_plans",
+        statements: "
+            DELETE day_schedule WHERE start = NONE;",
+    },
+    // A day is committed when it has a marker, not when it has blocks, so a day committed
+    // without any blocks stays committed. Days committed before get theirs from their blocks.
+    Migration {
+        version: 6,
+        name: "committed_days",
+        statements: "
+            DEFINE TABLE OVERWRITE committed_day SCHEMALESS;
+            DEFINE FIELD OVERWRITE date ON committed_day TYPE string;
+            DEFINE FIELD OVERWRITE owner ON committed_day TYPE option<record<user>>;
+            DEFINE INDEX IF NOT EXISTS committed_day_owner_date
+                ON committed_day FIELDS owner, date UNIQUE;
+            FOR $block IN (SELECT owner, date, focus, strategy FROM day_schedule WHERE start != NONE) {
+                IF array::len((SELECT VALUE id FROM committed_day
+                        WHERE owner = $block.owner AND date = $block.date)) = 0 {
+                    CREATE committed_day CONTENT {
+                        date: $block.date,
+                        focus: $block.focus,
+                        strategy: $block.strategy,
+                        owner: $block.owner,
+                    };
+                };
+            };",
+    },
+    // Full-text indexes behind the task search, so it doesn't read every task of the owner.
+    // Words are indexed by their beginnings, lowercased and without accents.
+    Migration {
+        version: 7,
+        name: "task_search",
+        statements: "
+            DEFINE ANALYZER IF NOT EXISTS task_text
+                TOKENIZERS blank, class FILTERS lowercase, ascii, edgengram(1, 32);
+            DEFINE INDEX IF NOT EXISTS tasks_name_search
+                ON Tasks FIELDS name SEARCH ANALYZER task_text BM25;
+            DEFINE INDEX IF NOT EXISTS tasks_description_search
+                ON Tasks FIELDS description SEARCH ANALYZER task_text BM25;",
+    },
+];
+
+// Migrations the database hasn't seen yet, in order. A database that a newer build already
+// migrated further is refused instead of being written with an older layout.
+fn pending(applied: &[u32]) -> Result<Vec<&'static Migration>, String> {
+    if let Some(unknown) = applied
+        .iter()
+        .find(|version| !MIGRATIONS.iter().any(|m| m.version == **version))
+    {
+        return Err(format!(
+            "The database has migration {} applied, which this build doesn't know. \
+             It was last opened by a newer version of TaskFlow.",
+            unknown
+        ));
+    }
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .collect())
+}
+
+// Brings the database up to the latest migration. Each one runs in its own transaction together
+// with the record that marks it as applied, so a failed migration is retried on the next start.
+pub async fn run_migrations(conn: &Surreal<Db>) -> AppResult<()> {
+    conn.query(
+        "DEFINE TABLE IF NOT EXISTS migration SCHEMAFULL;
+         DEFINE FIELD IF NOT EXISTS version ON migration TYPE int;
+         DEFINE FIELD IF NOT EXISTS name ON migration TYPE string;
+         DEFINE FIELD IF NOT EXISTS applied_at ON migration TYPE datetime;",
+    )
+    .await?
+    .check()?;
+    let mut applied_dbresp = conn.query("SELECT VALUE version FROM migration").await?;
+    let applied: Vec<u32> = applied_dbresp.take(0)?;
+
+    for migration in pending(&applied).map_err(AppError::Internal)? {
+        conn.query("BEGIN TRANSACTION;")
+            .query(migration.statements)
+            .query(
+                "CREATE type::thing('migration', $version)
+                     SET version = $version, name = $name, applied_at = time::now();",
+            )
+            .query("COMMIT TRANSACTION;")
+            .bind(("version", migration.version))
+            .bind(("name", migration.name))
+            .await?
+            .check()?;
+        println!(
+            "Applied database migration {} ({})",
+            migration.version, migration.name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_count_up_from_one() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, index + 1, "{}", migration.name);
+        }
+    }
+
+    #[test]
+    fn pending_migrations() {
+        assert_eq!(pending(&[]).unwrap().len(), MIGRATIONS.len());
+        let rest: Vec<u32> = pending(&[1])
+            .unwrap()
+            .iter()
+            .map(|migration| migration.version)
+            .collect();
+        assert_eq!(rest, vec![2, 3, 4, 5, 6, 7]);
+        assert!(pending(&[1, 2, 99]).is_err());
+    }
+}
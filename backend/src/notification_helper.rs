@@ -0,0 +1,111 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use surrealdb::{RecordId, Surreal, engine::local::Db};
+use taskflow_types::{Notification, NotificationKind, NotificationQuery};
+
+use crate::auth_helper::AuthUser;
+use crate::error::{AppError, AppResult};
+use crate::extract::{Json, Path, Query};
+
+// Most recent notifications a list returns
+const NOTIFICATION_LIMIT: u32 = 100;
+
+// A fired reminder. The record key is made of the source, the moment and the lead time, so the
+// same reminder can only ever be stored once.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NotificationDB {
+    pub id: Option<RecordId>,
+    pub kind: NotificationKind,
+    pub source: RecordId,
+    pub title: String,
+    pub message: String,
+    pub at: NaiveDateTime,
+    pub remind_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    #[serde(default)]
+    pub read: bool,
+    pub owner: RecordId,
+}
+
+impl From<NotificationDB> for Notification {
+    fn from(db: NotificationDB) -> Self {
+        Self {
+            id: db.id.map(|rid| rid.to_string()),
+            kind: db.kind,
+            source: db.source.to_string(),
+            title: db.title,
+            message: db.message,
+            at: db.at,
+            remind_at: db.remind_at,
+            read: db.read,
+        }
+    }
+}
+
+// Ids may be given with or without the table name, ids of other tables match no notification
+pub fn notification_record_id(id: &str) -> RecordId {
+    let key = id.strip_prefix("notification:").unwrap_or(id);
+    RecordId::from_table_key("notification", key)
+}
+
+// Notifications of other users are reported as missing
+async fn get_owned_notification(
+    conn: &Surreal<Db>,
+    id: &str,
+    owner: &RecordId,
+) -> AppResult<RecordId> {
+    let record_id = notification_record_id(id);
+    let notification: Option<NotificationDB> = conn.select(record_id.clone()).await?;
+    notification
+        .filter(|notification| &notification.owner == owner)
+        .map(|_| record_id)
+        .ok_or(AppError::NotFound(format!("Notification '{}'", id)))
+}
+
+// Newest first, `?unread=true` leaves out the ones already read
+pub async fn list_notifications(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Query(query): Query<NotificationQuery>,
+) -> AppResult<impl IntoResponse> {
+    let unread = if query.unread { "AND read = false" } else { "" };
+    let mut notifications_dbresp = conn
+        .query(format!(
+            "SELECT * FROM notification WHERE owner = $owner {unread}
+             ORDER BY remind_at DESC LIMIT {NOTIFICATION_LIMIT}"
+        ))
+        .bind(("owner", user.id))
+        .await?;
+    let notifications: Vec<NotificationDB> = notifications_dbresp.take(0)?;
+    let notifications: Vec<Notification> =
+        notifications.into_iter().map(Notification::from).collect();
+    Ok(Json(notifications))
+}
+
+pub async fn mark_notification_read(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let record_id = get_owned_notification(&conn, &id, &user.id).await?;
+    let updated: Option<NotificationDB> = conn
+        .update(record_id)
+        .merge(serde_json::json!({ "read": true }))
+        .await?;
+    let notification = updated
+        .map(Notification::from)
+        .ok_or(AppError::NotFound(format!("Notification '{}'", id)))?;
+    Ok(Json(notification))
+}
+
+pub async fn mark_all_notifications_read(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+) -> AppResult<impl IntoResponse> {
+    conn.query("UPDATE notification SET read = true WHERE owner = $owner AND read = false")
+        .bind(("owner", user.id))
+        .await?
+        .check()?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,175 @@
+use std::cmp::Ordering;
+
+use chrono::{NaiveDateTime, TimeDelta};
+use taskflow_types::{StrategyKind, Task};
+
+// Decides which tasks the scheduler books first
+pub trait PrioritizationStrategy: Send + Sync {
+    // Ordering::Less means `a` should be worked on before `b`
+    fn compare(&self, a: &Task, b: &Task, now: NaiveDateTime) -> Ordering;
+
+    fn sort(&self, tasks: &mut [Task], now: NaiveDateTime) {
+        tasks.sort_by(|a, b| self.compare(a, b, now));
+    }
+}
+
+pub fn strategy_for(kind: StrategyKind) -> Box<dyn PrioritizationStrategy> {
+    match kind {
+        StrategyKind::EarliestDeadline => Box::new(EarliestDeadlineFirst),
+        StrategyKind::Weighted => Box::new(WeightedScore::default()),
+        StrategyKind::ShortestRemaining => Box::new(ShortestRemainingTime),
+        StrategyKind::Eisenhower => Box::new(Eisenhower::default()),
+    }
+}
+
+fn remaining_minutes(task: &Task) -> i64 {
+    task.req_time
+        .signed_duration_since(task.time_alloted)
+        .num_minutes()
+        .max(0)
+}
+
+pub struct EarliestDeadlineFirst;
+
+impl PrioritizationStrategy for EarliestDeadlineFirst {
+    fn compare(&self, a: &Task, b: &Task, _now: NaiveDateTime) -> Ordering {
+        a.due_by
+            .cmp(&b.due_by)
+            .then_with(|| b.imp_lvl.cmp(&a.imp_lvl))
+    }
+}
+
+// Blends importance and urgency into one score, both scaled to 0..=1 so the weights mean something
+pub struct WeightedScore {
+    pub importance_weight: f64,
+    pub urgency_weight: f64,
+    // How far away a deadline can be before it stops feeling urgent at all
+    pub horizon: TimeDelta,
+}
+
+impl Default for WeightedScore {
+    fn default() -> Self {
+        Self {
+            importance_weight: 0.6,
+            urgency_weight: 0.4,
+            horizon: TimeDelta::days(14),
+        }
+    }
+}
+
+impl WeightedScore {
+    fn score(&self, task: &Task, now: NaiveDateTime) -> f64 {
+        let importance = task.imp_lvl.min(10) as f64 / 10.0;
+        let time_left = (task.due_by - now).num_minutes().max(0) as f64;
+        let urgency = 1.0 - (time_left / self.horizon.num_minutes() as f64).min(1.0);
+        self.importance_weight * importance + self.urgency_weight * urgency
+    }
+}
+
+impl PrioritizationStrategy for WeightedScore {
+    fn compare(&self, a: &Task, b: &Task, now: NaiveDateTime) -> Ordering {
+        self.score(b, now)
+            .total_cmp(&self.score(a, now))
+            .then_with(|| a.due_by.cmp(&b.due_by))
+    }
+}
+
+pub struct ShortestRemainingTime;
+
+impl PrioritizationStrategy for ShortestRemainingTime {
+    fn compare(&self, a: &Task, b: &Task, _now: NaiveDateTime) -> Ordering {
+        remaining_minutes(a)
+            .cmp(&remaining_minutes(b))
+            .then_with(|| a.due_by.cmp(&b.due_by))
+    }
+}
+
+// Urgent and important first, then important, then urgent, then the rest
+pub struct Eisenhower {
+    pub important_from: u8,
+    pub urgent_within: TimeDelta,
+}
+
+impl Default for Eisenhower {
+    fn default() -> Self {
+        Self {
+            important_from: 7,
+            urgent_within: TimeDelta::days(2),
+        }
+    }
+}
+
+impl Eisenhower {
+    fn quadrant(&self, task: &Task, now: NaiveDateTime) -> u8 {
+        let important = task.imp_lvl >= self.important_from;
+        let urgent = task.due_by - now <= self.urgent_within;
+        match (urgent, important) {
+            (true, true) => 0,
+            (false, true) => 1,
+            (true, false) => 2,
+            (false, false) => 3,
+        }
+    }
+}
+
+impl PrioritizationStrategy for Eisenhower {
+    fn compare(&self, a: &Task, b: &Task, now: NaiveDateTime) -> Ordering {
+        self.quadrant(a, now)
+            .cmp(&self.quadrant(b, now))
+            .then_with(|| a.due_by.cmp(&b.due_by))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::*;
+
+    fn task(
+        name: &str,
+        due_in: TimeDelta,
+        imp_lvl: u8,
+        req_minutes: u32,
+        now: NaiveDateTime,
+    ) -> Task {
+        Task {
+            id: Some(format!("Tasks:{}", name.to_lowercase())),
+            name: name.to_string(),
+            description: String::new(),
+            due_by: now + due_in,
+            imp_lvl,
+            req_time: NaiveTime::from_hms_opt(req_minutes / 60, req_minutes % 60, 0).unwrap(),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: None,
+            tags: Vec::new(),
+            reminders: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn strategies_order_the_same_tasks_differently() {
+        let now = NaiveDateTime::parse_from_str("2025-03-10 08:00", "%Y-%m-%d %H:%M").unwrap();
+        let tasks = vec![
+            task("A", TimeDelta::days(1), 3, 240, now),
+            task("B", TimeDelta::days(10), 9, 60, now),
+            task("C", TimeDelta::days(3), 6, 30, now),
+            task("D", TimeDelta::hours(25), 8, 120, now),
+        ];
+        let expected = [
+            (StrategyKind::EarliestDeadline, ["A", "D", "C", "B"]),
+            (StrategyKind::Weighted, ["D", "C", "B", "A"]),
+            (StrategyKind::ShortestRemaining, ["C", "B", "D", "A"]),
+            (StrategyKind::Eisenhower, ["D", "B", "A", "C"]),
+        ];
+        assert_eq!(expected.len(), StrategyKind::ALL.len());
+        for (kind, order) in expected {
+            let mut sorted = tasks.clone();
+            strategy_for(kind).sort(&mut sorted, now);
+            let names: Vec<&str> = sorted.iter().map(|task| task.name.as_str()).collect();
+            assert_eq!(names, order, "{:?}", kind);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime, TimeDelta};
+use surrealdb::{RecordId, Surreal, engine::local::Db};
+use taskflow_types::{
+    MAX_REMINDER_MINUTES, Notification, NotificationKind, REMINDER_GRACE_MINUTES, Reminder,
+    Routine, Task,
+};
+
+use crate::error::AppResult;
+use crate::notification_helper::NotificationDB;
+use crate::schedule_helper::RoutineDB;
+use crate::task_helper::TaskDB;
+
+// How often the job looks for reminders that came due
+const REMINDER_TICK: Duration = Duration::from_secs(30);
+
+// Checks the reminders of every user in the background for as long as the server runs
+pub fn spawn_reminder_job(conn: Surreal<Db>) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(REMINDER_TICK);
+        loop {
+            tick.tick().await;
+            if let Err(err) = fire_due_reminders(&conn, Local::now().naive_local()).await {
+                eprintln!("Reminder check failed: {}", err);
+            }
+        }
+    });
+}
+
+// Same source, moment and lead time always give the same key
+fn notification_key(source: &RecordId, at: NaiveDateTime, reminder: Reminder) -> RecordId {
+    let key: String = source
+        .key()
+        .to_string()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    RecordId::from_table_key(
+        "notification",
+        format!(
+            "{}_{}_{}_{}",
+            source.table(),
+            key,
+            at.format("%Y%m%d%H%M"),
+            reminder.minutes_before
+        ),
+    )
+}
+
+fn notification(
+    source: &RecordId,
+    kind: NotificationKind,
+    title: String,
+    at: NaiveDateTime,
+    reminder: Reminder,
+    owner: RecordId,
+    now: NaiveDateTime,
+) -> NotificationDB {
+    NotificationDB {
+        id: Some(notification_key(source, at, reminder)),
+        kind,
+        source: source.clone(),
+        title,
+        message: Notification::describe(kind, reminder),
+        at,
+        remind_at: reminder.remind_at(at),
+        created_at: now,
+        read: false,
+        owner,
+    }
+}
+
+// Starts of the routine whose reminders are due now
+fn routine_reminders_due(routine: &Routine, now: NaiveDateTime) -> Vec<(NaiveDateTime, Reminder)> {
+    let from = now - TimeDelta::minutes(REMINDER_GRACE_MINUTES);
+    let to = now + TimeDelta::minutes(MAX_REMINDER_MINUTES.into());
+    routine
+        .starts_between(from, to)
+        .into_iter()
+        .flat_map(|start| {
+            routine
+                .reminders
+                .iter()
+                .map(move |reminder| (start, *reminder))
+        })
+        .filter(|(start, reminder)| reminder.is_due(*start, now))
+        .collect()
+}
+
+// Stores a notification for every reminder that is due. Ones that fired before, also before a
+// restart, already have their record and are skipped by the insert.
+pub async fn fire_due_reminders(conn: &Surreal<Db>, now: NaiveDateTime) -> AppResult<()> {
+    let mut reminders_dbresp = conn
+        .query(
+            "SELECT * FROM Tasks
+                 WHERE owner != NONE AND is_done = false AND array::len(reminders ?? []) > 0;
+             SELECT * FROM static_schedule
+                 WHERE owner != NONE AND end_date >= $today AND array::len(reminders ?? []) > 0;",
+        )
+        .bind(("today", now.date()))
+        .await?;
+    let tasks: Vec<TaskDB> = reminders_dbresp.take(0)?;
+    let routines: Vec<RoutineDB> = reminders_dbresp.take(1)?;
+
+    let mut due = Vec::new();
+    // Records nobody owns yet have nobody to remind
+    for task_db in tasks {
+        let (Some(source), Some(owner)) = (task_db.id.clone(), task_db.owner.clone()) else {
+            continue;
+        };
+        let task = Task::from(task_db);
+        for reminder in &task.reminders {
+            if reminder.is_due(task.due_by, now) {
+                due.push(notification(
+                    &source,
+                    NotificationKind::TaskDue,
+                    task.name.clone(),
+                    task.due_by,
+                    *reminder,
+                    owner.clone(),
+                    now,
+                ));
+            }
+        }
+    }
+    for routine_db in routines {
+        let (Some(source), Some(owner)) = (routine_db.id.clone(), routine_db.owner.clone()) else {
+            continue;
+        };
+        let routine = Routine::from(routine_db);
+        for (start, reminder) in routine_reminders_due(&routine, now) {
+            due.push(notification(
+                &source,
+                NotificationKind::RoutineStart,
+                routine.title.clone(),
+                start,
+                reminder,
+                owner.clone(),
+                now,
+            ));
+        }
+    }
+
+    if !due.is_empty() {
+        conn.query("INSERT IGNORE INTO notification $due")
+            .bind(("due", due))
+            .await?
+            .check()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+    use taskflow_types::Weekday;
+
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn routine_reminders_fire_before_each_start() {
+        let standup = Routine {
+            id: None,
+            title: "Standup".to_string(),
+            start_date: NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2025, 3, 31).unwrap(),
+            imp: 5,
+            start_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(9, 15, 0).unwrap(),
+            weekdays: vec![Weekday::Tuesday],
+            reminders: vec![Reminder::AT_START, Reminder::before(24 * 60)],
+        };
+        // The day before, one day ahead
+        assert_eq!(
+            routine_reminders_due(&standup, at("2025-03-03 09:00")),
+            vec![(at("2025-03-04 09:00"), Reminder::before(24 * 60))]
+        );
+        // A few minutes late, the day itself
+        assert_eq!(
+            routine_reminders_due(&standup, at("2025-03-04 09:05")),
+            vec![(at("2025-03-04 09:00"), Reminder::AT_START)]
+        );
+        assert!(routine_reminders_due(&standup, at("2025-03-04 12:00")).is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+use axum::{
+    Router,
+    extract::DefaultBodyLimit,
+    http::{HeaderValue, header::LINK},
+    middleware,
+    response::Response,
+    routing::{get, patch, post, put},
+};
+use surrealdb::{Surreal, engine::local::Db};
+
+use crate::auth_helper::{get_me, login, logout, signup};
+use crate::backup_helper::{MAX_BACKUP_BYTES, export_backup, import_backup};
+use crate::calendar_helper::{export_calendar, import_calendar};
+use crate::csv_helper::{export_tasks_csv, import_tasks_csv};
+use crate::dependency_helper::{
+    add_dependency, get_blocked_tasks, get_dependencies, remove_dependency,
+};
+use crate::events_helper::stream_events;
+use crate::notification_helper::{
+    list_notifications, mark_all_notifications_read, mark_notification_read,
+};
+use crate::schedule_helper::{
+    add_schedule, commit_schedule, delete_routine, get_routine_by_id, get_routines,
+    get_schedule_by_day, legacy_add_schedule, legacy_delete_routine, rollback_schedule,
+    update_routine,
+};
+use crate::subtask_helper::{add_subtask, get_subtask_tree};
+use crate::tag_helper::{
+    add_tag, delete_tag, get_tag, get_task_tags, list_tags, set_task_tags, update_tag,
+};
+use crate::task_helper::{
+    add_task, delete_task, edit_task, get_task, get_task_by_id, legacy_add_task,
+    legacy_delete_task, legacy_mark_done, legacy_mark_undone, list_tasks,
+};
+
+pub const API_PREFIX: &str = "/api/v1";
+
+pub fn router() -> Router<Surreal<Db>> {
+    Router::new()
+        .nest(API_PREFIX, api_v1())
+        .merge(legacy().layer(middleware::map_response(mark_deprecated)))
+}
+
+fn api_v1() -> Router<Surreal<Db>> {
+    Router::new()
+        .route("/auth/signup", post(signup))
+        .route("/auth/login", post(login))
+        .route("/auth/logout", post(logout))
+        .route("/me", get(get_me))
+        .route("/events", get(stream_events))
+        .route("/notifications", get(list_notifications))
+        .route("/notifications/read", post(mark_all_notifications_read))
+        .route("/notifications/{id}/read", post(mark_notification_read))
+        .route("/tasks", get(list_tasks).post(add_task))
+        .route("/tasks/blocked", get(get_blocked_tasks))
+        .route(
+            "/tasks/{id}",
+            get(get_task_by_id).patch(edit_task).delete(delete_task),
+        )
+        .route(
+            "/tasks/{id}/subtasks",
+            get(get_subtask_tree).post(add_subtask),
+        )
+        .route("/tasks/{id}/dependencies", get(get_dependencies))
+        .route(
+            "/tasks/{id}/dependencies/{prerequisite_id}",
+            put(add_dependency).delete(remove_dependency),
+        )
+        .route("/tasks/{id}/tags", get(get_task_tags).put(set_task_tags))
+        .route("/tags", get(list_tags).post(add_tag))
+        .route(
+            "/tags/{id}",
+            get(get_tag).put(update_tag).delete(delete_tag),
+        )
+        .route("/routines", get(get_routines).post(add_schedule))
+        .route(
+            "/routines/{id}",
+            get(get_routine_by_id)
+                .put(update_routine)
+                .delete(delete_routine),
+        )
+        .route(
+            "/schedules/{day_str}",
+            get(get_schedule_by_day)
+                .post(commit_schedule)
+                .delete(rollback_schedule),
+        )
+        .route("/export/calendar.ics", get(export_calendar))
+        .route("/import/calendar", post(import_calendar))
+        .route("/export/tasks.csv", get(export_tasks_csv))
+        .route("/import/tasks", post(import_tasks_csv))
+        .route("/admin/export", get(export_backup))
+        .route(
+            "/admin/import",
+            post(import_backup).layer(DefaultBodyLimit::max(MAX_BACKUP_BYTES)),
+        )
+}
+
+// The unversioned RPC-style paths, kept as aliases until existing clients have moved to /api/v1
+fn legacy() -> Router<Surreal<Db>> {
+    Router::new()
+        .route("/signup", post(signup))
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        .route("/me", get(get_me))
+        .route("/get_tasks", get(get_task))
+        .route("/get_task/{id}", get(get_task_by_id))
+        .route("/add_task", post(legacy_add_task))
+        .route("/mark_done", post(legacy_mark_done))
+        .route("/mark_undone", post(legacy_mark_undone))
+        .route("/delete", post(legacy_delete_task))
+        .route("/tasks/{id}", patch(edit_task))
+        .route("/add_sched", post(legacy_add_schedule))
+        .route("/routines", get(get_routines))
+        .route(
+            "/routines/{id}",
+            get(get_routine_by_id)
+                .put(update_routine)
+                .delete(legacy_delete_routine),
+        )
+        .route("/get_schedule/{day_str}", get(get_schedule_by_day))
+        .route(
+            "/schedules/{day_str}",
+            post(commit_schedule).delete(rollback_schedule),
+        )
+}
+
+// Marks responses of the old paths as deprecated and points clients at the versioned API
+async fn mark_deprecated(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    headers.insert(
+        LINK,
+        HeaderValue::from_static("</api/v1>; rel=\"successor-version\""),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{Body, to_bytes},
+        http::{HeaderMap, Request, StatusCode, header::AUTHORIZATION},
+    };
+    use taskflow_types::{AuthSession, Credentials};
+    use tower::ServiceExt;
+
+    use crate::test_db::test_db;
+
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await.unwrap();
+        (parts.status, parts.headers, body.to_vec())
+    }
+
+    fn get(path: &str, token: Option<&str>) -> Request<Body> {
+        let mut request = Request::get(path);
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn legacy_paths_answer_like_their_versioned_routes() {
+        let app = router().with_state(test_db().await);
+        let credentials = Credentials {
+            username: "ada".to_string(),
+            password: "correct horse".to_string(),
+        };
+        let signup = Request::post("/api/v1/auth/signup")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::to_vec(&credentials).unwrap()))
+            .unwrap();
+        let (status, _, body) = send(&app, signup).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let session: AuthSession = serde_json::from_slice(&body).unwrap();
+        let token = Some(session.token.as_str());
+
+        let pairs = [
+            ("/me", "/api/v1/me", token),
+            ("/me", "/api/v1/me", None),
+            ("/routines", "/api/v1/routines", token),
+            (
+                "/get_task/Tasks:missing",
+                "/api/v1/tasks/Tasks:missing",
+                token,
+            ),
+        ];
+        for (legacy, versioned, token) in pairs {
+            let (old_status, old_headers, old_body) = send(&app, get(legacy, token)).await;
+            let (new_status, new_headers, new_body) = send(&app, get(versioned, token)).await;
+            assert_eq!(old_status, new_status, "{}", legacy);
+            assert_eq!(old_body, new_body, "{}", legacy);
+            assert_eq!(
+                old_headers.get("deprecation").unwrap(),
+                "true",
+                "{}",
+                legacy
+            );
+            assert_eq!(
+                old_headers.get(LINK).unwrap(),
+                "</api/v1>; rel=\"successor-version\""
+            );
+            assert!(new_headers.get("deprecation").is_none());
+            assert!(new_headers.get(LINK).is_none());
+        }
+    }
+}
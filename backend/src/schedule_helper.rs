@@ -1,52 +1,130 @@
-use axum::{Json, extract::Path, extract::State, http::StatusCode, response::IntoResponse};
-use chrono::{Local, NaiveDate, NaiveTime, TimeDelta, Timelike};
+use std::collections::{HashMap, HashSet};
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
 use serde::{Deserialize, Serialize};
 use surrealdb::{RecordId, Surreal, engine::local::Db};
+use taskflow_types::{
+    BlockKind, DayPlan, Reminder, Routine, SchedBlock, ScheduleQuery, StrategyKind, Task, Weekday,
+};
 
-use crate::task_helper::{Task, TaskDB};
+use crate::auth_helper::AuthUser;
+use crate::dependency_helper::{Prerequisites, get_dependency_links, open_prerequisites};
+use crate::error::{AppError, AppResult, is_index_violation, is_thrown};
+use crate::extract::{Json, Path, Query};
+use crate::priority::{PrioritizationStrategy, strategy_for};
+use crate::subtask_helper::get_subtask_links;
+use crate::tag_helper::resolve_focus;
+use crate::task_helper::{TaskDB, get_user_tasks};
+use crate::timeline::{MINUTES_IN_DAY, Timeline, at_minute, minute_of_day};
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct Routine {
-    pub id: Option<String>,
+pub struct RoutineDB {
+    pub id: Option<RecordId>,
     pub title: String,
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
-    pub imp: String,
+    pub imp: u8,
     pub start_time: NaiveTime,
     pub end_time: NaiveTime,
-    pub weekdays: Vec<String>,
+    pub weekdays: Vec<Weekday>,
+    #[serde(default)]
+    pub reminders: Vec<Reminder>,
+    // Records from before accounts existed have none until the first sign up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<RecordId>,
+    // UID of the event the routine was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ical_uid: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct RoutineDB {
+pub struct SchedBlockDB {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
+    pub date: NaiveDate,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
     pub title: String,
-    pub start_date: NaiveDate,
-    pub end_date: NaiveDate,
-    pub imp: String,
-    pub start_time: NaiveTime,
-    pub end_time: NaiveTime,
-    pub weekdays: Vec<String>,
+    pub kind: BlockKind,
+    pub source: Option<RecordId>,
+    // Records from before accounts existed have none until the first sign up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<RecordId>,
+    // Copies of the day's CommittedDayDB fields. Older builds kept them only here, and the
+    // markers of their days are derived from them.
+    #[serde(default)]
+    pub focus: Vec<RecordId>,
+    #[serde(default)]
+    pub strategy: StrategyKind,
 }
 
+// Marks a day as committed, also when its plan has no blocks at all
 #[derive(Clone, Serialize, Deserialize)]
-pub struct SchedItemDB {
+pub struct CommittedDayDB {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
     pub date: NaiveDate,
-    pub represented_hour_start: u8,
-    pub has_time: bool,
-    pub time_left_mins: u8,
-    pub title: Vec<String>,
+    // Tags the day was limited to when it was committed
+    #[serde(default)]
+    pub focus: Vec<RecordId>,
+    // Strategy the day was committed with, replanning the day keeps using it
+    #[serde(default)]
+    pub strategy: StrategyKind,
+    // Records from before accounts existed have none until the first sign up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<RecordId>,
+}
+
+// Creates the markers of committed days that only have blocks, as older builds left them
+pub const BACKFILL_COMMITTED_DAYS: &str = "
+    FOR $block IN (SELECT owner, date, focus, strategy FROM day_schedule WHERE start != NONE) {
+        IF array::len((SELECT VALUE id FROM committed_day
+                WHERE owner = $block.owner AND date = $block.date)) = 0 {
+            CREATE committed_day CONTENT {
+                date: $block.date,
+                focus: $block.focus,
+                strategy: $block.strategy,
+                owner: $block.owner,
+            };
+        };
+    };";
+
+fn new_block(
+    date: NaiveDate,
+    (start, end): (u32, u32),
+    title: String,
+    kind: BlockKind,
+    source: Option<String>,
+) -> SchedBlock {
+    SchedBlock {
+        id: None,
+        date,
+        start: at_minute(date, start),
+        end: at_minute(date, end),
+        title,
+        kind,
+        source,
+    }
 }
 
+// Minutes of a task that a committed day plan has booked
 #[derive(Clone, Serialize, Deserialize)]
-pub struct SchedItem {
-    pub id: Option<String>,
+pub struct AllocationDB {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<RecordId>,
     pub date: NaiveDate,
-    pub represented_hour_start: u8,
-    pub has_time: bool,
-    pub time_left_mins: u8,
-    pub title: Vec<String>,
+    pub task: RecordId,
+    pub minutes: i64,
+    // Records from before accounts existed have none until the first sign up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<RecordId>,
+}
+
+#[derive(Deserialize)]
+struct AllocationTotal {
+    task: RecordId,
+    minutes: i64,
 }
 
 // Conversions
@@ -61,12 +139,13 @@ impl From<RoutineDB> for Routine {
             start_time: db_item.start_time,
             end_time: db_item.end_time,
             weekdays: db_item.weekdays,
+            reminders: db_item.reminders,
         }
     }
 }
 
-impl From<Routine> for RoutineDB {
-    fn from(api: Routine) -> Self {
+impl RoutineDB {
+    pub fn new(api: Routine, owner: RecordId) -> Self {
         Self {
             id: api.id.and_then(|s| s.parse().ok()),
             title: api.title,
@@ -76,174 +155,1171 @@ impl From<Routine> for RoutineDB {
             start_time: api.start_time,
             end_time: api.end_time,
             weekdays: api.weekdays,
+            reminders: api.reminders,
+            owner: Some(owner),
+            ical_uid: None,
         }
     }
 }
 
-impl From<SchedItem> for SchedItemDB {
-    fn from(scheditem: SchedItem) -> Self {
+impl SchedBlockDB {
+    pub fn new(block: SchedBlock, owner: RecordId) -> Self {
         Self {
-            id: scheditem.id.and_then(|s| s.parse().ok()),
-            date: scheditem.date,
-            represented_hour_start: scheditem.represented_hour_start,
-            has_time: scheditem.has_time,
-            time_left_mins: scheditem.time_left_mins,
-            title: scheditem.title,
+            id: block.id.and_then(|s| s.parse().ok()),
+            date: block.date,
+            start: block.start,
+            end: block.end,
+            title: block.title,
+            kind: block.kind,
+            source: block.source.and_then(|s| s.parse().ok()),
+            owner: Some(owner),
+            focus: Vec::new(),
+            strategy: StrategyKind::default(),
         }
     }
 }
 
-impl From<SchedItemDB> for SchedItem {
-    fn from(value: SchedItemDB) -> Self {
+impl From<SchedBlockDB> for SchedBlock {
+    fn from(value: SchedBlockDB) -> Self {
         Self {
             id: value.id.map(|s| s.to_string()),
             date: value.date,
-            represented_hour_start: value.represented_hour_start,
-            has_time: value.has_time,
-            time_left_mins: value.time_left_mins,
+            start: value.start,
+            end: value.end,
             title: value.title,
+            kind: value.kind,
+            source: value.source.map(|s| s.to_string()),
         }
     }
 }
 
+// Ids may be given with or without the table name, ids of other tables match no routine
+fn routine_record_id(id: &str) -> RecordId {
+    let key = id.strip_prefix("static_schedule:").unwrap_or(id);
+    RecordId::from_table_key("static_schedule", key)
+}
+
+// Routines of other users are reported as missing
+async fn get_owned_routine(conn: &Surreal<Db>, id: &str, owner: &RecordId) -> AppResult<RoutineDB> {
+    let routine_db: Option<RoutineDB> = conn.select(routine_record_id(id)).await?;
+    routine_db
+        .filter(|routine| routine.owner.as_ref() == Some(owner))
+        .ok_or(AppError::NotFound(format!("Routine '{}'", id)))
+}
+
+// A new routine takes its time on committed days too, they are replanned around it
+async fn create_routine(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    new_routine: Routine,
+) -> AppResult<Routine> {
+    new_routine.validate().map_err(AppError::BadRequest)?;
+    let db_scheditem = RoutineDB::new(new_routine, owner.clone());
+    let created: Option<RoutineDB> = conn.create("static_schedule").content(db_scheditem).await?;
+    let routine = created.map(Routine::from).ok_or(AppError::Internal(
+        "Created routine was not returned".to_string(),
+    ))?;
+    refresh_schedules(conn, owner).await?;
+    Ok(routine)
+}
+
 pub async fn add_schedule(
     State(conn): State<Surreal<Db>>,
+    user: AuthUser,
     Json(new_routine): Json<Routine>,
-) -> impl IntoResponse {
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let db_scheditem = RoutineDB::from(new_routine);
-    let _: Option<RoutineDB> = conn
-        .create("static_schedule")
-        .content(db_scheditem)
-        .await
-        .unwrap();
-    StatusCode::CREATED
+) -> AppResult<impl IntoResponse> {
+    let routine = create_routine(&conn, &user.id, new_routine).await?;
+    Ok((StatusCode::CREATED, Json(routine)))
+}
+
+pub async fn get_user_routines(conn: &Surreal<Db>, owner: &RecordId) -> AppResult<Vec<Routine>> {
+    let mut routines_dbresp = conn
+        .query("SELECT * FROM static_schedule WHERE owner = $owner")
+        .bind(("owner", owner.clone()))
+        .await?;
+    let values: Vec<RoutineDB> = routines_dbresp.take(0)?;
+    Ok(values.into_iter().map(Routine::from).collect())
 }
 
-// Make day_tasks table dynamically updateable such that if user adds new task and there is extra space left in the table then it is added for the same day instead of keeping the schedule fixed (Should work only for future and no the past)
+pub async fn get_routines(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+) -> AppResult<impl IntoResponse> {
+    Ok(Json(get_user_routines(&conn, &user.id).await?))
+}
+
+pub async fn get_routine_by_id(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let routine = get_owned_routine(&conn, &id, &user.id).await?;
+    Ok(Json(Routine::from(routine)))
+}
+
+pub async fn update_routine(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(routine): Json<Routine>,
+) -> AppResult<impl IntoResponse> {
+    routine.validate().map_err(AppError::BadRequest)?;
+    let before = get_owned_routine(&conn, &id, &user.id).await?;
+    let record_id = routine_record_id(&id);
+    let mut routine_db = RoutineDB::new(routine, user.id.clone());
+    routine_db.id = Some(record_id.clone());
+    // Editing doesn't make an imported routine importable again
+    routine_db.ical_uid = before.ical_uid;
+    let updated: Option<RoutineDB> = conn.update(record_id).content(routine_db).await?;
+    let routine = updated
+        .map(Routine::from)
+        .ok_or(AppError::NotFound(format!("Routine '{}'", id)))?;
+    refresh_schedules(&conn, &user.id).await?;
+    Ok(Json(routine))
+}
+
+pub async fn delete_routine(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    remove_routine(&conn, &user.id, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn remove_routine(conn: &Surreal<Db>, owner: &RecordId, id: &str) -> AppResult<()> {
+    get_owned_routine(conn, id, owner).await?;
+    let _: Option<RoutineDB> = conn.delete(routine_record_id(id)).await?;
+    refresh_schedules(conn, owner).await
+}
+
+// Deprecated handlers of the unversioned API, they keep its status codes and empty bodies
+pub async fn legacy_add_schedule(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Json(new_routine): Json<Routine>,
+) -> AppResult<impl IntoResponse> {
+    create_routine(&conn, &user.id, new_routine).await?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn legacy_delete_routine(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    remove_routine(&conn, &user.id, &id).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+// Reading a schedule never writes anything, days without a committed plan get a fresh preview
 pub async fn get_schedule_by_day(
     State(conn): State<Surreal<Db>>,
+    user: AuthUser,
     Path(day_str): Path<String>,
-) -> impl IntoResponse {
-    let date = NaiveDate::parse_from_str(&day_str, "%Y-%m-%d").unwrap();
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let mut today_scheditems_dbresp = conn
-        .query("SELECT * FROM day_schedule WHERE $date = date")
+    Query(query): Query<ScheduleQuery>,
+) -> AppResult<impl IntoResponse> {
+    let date = NaiveDate::parse_from_str(&day_str, "%Y-%m-%d")?;
+    let plan = read_day_plan(&conn, &user.id, date, &query, Local::now().naive_local()).await?;
+    Ok(Json(plan))
+}
+
+async fn read_day_plan(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    date: NaiveDate,
+    query: &ScheduleQuery,
+    now: NaiveDateTime,
+) -> AppResult<DayPlan> {
+    if let Some(committed_day) = get_committed_day(conn, owner, date).await? {
+        let stored_blocks = get_stored_blocks(conn, owner, date).await?;
+        return Ok(DayPlan {
+            date,
+            committed: true,
+            only: id_strings(&committed_day.focus),
+            blocks: stored_blocks.into_iter().map(SchedBlock::from).collect(),
+        });
+    }
+    let only = resolve_focus(conn, owner, &query.only_ids()).await?;
+    let (mut tasks_sorted, prerequisites) =
+        get_all_tasks_sorted(conn, owner, strategy_for(query.strategy).as_ref(), now).await?;
+    let blocks = plan_day(
+        date,
+        get_day_static_schedule(conn, owner, date).await?,
+        &mut tasks_sorted,
+        &prerequisites,
+        &id_strings(&only),
+        Timeline::new(),
+        now,
+    );
+    Ok(DayPlan {
+        date,
+        committed: false,
+        blocks,
+        only: id_strings(&only),
+    })
+}
+
+pub async fn commit_schedule(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(day_str): Path<String>,
+    Query(query): Query<ScheduleQuery>,
+) -> AppResult<impl IntoResponse> {
+    let date = NaiveDate::parse_from_str(&day_str, "%Y-%m-%d")?;
+    let plan = commit_day_plan(&conn, &user.id, date, &query, Local::now().naive_local()).await?;
+    Ok((StatusCode::CREATED, Json(plan)))
+}
+
+// What a committed day already has is checked in the same transaction that stores the new
+// plan, so of two commits of the same day only one gets through
+async fn commit_day_plan(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    date: NaiveDate,
+    query: &ScheduleQuery,
+    now: NaiveDateTime,
+) -> AppResult<DayPlan> {
+    if date < now.date() {
+        return Err(AppError::BadRequest(
+            "Plans for past days cannot be committed".to_string(),
+        ));
+    }
+    let only = resolve_focus(conn, owner, &query.only_ids()).await?;
+    let (mut tasks_sorted, prerequisites) =
+        get_all_tasks_sorted(conn, owner, strategy_for(query.strategy).as_ref(), now).await?;
+    let blocks = plan_day(
+        date,
+        get_day_static_schedule(conn, owner, date).await?,
+        &mut tasks_sorted,
+        &prerequisites,
+        &id_strings(&only),
+        Timeline::new(),
+        now,
+    );
+    let blocks_db: Vec<SchedBlockDB> = blocks
+        .iter()
+        .cloned()
+        .map(|block| {
+            let mut block_db = SchedBlockDB::new(block, owner.clone());
+            block_db.focus = only.clone();
+            block_db.strategy = query.strategy;
+            block_db
+        })
+        .collect();
+    let allocations = allocations_of(owner, date, blocks_db.iter());
+    let committed_day = CommittedDayDB {
+        id: None,
+        date,
+        focus: only.clone(),
+        strategy: query.strategy,
+        owner: Some(owner.clone()),
+    };
+    let mut commit_dbresp = conn
+        .query(
+            "BEGIN TRANSACTION;
+             LET $committed = (SELECT VALUE id FROM committed_day
+                 WHERE owner = $owner AND date = $date);
+             IF array::len($committed) > 0 {
+                 THROW 'already committed';
+             };
+             CREATE committed_day CONTENT $committed_day;
+             FOR $block IN $blocks {
+                 CREATE day_schedule CONTENT $block;
+             };
+             DELETE allocation WHERE owner = $owner AND date = $date;
+             FOR $allocation IN $allocations {
+                 CREATE allocation CONTENT $allocation;
+             };
+             COMMIT TRANSACTION;",
+        )
+        .bind(("owner", owner.clone()))
         .bind(("date", date))
-        .await
-        .unwrap();
-    let today_scheditems_db: Vec<SchedItemDB> = today_scheditems_dbresp.take(0).unwrap();
-    if !today_scheditems_db.is_empty() {
-        let today_scheditems: Vec<SchedItem> = today_scheditems_db
+        .bind(("committed_day", committed_day))
+        .bind(("blocks", blocks_db))
+        .bind(("allocations", allocations))
+        .await?;
+    let errors = commit_dbresp.take_errors();
+    if errors
+        .values()
+        .any(|err| is_thrown(err) || is_index_violation(err))
+    {
+        return Err(AppError::Conflict(format!(
+            "The plan for {} is already committed",
+            date
+        )));
+    }
+    if let Some(err) = errors.into_values().next() {
+        return Err(err.into());
+    }
+    sync_time_alloted(conn, owner).await?;
+    Ok(DayPlan {
+        date,
+        committed: true,
+        blocks,
+        only: id_strings(&only),
+    })
+}
+
+pub async fn rollback_schedule(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(day_str): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let date = NaiveDate::parse_from_str(&day_str, "%Y-%m-%d")?;
+    rollback_day_plan(&conn, &user.id, date, Local::now().naive_local()).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+// Blocks that already ended stay, and with them the day stays committed
+async fn rollback_day_plan(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    date: NaiveDate,
+    now: NaiveDateTime,
+) -> AppResult<()> {
+    if date < now.date() {
+        return Err(AppError::BadRequest(
+            "Schedules of past days are kept as history".to_string(),
+        ));
+    }
+    let kept = clear_unfinished_blocks(conn, owner, date, now).await?;
+    if kept.is_empty() {
+        conn.query("DELETE committed_day WHERE owner = $owner AND date = $date")
+            .bind(("owner", owner.clone()))
+            .bind(("date", date))
+            .await?
+            .check()?;
+    }
+    sync_time_alloted(conn, owner).await
+}
+
+// Replans the user's committed schedules from today onwards after a task or routine changed.
+// Past days stay frozen, and on today only the blocks that have not ended yet are replaced.
+// A day that was limited to some projects or contexts stays limited to them, and is planned
+// with the strategy it was committed with.
+pub async fn refresh_schedules(conn: &Surreal<Db>, owner: &RecordId) -> AppResult<()> {
+    refresh_schedules_at(conn, owner, Local::now().naive_local()).await
+}
+
+async fn refresh_schedules_at(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    now: NaiveDateTime,
+) -> AppResult<()> {
+    let mut days_dbresp = conn
+        .query(
+            "SELECT * FROM committed_day
+             WHERE owner = $owner AND date >= $today ORDER BY date",
+        )
+        .bind(("owner", owner.clone()))
+        .bind(("today", now.date()))
+        .await?;
+    let days: Vec<CommittedDayDB> = days_dbresp.take(0)?;
+
+    // Every affected day is cleared first so the time it held is free for the replanning
+    let mut kept_by_date = Vec::new();
+    for day in days {
+        let kept = clear_unfinished_blocks(conn, owner, day.date, now).await?;
+        kept_by_date.push((day.date, kept, day.focus, day.strategy));
+    }
+    sync_time_alloted(conn, owner).await?;
+
+    let (mut tasks_sorted, prerequisites) = get_all_tasks_sorted(
+        conn,
+        owner,
+        strategy_for(StrategyKind::default()).as_ref(),
+        now,
+    )
+    .await?;
+    for (date, kept, only, strategy) in kept_by_date {
+        // The time booked on earlier days stays with the tasks, only their order changes
+        strategy_for(strategy).sort(&mut tasks_sorted, now);
+        let mut timeline = Timeline::new();
+        for block in &kept {
+            timeline.reserve(
+                minute_of_day(block.start.time()),
+                minute_of_day(block.end.time()),
+            );
+        }
+        let routines: Vec<Routine> = get_day_static_schedule(conn, owner, date)
+            .await?
             .into_iter()
-            .map(SchedItem::from)
+            .filter(|routine| {
+                !kept.iter().any(|block| {
+                    block.kind == BlockKind::Routine
+                        && block.source.as_ref().map(|id| id.to_string()) == routine.id
+                })
+            })
             .collect();
-        Json(today_scheditems)
-    } else {
-        let schedule_today = create_day_sched(
-            &conn,
+        let blocks = plan_day(
             date,
-            get_day_static_schedule(&conn, date).await,
-            get_all_tasks_sorted(&conn).await,
+            routines,
+            &mut tasks_sorted,
+            &prerequisites,
+            &id_strings(&only),
+            timeline,
+            now,
+        );
+        store_day_plan(conn, owner, date, &blocks, &kept, &only, strategy).await?;
+    }
+    sync_time_alloted(conn, owner).await
+}
+
+fn id_strings(ids: &[RecordId]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+// Blocks of every committed day, oldest first
+pub async fn get_committed_blocks(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+) -> AppResult<Vec<SchedBlock>> {
+    let mut blocks_dbresp = conn
+        .query("SELECT * FROM day_schedule WHERE owner = $owner AND start != NONE ORDER BY start")
+        .bind(("owner", owner.clone()))
+        .await?;
+    let blocks_db: Vec<SchedBlockDB> = blocks_dbresp.take(0)?;
+    Ok(blocks_db.into_iter().map(SchedBlock::from).collect())
+}
+
+async fn get_committed_day(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    date: NaiveDate,
+) -> AppResult<Option<CommittedDayDB>> {
+    let mut day_dbresp = conn
+        .query("SELECT * FROM committed_day WHERE owner = $owner AND date = $date")
+        .bind(("owner", owner.clone()))
+        .bind(("date", date))
+        .await?;
+    let days: Vec<CommittedDayDB> = day_dbresp.take(0)?;
+    Ok(days.into_iter().next())
+}
+
+async fn get_stored_blocks(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    date: NaiveDate,
+) -> AppResult<Vec<SchedBlockDB>> {
+    // Rows written by the old hourly scheduler have no start and are left out
+    let mut blocks_dbresp = conn
+        .query(
+            "SELECT * FROM day_schedule
+             WHERE owner = $owner AND $date = date AND start != NONE ORDER BY start",
         )
-        .await;
-        for val in schedule_today.clone() {
-            let db_value = SchedItemDB::from(val);
-            let _: Option<SchedItemDB> =
-                conn.create("day_schedule").content(db_value).await.unwrap();
+        .bind(("owner", owner.clone()))
+        .bind(("date", date))
+        .await?;
+    let blocks_db: Vec<SchedBlockDB> = blocks_dbresp.take(0)?;
+    Ok(blocks_db)
+}
+
+// Deletes the blocks of a day that have not ended yet and returns the ones that were kept,
+// the day's allocations are rebuilt from the kept blocks
+async fn clear_unfinished_blocks(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    date: NaiveDate,
+    now: NaiveDateTime,
+) -> AppResult<Vec<SchedBlockDB>> {
+    let kept: Vec<SchedBlockDB> = get_stored_blocks(conn, owner, date)
+        .await?
+        .into_iter()
+        .filter(|block| block.end <= now)
+        .collect();
+    conn.query("DELETE day_schedule WHERE owner = $owner AND date = $date AND end > $now")
+        .bind(("owner", owner.clone()))
+        .bind(("date", date))
+        .bind(("now", now))
+        .await?
+        .check()?;
+    store_allocations(conn, owner, date, kept.iter()).await?;
+    Ok(kept)
+}
+
+// Stores freshly planned blocks and rewrites the day's allocations to cover them and the kept ones
+async fn store_day_plan(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    date: NaiveDate,
+    blocks: &[SchedBlock],
+    kept: &[SchedBlockDB],
+    only: &[RecordId],
+    strategy: StrategyKind,
+) -> AppResult<()> {
+    let mut blocks_db = Vec::new();
+    for val in blocks.iter().cloned() {
+        let mut db_value = SchedBlockDB::new(val, owner.clone());
+        db_value.focus = only.to_vec();
+        db_value.strategy = strategy;
+        let created: Option<SchedBlockDB> = conn
+            .create("day_schedule")
+            .content(db_value.clone())
+            .await?;
+        blocks_db.push(created.unwrap_or(db_value));
+    }
+    store_allocations(conn, owner, date, kept.iter().chain(blocks_db.iter())).await
+}
+
+// One allocation per task booked in the blocks of a day
+fn allocations_of<'a>(
+    owner: &RecordId,
+    date: NaiveDate,
+    blocks: impl Iterator<Item = &'a SchedBlockDB>,
+) -> Vec<AllocationDB> {
+    let mut minutes_by_task: HashMap<RecordId, i64> = HashMap::new();
+    for block in blocks {
+        if let (BlockKind::Task, Some(task_id)) = (block.kind, &block.source) {
+            *minutes_by_task.entry(task_id.clone()).or_default() +=
+                (block.end - block.start).num_minutes();
         }
-        Json(schedule_today)
     }
+    minutes_by_task
+        .into_iter()
+        .map(|(task, minutes)| AllocationDB {
+            id: None,
+            date,
+            task,
+            minutes,
+            owner: Some(owner.clone()),
+        })
+        .collect()
 }
 
-async fn create_day_sched(
+async fn store_allocations<'a>(
     conn: &Surreal<Db>,
+    owner: &RecordId,
+    date: NaiveDate,
+    blocks: impl Iterator<Item = &'a SchedBlockDB>,
+) -> AppResult<()> {
+    conn.query("DELETE allocation WHERE owner = $owner AND date = $date")
+        .bind(("owner", owner.clone()))
+        .bind(("date", date))
+        .await?
+        .check()?;
+    for allocation in allocations_of(owner, date, blocks) {
+        let _: Option<AllocationDB> = conn.create("allocation").content(allocation).await?;
+    }
+    Ok(())
+}
+
+// A task's time_alloted caches the sum of everything committed for it in the allocation table
+async fn sync_time_alloted(conn: &Surreal<Db>, owner: &RecordId) -> AppResult<()> {
+    let mut totals_dbresp = conn
+        .query(
+            "SELECT task, math::sum(minutes) AS minutes FROM allocation
+             WHERE owner = $owner GROUP BY task",
+        )
+        .bind(("owner", owner.clone()))
+        .await?;
+    let totals: Vec<AllocationTotal> = totals_dbresp.take(0)?;
+    let totals: HashMap<String, i64> = totals
+        .into_iter()
+        .map(|total| (total.task.to_string(), total.minutes))
+        .collect();
+    let tasks = get_user_tasks(conn, owner).await?;
+    for task in tasks.into_iter().map(Task::from) {
+        let Some(id) = task.id else {
+            continue;
+        };
+        let minutes = totals.get(&id).copied().unwrap_or(0);
+        let time_alloted = NaiveTime::MIN + TimeDelta::minutes(minutes);
+        if time_alloted != task.time_alloted {
+            let record_id: RecordId = id.parse().map_err(|_| AppError::invalid_id(&id))?;
+            let _: Option<TaskDB> = conn
+                .update(record_id)
+                .merge(serde_json::json!({ "time_alloted": time_alloted }))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+// Gaps shorter than this are not worth booking a task into
+const MIN_TASK_BLOCK_MINS: u32 = 5;
+
+// Lays out one day without touching the database, the tasks' time_alloted only advances in memory.
+// With `only` set, just the tasks filed under one of those tags get time.
+fn plan_day(
     date: NaiveDate,
     static_sched_for_today: Vec<Routine>,
-    mut tasks_sorted: Vec<Task>,
-) -> Vec<SchedItem> {
-    let mut day_sched: Vec<SchedItem> = Vec::new();
-    for x in 00..24 {
-        day_sched.push(SchedItem {
-            id: None,
+    tasks_sorted: &mut [Task],
+    prerequisites: &Prerequisites,
+    only: &[String],
+    mut timeline: Timeline,
+    now: NaiveDateTime,
+) -> Vec<SchedBlock> {
+    let mut day_sched: Vec<SchedBlock> = Vec::new();
+    for routine in &static_sched_for_today {
+        let slot = (
+            minute_of_day(routine.start_time),
+            minute_of_day(routine.end_time),
+        );
+        timeline.reserve(slot.0, slot.1);
+        day_sched.push(new_block(
             date,
-            represented_hour_start: x,
-            has_time: true,
-            time_left_mins: 60,
-            title: Vec::new(),
-        });
+            slot,
+            routine.title.clone(),
+            BlockKind::Routine,
+            routine.id.clone(),
+        ));
     }
-    for scheditem in &mut day_sched {
-        for routine in &static_sched_for_today {
-            if routine.start_time.hour() == scheditem.represented_hour_start as u32 {
-                scheditem.title.push(routine.title.clone());
-                scheditem.time_left_mins = scheditem
-                    .time_left_mins
-                    .saturating_sub(routine.start_time.minute() as u8);
-                if scheditem.time_left_mins == 0 {
-                    scheditem.has_time = false
-                }
-            } else if (routine.start_time.hour() as u8) < scheditem.represented_hour_start
-                && (routine.end_time.hour() as u8) > scheditem.represented_hour_start
-            {
-                scheditem.title.push(routine.title.clone());
-                scheditem.has_time = false;
-                scheditem.time_left_mins = 0;
-            }
-        }
+    // Tasks are only booked into time that is still ahead of us
+    if date < now.date() {
+        timeline.reserve(0, MINUTES_IN_DAY);
+    } else if date == now.date() {
+        timeline.reserve(0, minute_of_day(now.time()));
     }
-    'scheditem_loop: for scheditem in &mut day_sched {
-        if scheditem.has_time {
-            for task in &mut tasks_sorted {
-                if !(task.is_done || task.time_alloted >= task.req_time) {
-                    let time_alloted: u8 = scheditem.time_left_mins.min(
-                        task.req_time
-                            .signed_duration_since(task.time_alloted)
-                            .num_minutes() as u8,
-                    );
-                    task.time_alloted +=
-                        TimeDelta::try_minutes(time_alloted as i64).unwrap_or(TimeDelta::zero());
-                    let task_db = TaskDB::from(task.to_owned());
-                    let id = task.id.clone().unwrap_or("Task".to_string());
-                    let (table, key) = id.split_once(':').unwrap_or(("Tasks", id.as_str()));
-                    conn.use_ns("core").use_db("main").await.unwrap();
-                    let _: Option<TaskDB> =
-                        conn.update((table, key)).content(task_db).await.unwrap();
-                    scheditem.time_left_mins =
-                        scheditem.time_left_mins.saturating_sub(time_alloted);
-                    scheditem.title.push(task.name.clone());
-                    let low_remaining_time_threshold: u8 = 4;
-                    if scheditem.time_left_mins <= low_remaining_time_threshold {
-                        scheditem.has_time = false;
-                        continue 'scheditem_loop;
-                    }
-                }
-            }
+    // Minute from which a task counts as finished for its dependents, known once it is booked
+    // in full. Tasks booked in full on earlier days are finished from the start of this one.
+    let mut finished_at: HashMap<String, u32> = tasks_sorted
+        .iter()
+        .filter(|task| task.req_time > NaiveTime::MIN && task.time_alloted >= task.req_time)
+        .filter_map(|task| Some((task.id.clone()?, 0)))
+        .collect();
+    let mut pending: Vec<usize> = (0..tasks_sorted.len())
+        .filter(|&i| {
+            let task = &tasks_sorted[i];
+            // Nothing is booked for a task on the days after it is due
+            !task.is_done
+                && task.time_alloted < task.req_time
+                && date <= task.due_by.date()
+                && (only.is_empty() || task.tags.iter().any(|tag| only.contains(tag)))
+        })
+        .collect();
+    // Highest priority first, but never ahead of a prerequisite. A task waiting for something
+    // that is not booked in full stays blocked and gets no time at all.
+    while timeline.free_minutes() > 0 {
+        let next = pending.iter().enumerate().find_map(|(pos, &i)| {
+            let waiting_on = tasks_sorted[i]
+                .id
+                .as_ref()
+                .and_then(|id| prerequisites.get(id));
+            let start_from = waiting_on
+                .into_iter()
+                .flatten()
+                .try_fold(0, |from, id| Some(from.max(*finished_at.get(id)?)))?;
+            Some((pos, i, start_from))
+        });
+        let Some((pos, i, start_from)) = next else {
+            break;
+        };
+        pending.remove(pos);
+        let task = &mut tasks_sorted[i];
+        let time_needed = task
+            .req_time
+            .signed_duration_since(task.time_alloted)
+            .num_minutes() as u32;
+        let slots = timeline.allocate_from(start_from, time_needed, MIN_TASK_BLOCK_MINS);
+        let time_alloted: u32 = slots.iter().map(|(start, end)| end - start).sum();
+        task.time_alloted += TimeDelta::minutes(time_alloted as i64);
+        if task.time_alloted >= task.req_time
+            && let Some(id) = &task.id
+        {
+            let end = slots.last().map_or(start_from, |slot| slot.1);
+            finished_at.insert(id.clone(), end);
+        }
+        for slot in slots {
+            day_sched.push(new_block(
+                date,
+                slot,
+                task.name.clone(),
+                BlockKind::Task,
+                task.id.clone(),
+            ));
         }
     }
+    day_sched.sort_by_key(|block| block.start);
     day_sched
 }
 
-async fn get_day_static_schedule(conn: &Surreal<Db>, date: NaiveDate) -> Vec<Routine> {
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let sql = "SELECT * FROM static_schedule WHERE $date IN start_date..=end_date";
-    let mut result = conn.query(sql).bind(("date", date)).await.unwrap();
-    let schedule_db: Vec<RoutineDB> = result.take(0).unwrap();
-    let schedule_today: Vec<Routine> = schedule_db.into_iter().map(Routine::from).collect();
-    schedule_today
+async fn get_day_static_schedule(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    date: NaiveDate,
+) -> AppResult<Vec<Routine>> {
+    let sql =
+        "SELECT * FROM static_schedule WHERE owner = $owner AND $date IN start_date..=end_date";
+    let mut result = conn
+        .query(sql)
+        .bind(("owner", owner.clone()))
+        .bind(("date", date))
+        .await?;
+    let schedule_db: Vec<RoutineDB> = result.take(0)?;
+    let schedule_today: Vec<Routine> = schedule_db
+        .into_iter()
+        .map(Routine::from)
+        .filter(|routine| routine.occurs_on(date))
+        .collect();
+    Ok(schedule_today)
 }
 
-async fn get_all_tasks_sorted(conn: &Surreal<Db>) -> Vec<Task> {
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let tasks: Vec<TaskDB> = conn.select("Tasks").await.unwrap();
-    let tasks: Vec<Task> = tasks.into_iter().map(Task::from).collect();
-    let mut tasks: Vec<Task> = tasks
+// Tasks with subtasks that need time of their own are booked through those subtasks, the parent's
+// own estimate only counts while it has none. Subtasks of a finished task are left out, the
+// others count as filed under their parents' tags too. Comes with the open prerequisites of every task, which `plan_day` books first.
+async fn get_all_tasks_sorted(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    strategy: &dyn PrioritizationStrategy,
+    now: NaiveDateTime,
+) -> AppResult<(Vec<Task>, Prerequisites)> {
+    let tasks: Vec<Task> = get_user_tasks(conn, owner)
+        .await?
         .into_iter()
-        .filter(|task| task.due_by > Local::now().naive_local())
+        .map(Task::from)
+        .collect();
+    let by_id: HashMap<&str, &Task> = tasks
+        .iter()
+        .filter_map(|task| Some((task.id.as_deref()?, task)))
+        .collect();
+    let mut parent_of: HashMap<String, &Task> = HashMap::new();
+    let mut booked_through_subtasks: HashSet<String> = HashSet::new();
+    let subtask_links = get_subtask_links(conn, owner).await?;
+    for link in &subtask_links {
+        let (parent_id, child_id) = (link.parent.to_string(), link.child.to_string());
+        let (Some(parent), Some(child)) =
+            (by_id.get(parent_id.as_str()), by_id.get(child_id.as_str()))
+        else {
+            continue;
+        };
+        if child.req_time > NaiveTime::MIN {
+            booked_through_subtasks.insert(parent_id);
+        }
+        parent_of.insert(child_id, parent);
+    }
+
+    let mut tasks_sorted: Vec<Task> = Vec::new();
+    for task in &tasks {
+        let id = task.id.clone().unwrap_or_default();
+        if task.due_by <= now || booked_through_subtasks.contains(&id) {
+            continue;
+        }
+        let mut task = task.clone();
+        if let Some(parent) = parent_of.get(&id) {
+            if parent.is_done {
+                continue;
+            }
+            // Blocks of a subtask say which task they are part of
+            task.name = format!("{} › {}", parent.name, task.name);
+            let mut ancestor = Some(*parent);
+            while let Some(current) = ancestor {
+                task.tags.extend(current.tags.iter().cloned());
+                ancestor = current
+                    .id
+                    .as_ref()
+                    .and_then(|id| parent_of.get(id))
+                    .copied();
+            }
+        }
+        tasks_sorted.push(task);
+    }
+    strategy.sort(&mut tasks_sorted, now);
+
+    let tasks_by_id: HashMap<String, Task> = tasks
+        .iter()
+        .filter_map(|task| Some((task.id.clone()?, task.clone())))
         .collect();
-    tasks.sort_by_key(|task| {
-        -((task.due_by - Local::now().naive_local()).num_minutes() as i32)
-            + (task.imp_lvl * 10) as i32
-    });
-    tasks
+    let prerequisites = open_prerequisites(
+        &tasks_by_id,
+        &get_dependency_links(conn, owner).await?,
+        &subtask_links,
+    );
+    Ok((tasks_sorted, prerequisites))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db::{test_db, test_user};
+
+    fn hours(hours: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hours, 0, 0).unwrap()
+    }
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    async fn add_task(
+        conn: &Surreal<Db>,
+        owner: &RecordId,
+        name: &str,
+        due_by: &str,
+        imp_lvl: u8,
+        req_hours: u32,
+    ) -> String {
+        let task = Task {
+            id: None,
+            name: name.to_string(),
+            description: String::new(),
+            due_by: at(due_by),
+            imp_lvl,
+            req_time: hours(req_hours),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: None,
+            tags: Vec::new(),
+            reminders: Vec::new(),
+        };
+        let created: Option<TaskDB> = conn
+            .create("Tasks")
+            .content(TaskDB::new(task, owner.clone()))
+            .await
+            .unwrap();
+        created.and_then(|task| task.id).unwrap().to_string()
+    }
+
+    // Title, start and end of every block of a day, and the blocks' ids
+    async fn day_blocks(
+        conn: &Surreal<Db>,
+        owner: &RecordId,
+        date: &str,
+    ) -> (Vec<(String, NaiveTime, NaiveTime)>, Vec<RecordId>) {
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+        let blocks = get_stored_blocks(conn, owner, date).await.unwrap();
+        let ids = blocks.iter().filter_map(|block| block.id.clone()).collect();
+        let times = blocks
+            .into_iter()
+            .map(|block| (block.title, block.start.time(), block.end.time()))
+            .collect();
+        (times, ids)
+    }
+
+    async fn alloted(conn: &Surreal<Db>, owner: &RecordId) -> HashMap<String, NaiveTime> {
+        get_user_tasks(conn, owner)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(Task::from)
+            .map(|task| (task.name, task.time_alloted))
+            .collect()
+    }
+
+    // Allocated minutes of every task, and the ids of every block and allocation
+    async fn snapshot(
+        conn: &Surreal<Db>,
+        owner: &RecordId,
+    ) -> (Vec<(String, NaiveTime)>, Vec<String>, Vec<String>) {
+        let mut tasks: Vec<(String, NaiveTime)> = get_user_tasks(conn, owner)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(Task::from)
+            .map(|task| (task.id.unwrap_or_default(), task.time_alloted))
+            .collect();
+        tasks.sort();
+        let mut ids_dbresp = conn
+            .query("SELECT VALUE id FROM day_schedule; SELECT VALUE id FROM allocation;")
+            .await
+            .unwrap();
+        let ids = |records: Vec<RecordId>| {
+            let mut ids: Vec<String> = records.iter().map(RecordId::to_string).collect();
+            ids.sort();
+            ids
+        };
+        let blocks = ids(ids_dbresp.take(0).unwrap());
+        let allocations = ids(ids_dbresp.take(1).unwrap());
+        (tasks, blocks, allocations)
+    }
+
+    #[test]
+    fn tasks_get_no_time_after_their_due_date() {
+        let task = Task {
+            id: Some("Tasks:report".to_string()),
+            name: "Report".to_string(),
+            description: String::new(),
+            due_by: at("2025-03-11 09:00"),
+            imp_lvl: 5,
+            req_time: hours(2),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: None,
+            tags: Vec::new(),
+            reminders: Vec::new(),
+        };
+        let now = at("2025-03-09 12:00");
+        for (day, booked) in [(10, true), (11, true), (12, false)] {
+            let mut tasks = vec![task.clone()];
+            let blocks = plan_day(
+                NaiveDate::from_ymd_opt(2025, 3, day).unwrap(),
+                Vec::new(),
+                &mut tasks,
+                &Prerequisites::new(),
+                &[],
+                Timeline::new(),
+                now,
+            );
+            assert_eq!(!blocks.is_empty(), booked, "2025-03-{}", day);
+            assert_eq!(tasks[0].time_alloted > NaiveTime::MIN, booked);
+        }
+    }
+
+    #[test]
+    fn focused_days_book_only_the_tasks_filed_there() {
+        let task = |id: &str, tags: &[&str]| Task {
+            id: Some(id.to_string()),
+            name: id.to_string(),
+            description: String::new(),
+            due_by: at("2025-03-14 17:00"),
+            imp_lvl: 5,
+            req_time: hours(2),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            reminders: Vec::new(),
+        };
+        let mut tasks = vec![
+            task("Tasks:thesis", &["tag:thesis"]),
+            task("Tasks:errands", &["tag:errands"]),
+            task("Tasks:slides", &["tag:thesis", "tag:urgent"]),
+            task("Tasks:loose", &[]),
+        ];
+        let date = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let only = vec!["tag:thesis".to_string()];
+        let blocks = plan_day(
+            date,
+            Vec::new(),
+            &mut tasks,
+            &Prerequisites::new(),
+            &only,
+            Timeline::new(),
+            at("2025-03-09 12:00"),
+        );
+        let booked: HashSet<&str> = blocks
+            .iter()
+            .filter_map(|block| block.source.as_deref())
+            .collect();
+        assert_eq!(booked, HashSet::from(["Tasks:thesis", "Tasks:slides"]));
+        for task in &tasks {
+            let expected = if booked.contains(task.name.as_str()) {
+                hours(2)
+            } else {
+                NaiveTime::MIN
+            };
+            assert_eq!(task.time_alloted, expected, "{}", task.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn previews_have_no_side_effects() {
+        let conn = test_db().await;
+        let owner = test_user(&conn, "alice").await;
+        let now = Local::now().naive_local();
+        let (tomorrow, day_after) = (
+            now.date() + TimeDelta::days(1),
+            now.date() + TimeDelta::days(2),
+        );
+        // More work than fits into tomorrow next to the routine
+        let task = Task {
+            id: None,
+            name: "Thesis".to_string(),
+            description: String::new(),
+            due_by: now + TimeDelta::days(5),
+            imp_lvl: 8,
+            req_time: hours(23),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: None,
+            tags: Vec::new(),
+            reminders: Vec::new(),
+        };
+        let _: Option<TaskDB> = conn
+            .create("Tasks")
+            .content(TaskDB::new(task, owner.clone()))
+            .await
+            .unwrap();
+        let routine = Routine {
+            id: None,
+            title: "Work".to_string(),
+            start_date: now.date(),
+            end_date: day_after,
+            imp: 5,
+            start_time: hours(9),
+            end_time: hours(17),
+            weekdays: Weekday::ALL.to_vec(),
+            reminders: Vec::new(),
+        };
+        let _: Option<RoutineDB> = conn
+            .create("static_schedule")
+            .content(RoutineDB::new(routine, owner.clone()))
+            .await
+            .unwrap();
+
+        let query = ScheduleQuery::default();
+        let committed = commit_day_plan(&conn, &owner, tomorrow, &query, now)
+            .await
+            .unwrap();
+        assert!(committed.committed);
+        let before = snapshot(&conn, &owner).await;
+        assert!(!before.1.is_empty() && !before.2.is_empty());
+        assert!(
+            before
+                .0
+                .iter()
+                .all(|(_, alloted)| *alloted > NaiveTime::MIN)
+        );
+
+        // The day after is only previewed, tomorrow is read back as committed
+        let first = read_day_plan(&conn, &owner, day_after, &query, now)
+            .await
+            .unwrap();
+        let second = read_day_plan(&conn, &owner, day_after, &query, now)
+            .await
+            .unwrap();
+        assert!(!first.committed);
+        assert!(
+            first
+                .blocks
+                .iter()
+                .any(|block| block.kind == BlockKind::Task)
+        );
+        assert_eq!(first, second);
+        let stored = read_day_plan(&conn, &owner, tomorrow, &query, now)
+            .await
+            .unwrap();
+        assert!(stored.committed);
+        assert_eq!(stored.blocks.len(), committed.blocks.len());
+        assert_eq!(snapshot(&conn, &owner).await, before);
+
+        // A second commit of the same day is refused and writes nothing
+        assert!(matches!(
+            commit_day_plan(&conn, &owner, tomorrow, &query, now).await,
+            Err(AppError::Conflict(_))
+        ));
+        assert_eq!(snapshot(&conn, &owner).await, before);
+    }
+
+    #[tokio::test]
+    async fn days_committed_without_blocks_stay_committed() {
+        let conn = test_db().await;
+        let owner = test_user(&conn, "alice").await;
+        let query = ScheduleQuery::default();
+        let date = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let now = at("2025-03-09 12:00");
+
+        let committed = commit_day_plan(&conn, &owner, date, &query, now)
+            .await
+            .unwrap();
+        assert!(committed.committed && committed.blocks.is_empty());
+        let stored = read_day_plan(&conn, &owner, date, &query, now)
+            .await
+            .unwrap();
+        assert!(stored.committed && stored.blocks.is_empty());
+        assert!(matches!(
+            commit_day_plan(&conn, &owner, date, &query, now).await,
+            Err(AppError::Conflict(_))
+        ));
+
+        // A task added later is booked into the committed day
+        add_task(&conn, &owner, "Report", "2025-03-12 12:00", 5, 2).await;
+        refresh_schedules_at(&conn, &owner, now).await.unwrap();
+        let (blocks, _) = day_blocks(&conn, &owner, "2025-03-10").await;
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].0, "Report");
+        assert_eq!(alloted(&conn, &owner).await["Report"], hours(2));
+
+        // Rolled back, the day is only a preview again and gives the time back
+        rollback_day_plan(&conn, &owner, date, now).await.unwrap();
+        let preview = read_day_plan(&conn, &owner, date, &query, now)
+            .await
+            .unwrap();
+        assert!(!preview.committed);
+        assert_eq!(alloted(&conn, &owner).await["Report"], NaiveTime::MIN);
+    }
+
+    #[tokio::test]
+    async fn refreshing_keeps_past_days_and_blocks_that_ended() {
+        let conn = test_db().await;
+        let owner = test_user(&conn, "alice").await;
+        let query = ScheduleQuery::default();
+        let date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap();
+
+        add_task(&conn, &owner, "Post", "2025-03-09 23:30", 5, 1).await;
+        commit_day_plan(
+            &conn,
+            &owner,
+            date("2025-03-09"),
+            &query,
+            at("2025-03-09 22:00"),
+        )
+        .await
+        .unwrap();
+        add_task(&conn, &owner, "Call", "2025-03-11 12:00", 9, 1).await;
+        add_task(&conn, &owner, "Report", "2025-03-20 12:00", 3, 3).await;
+        commit_day_plan(
+            &conn,
+            &owner,
+            date("2025-03-10"),
+            &query,
+            at("2025-03-10 06:00"),
+        )
+        .await
+        .unwrap();
+        let (monday, monday_ids) = day_blocks(&conn, &owner, "2025-03-10").await;
+        assert_eq!(
+            monday,
+            vec![
+                ("Call".to_string(), hours(6), hours(7)),
+                ("Report".to_string(), hours(7), hours(10)),
+            ]
+        );
+        let sunday = day_blocks(&conn, &owner, "2025-03-09").await;
+
+        // An urgent task comes in at eight, while the report is being worked on
+        add_task(&conn, &owner, "Fix", "2025-03-10 23:00", 10, 1).await;
+        refresh_schedules_at(&conn, &owner, at("2025-03-10 08:00"))
+            .await
+            .unwrap();
+
+        assert_eq!(day_blocks(&conn, &owner, "2025-03-09").await, sunday);
+        let (replanned, replanned_ids) = day_blocks(&conn, &owner, "2025-03-10").await;
+        assert_eq!(
+            replanned,
+            vec![
+                ("Call".to_string(), hours(6), hours(7)),
+                ("Fix".to_string(), hours(8), hours(9)),
+                ("Report".to_string(), hours(9), hours(12)),
+            ]
+        );
+        assert_eq!(replanned_ids[0], monday_ids[0]);
+        let alloted = alloted(&conn, &owner).await;
+        assert_eq!(alloted["Post"], hours(1));
+        assert_eq!(alloted["Call"], hours(1));
+        assert_eq!(alloted["Fix"], hours(1));
+        assert_eq!(alloted["Report"], hours(3));
+    }
+
+    #[tokio::test]
+    async fn tasks_added_later_get_time_on_committed_future_days() {
+        let conn = test_db().await;
+        let owner = test_user(&conn, "alice").await;
+        let now = at("2025-03-10 08:00");
+        let tuesday = NaiveDate::from_ymd_opt(2025, 3, 11).unwrap();
+
+        add_task(&conn, &owner, "Read", "2025-03-20 12:00", 5, 1).await;
+        commit_day_plan(&conn, &owner, tuesday, &ScheduleQuery::default(), now)
+            .await
+            .unwrap();
+        add_task(&conn, &owner, "Slides", "2025-03-11 12:00", 10, 2).await;
+        assert_eq!(alloted(&conn, &owner).await["Slides"], NaiveTime::MIN);
+
+        refresh_schedules_at(&conn, &owner, now).await.unwrap();
+        let (blocks, _) = day_blocks(&conn, &owner, "2025-03-11").await;
+        assert_eq!(
+            blocks,
+            vec![
+                ("Slides".to_string(), hours(0), hours(2)),
+                ("Read".to_string(), hours(2), hours(3)),
+            ]
+        );
+        let alloted = alloted(&conn, &owner).await;
+        assert_eq!(alloted["Slides"], hours(2));
+        assert_eq!(alloted["Read"], hours(1));
+    }
 }
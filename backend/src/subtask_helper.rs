@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use chrono::NaiveTime;
+use serde::Deserialize;
+use surrealdb::{RecordId, Surreal, engine::local::Db};
+use taskflow_types::{NewSubtask, Task, TaskProgress, TaskTree};
+
+use crate::auth_helper::AuthUser;
+use crate::error::{AppError, AppResult};
+use crate::extract::{Json, Path};
+use crate::schedule_helper::refresh_schedules;
+use crate::task_helper::{TaskDB, continue_series, get_owned_task_db, get_user_tasks};
+
+// One edge of the `subtask` relation, stored as parent->subtask->child.
+// A task has at most one parent, the unique index on `out` makes sure of that.
+#[derive(Clone, Deserialize)]
+pub struct SubtaskLink {
+    pub parent: RecordId,
+    pub child: RecordId,
+}
+
+pub async fn get_subtask_links(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+) -> AppResult<Vec<SubtaskLink>> {
+    let mut links_dbresp = conn
+        .query("SELECT in AS parent, out AS child FROM subtask WHERE in.owner = $owner")
+        .bind(("owner", owner.clone()))
+        .await?;
+    let links: Vec<SubtaskLink> = links_dbresp.take(0)?;
+    Ok(links)
+}
+
+pub async fn parent_of(conn: &Surreal<Db>, task: &RecordId) -> AppResult<Option<RecordId>> {
+    let mut parent_dbresp = conn
+        .query("SELECT VALUE in FROM subtask WHERE out = $task LIMIT 1")
+        .bind(("task", task.clone()))
+        .await?;
+    let parents: Vec<RecordId> = parent_dbresp.take(0)?;
+    Ok(parents.into_iter().next())
+}
+
+// Every task below `task`, at any depth
+pub async fn descendants_of(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    task: &RecordId,
+) -> AppResult<Vec<RecordId>> {
+    let children = children_by_parent(get_subtask_links(conn, owner).await?);
+    let mut descendants = Vec::new();
+    let mut pending = vec![task.to_string()];
+    while let Some(parent) = pending.pop() {
+        for child in children.get(&parent).into_iter().flatten() {
+            pending.push(child.to_string());
+            descendants.push(child.clone());
+        }
+    }
+    Ok(descendants)
+}
+
+fn children_by_parent(links: Vec<SubtaskLink>) -> HashMap<String, Vec<RecordId>> {
+    let mut children: HashMap<String, Vec<RecordId>> = HashMap::new();
+    for link in links {
+        children
+            .entry(link.parent.to_string())
+            .or_default()
+            .push(link.child);
+    }
+    children
+}
+
+// Brings auto-completing tasks in line with their subtasks, starting at `task` and walking up
+// to the top level task, since finishing the last step may finish the parent's parent too
+pub async fn roll_up_completion(conn: &Surreal<Db>, task: RecordId) -> AppResult<()> {
+    let mut current = Some(task);
+    while let Some(task_id) = current {
+        let task_db: Option<TaskDB> = conn.select(task_id.clone()).await?;
+        let Some(task_db) = task_db else {
+            break;
+        };
+        if task_db.auto_complete {
+            let mut done_dbresp = conn
+                .query("SELECT VALUE out.is_done FROM subtask WHERE in = $task")
+                .bind(("task", task_id.clone()))
+                .await?;
+            let subtasks_done: Vec<bool> = done_dbresp.take(0)?;
+            let all_done = subtasks_done.iter().all(|is_done| *is_done);
+            if !subtasks_done.is_empty() && all_done != task_db.is_done {
+                let updated: Option<TaskDB> = conn
+                    .update(task_id.clone())
+                    .merge(serde_json::json!({ "is_done": all_done }))
+                    .await?;
+                if let Some(updated) = updated
+                    && all_done
+                {
+                    continue_series(conn, &task_id, updated).await?;
+                }
+            }
+        }
+        current = parent_of(conn, &task_id).await?;
+    }
+    Ok(())
+}
+
+pub async fn add_subtask(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(new_subtask): Json<NewSubtask>,
+) -> AppResult<impl IntoResponse> {
+    let subtask = create_subtask(&conn, &user.id, &id, new_subtask).await?;
+    Ok((StatusCode::CREATED, Json(subtask)))
+}
+
+// Only a task of the same owner can take subtasks, so a tree never spans two users
+async fn create_subtask(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    id: &str,
+    new_subtask: NewSubtask,
+) -> AppResult<Task> {
+    new_subtask.validate().map_err(AppError::BadRequest)?;
+    let (parent_id, parent_db) = get_owned_task_db(conn, id, owner).await?;
+    let parent = Task::from(parent_db);
+    let subtask = Task {
+        id: None,
+        name: new_subtask.name,
+        description: new_subtask.description,
+        due_by: new_subtask.due_by.unwrap_or(parent.due_by),
+        imp_lvl: new_subtask.imp_lvl.unwrap_or(parent.imp_lvl),
+        // No required time makes it a checklist item
+        req_time: new_subtask.req_time.unwrap_or(NaiveTime::MIN),
+        time_alloted: NaiveTime::MIN,
+        is_done: false,
+        auto_complete: false,
+        recurrence: None,
+        tags: Vec::new(),
+        reminders: Vec::new(),
+    };
+    let created: Option<TaskDB> = conn
+        .create("Tasks")
+        .content(TaskDB::new(subtask, owner.clone()))
+        .await?;
+    let subtask = created.map(Task::from).ok_or(AppError::Internal(
+        "Created subtask was not returned".to_string(),
+    ))?;
+    let subtask_id: RecordId = subtask
+        .id
+        .as_deref()
+        .and_then(|id| id.parse().ok())
+        .ok_or(AppError::Internal("Created subtask has no id".to_string()))?;
+    conn.query("RELATE $parent->subtask->$child")
+        .bind(("parent", parent_id.clone()))
+        .bind(("child", subtask_id))
+        .await?
+        .check()?;
+    // A new open step reopens a parent that had completed itself
+    roll_up_completion(conn, parent_id).await?;
+    refresh_schedules(conn, owner).await?;
+    Ok(subtask)
+}
+
+pub async fn get_subtask_tree(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let (root_id, _) = get_owned_task_db(&conn, &id, &user.id).await?;
+    let mut tasks: HashMap<String, Task> = get_user_tasks(&conn, &user.id)
+        .await?
+        .into_iter()
+        .map(Task::from)
+        .filter_map(|task| Some((task.id.clone()?, task)))
+        .collect();
+    let children = children_by_parent(get_subtask_links(&conn, &user.id).await?);
+    build_tree(&root_id.to_string(), &mut tasks, &children)
+        .map(Json)
+        .ok_or(AppError::NotFound(format!("Task '{}'", id)))
+}
+
+// Tasks are taken out of the map as they are placed, so each shows up only once in the tree
+fn build_tree(
+    id: &str,
+    tasks: &mut HashMap<String, Task>,
+    children: &HashMap<String, Vec<RecordId>>,
+) -> Option<TaskTree> {
+    let task = tasks.remove(id)?;
+    let mut subtasks: Vec<TaskTree> = children
+        .get(id)
+        .into_iter()
+        .flatten()
+        .filter_map(|child| build_tree(&child.to_string(), tasks, children))
+        .collect();
+    subtasks.sort_by(|a, b| {
+        a.task
+            .due_by
+            .cmp(&b.task.due_by)
+            .then_with(|| a.task.name.cmp(&b.task.name))
+    });
+    let progress = (!subtasks.is_empty()).then(|| {
+        subtasks
+            .iter()
+            .fold(TaskProgress::default(), |mut progress, subtask| {
+                let below = subtask.progress.unwrap_or_default();
+                progress.done += subtask.task.is_done as u32 + below.done;
+                progress.total += 1 + below.total;
+                progress
+            })
+    });
+    Some(TaskTree {
+        task,
+        progress,
+        subtasks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+    use crate::test_db::{test_db, test_user};
+
+    fn task(id: &str, is_done: bool) -> Task {
+        Task {
+            id: Some(id.to_string()),
+            name: id.to_string(),
+            description: String::new(),
+            due_by: NaiveDateTime::parse_from_str("2025-03-14 17:00", "%Y-%m-%d %H:%M").unwrap(),
+            imp_lvl: 5,
+            req_time: NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+            time_alloted: NaiveTime::MIN,
+            is_done,
+            auto_complete: false,
+            recurrence: None,
+            tags: Vec::new(),
+            reminders: Vec::new(),
+        }
+    }
+
+    fn step(name: &str) -> NewSubtask {
+        NewSubtask {
+            name: name.to_string(),
+            description: String::new(),
+            due_by: None,
+            imp_lvl: None,
+            req_time: None,
+        }
+    }
+
+    async fn add_task(conn: &Surreal<Db>, owner: &RecordId, auto_complete: bool) -> RecordId {
+        let mut new_task = task("Tasks:new", false);
+        new_task.id = None;
+        new_task.auto_complete = auto_complete;
+        let created: Option<TaskDB> = conn
+            .create("Tasks")
+            .content(TaskDB::new(new_task, owner.clone()))
+            .await
+            .unwrap();
+        created.and_then(|task| task.id).unwrap()
+    }
+
+    async fn set_done(conn: &Surreal<Db>, id: &str, is_done: bool) {
+        let record_id: RecordId = id.parse().unwrap();
+        let _: Option<TaskDB> = conn
+            .update(record_id.clone())
+            .merge(serde_json::json!({ "is_done": is_done }))
+            .await
+            .unwrap();
+        let parent = parent_of(conn, &record_id).await.unwrap().unwrap();
+        roll_up_completion(conn, parent).await.unwrap();
+    }
+
+    async fn is_done(conn: &Surreal<Db>, id: &RecordId) -> bool {
+        let task: Option<TaskDB> = conn.select(id.clone()).await.unwrap();
+        task.unwrap().is_done
+    }
+
+    #[test]
+    fn progress_counts_subtasks_at_every_depth() {
+        let mut tasks: HashMap<String, Task> = [
+            task("Tasks:root", false),
+            task("Tasks:a", true),
+            task("Tasks:b", false),
+            task("Tasks:b1", true),
+            task("Tasks:b2", false),
+        ]
+        .into_iter()
+        .map(|task| (task.id.clone().unwrap(), task))
+        .collect();
+        let link = |parent: &str, child: &str| SubtaskLink {
+            parent: RecordId::from_table_key("Tasks", parent),
+            child: RecordId::from_table_key("Tasks", child),
+        };
+        // The last link points at a task that isn't in the map, it is left out
+        let children = children_by_parent(vec![
+            link("root", "a"),
+            link("root", "b"),
+            link("b", "b1"),
+            link("b", "b2"),
+            link("b", "other"),
+        ]);
+
+        let tree = build_tree("Tasks:root", &mut tasks, &children).unwrap();
+        let progress = tree.progress.unwrap();
+        assert_eq!(progress, TaskProgress { done: 2, total: 4 });
+        assert_eq!(progress.percent(), 50);
+        let b = &tree.subtasks[1];
+        assert_eq!(b.task.name, "Tasks:b");
+        assert_eq!(b.subtasks.len(), 2);
+        assert_eq!(b.progress.unwrap().percent(), 50);
+        assert_eq!(tree.subtasks[0].progress, None);
+        assert_eq!(TaskProgress::default().percent(), 100);
+        assert!(tasks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn parents_complete_and_reopen_with_their_subtasks() {
+        let conn = test_db().await;
+        let owner = test_user(&conn, "alice").await;
+        let parent = add_task(&conn, &owner, true).await;
+        let first = create_subtask(&conn, &owner, &parent.to_string(), step("Draft"))
+            .await
+            .unwrap();
+        let second = create_subtask(&conn, &owner, &parent.to_string(), step("Proofread"))
+            .await
+            .unwrap();
+        let (first, second) = (first.id.unwrap(), second.id.unwrap());
+
+        set_done(&conn, &first, true).await;
+        assert!(!is_done(&conn, &parent).await);
+        set_done(&conn, &second, true).await;
+        assert!(is_done(&conn, &parent).await);
+        set_done(&conn, &first, false).await;
+        assert!(!is_done(&conn, &parent).await);
+    }
+
+    #[tokio::test]
+    async fn tasks_of_other_users_take_no_subtasks() {
+        let conn = test_db().await;
+        let alice = test_user(&conn, "alice").await;
+        let bob = test_user(&conn, "bob").await;
+        let bobs_task = add_task(&conn, &bob, false).await;
+
+        assert!(matches!(
+            create_subtask(&conn, &alice, &bobs_task.to_string(), step("Sneak in")).await,
+            Err(AppError::NotFound(_))
+        ));
+        assert!(get_subtask_links(&conn, &bob).await.unwrap().is_empty());
+        assert_eq!(get_user_tasks(&conn, &bob).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::HashSet;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+use surrealdb::{RecordId, Surreal, engine::local::Db};
+use taskflow_types::{Tag, TagKind};
+
+use crate::auth_helper::AuthUser;
+use crate::error::{AppError, AppResult};
+use crate::extract::{Json, Path, Query};
+use crate::schedule_helper::refresh_schedules;
+use crate::task_helper::get_owned_task_db;
+
+// Tags, projects and contexts share one table, tasks point at them through the `tagged`
+// relation, stored as task->tagged->tag
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TagDB {
+    id: Option<RecordId>,
+    name: String,
+    kind: TagKind,
+    color: Option<String>,
+    owner: RecordId,
+}
+
+impl TagDB {
+    pub fn new(api: Tag, owner: RecordId) -> Self {
+        Self {
+            id: api.id.and_then(|s| s.parse().ok()),
+            name: api.name,
+            kind: api.kind,
+            color: api.color,
+            owner,
+        }
+    }
+}
+
+// Conversions
+impl From<TagDB> for Tag {
+    fn from(db: TagDB) -> Self {
+        Self {
+            id: db.id.map(|rid| rid.to_string()),
+            name: db.name,
+            kind: db.kind,
+            color: db.color,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TagListQuery {
+    #[serde(default)]
+    pub kind: Option<TagKind>,
+}
+
+// Ids may be given with or without the table name, ids of other tables match no tag
+pub fn tag_record_id(id: &str) -> RecordId {
+    RecordId::from_table_key("tag", id.strip_prefix("tag:").unwrap_or(id))
+}
+
+// Tags of other users are reported as missing
+async fn get_owned_tag(
+    conn: &Surreal<Db>,
+    id: &str,
+    owner: &RecordId,
+) -> AppResult<(RecordId, TagDB)> {
+    let record_id = tag_record_id(id);
+    let tag_db: Option<TagDB> = conn.select(record_id.clone()).await?;
+    tag_db
+        .filter(|tag| &tag.owner == owner)
+        .map(|tag| (record_id, tag))
+        .ok_or(AppError::NotFound(format!("Tag '{}'", id)))
+}
+
+// The projects and contexts a schedule is limited to, every one of them has to belong to the user
+pub async fn resolve_focus(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    ids: &[&str],
+) -> AppResult<Vec<RecordId>> {
+    let mut focus = Vec::new();
+    for id in ids {
+        let (record_id, tag_db) = get_owned_tag(conn, id, owner).await?;
+        if tag_db.kind == TagKind::Tag {
+            return Err(AppError::BadRequest(format!(
+                "A day can only be limited to projects and contexts, '{}' is a tag",
+                tag_db.name
+            )));
+        }
+        if !focus.contains(&record_id) {
+            focus.push(record_id);
+        }
+    }
+    Ok(focus)
+}
+
+// Names are unique per kind, a project and a context may still share one
+async fn ensure_unique_name(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    tag: &Tag,
+    except: Option<&RecordId>,
+) -> AppResult<()> {
+    let mut same_name_dbresp = conn
+        .query("SELECT VALUE id FROM tag WHERE owner = $owner AND kind = $kind AND name = $name")
+        .bind(("owner", owner.clone()))
+        .bind(("kind", tag.kind))
+        .bind(("name", tag.name.clone()))
+        .await?;
+    let same_name: Vec<RecordId> = same_name_dbresp.take(0)?;
+    if same_name.iter().any(|id| Some(id) != except) {
+        return Err(AppError::Conflict(format!(
+            "There already is a {} named '{}'",
+            tag.kind.as_str(),
+            tag.display_name()
+        )));
+    }
+    Ok(())
+}
+
+async fn tags_of(conn: &Surreal<Db>, task: &RecordId) -> AppResult<Vec<Tag>> {
+    let mut tags_dbresp = conn
+        .query(
+            "SELECT * FROM tag WHERE id IN (SELECT VALUE out FROM tagged WHERE in = $task)
+             ORDER BY kind, name",
+        )
+        .bind(("task", task.clone()))
+        .await?;
+    let tags: Vec<TagDB> = tags_dbresp.take(0)?;
+    Ok(tags.into_iter().map(Tag::from).collect())
+}
+
+pub async fn get_user_tags(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    kind: Option<TagKind>,
+) -> AppResult<Vec<Tag>> {
+    let filter = if kind.is_some() {
+        " AND kind = $kind"
+    } else {
+        ""
+    };
+    let mut tags_dbresp = conn
+        .query(format!(
+            "SELECT * FROM tag WHERE owner = $owner{} ORDER BY kind, name",
+            filter
+        ))
+        .bind(("owner", owner.clone()))
+        .bind(("kind", kind))
+        .await?;
+    let tags: Vec<TagDB> = tags_dbresp.take(0)?;
+    Ok(tags.into_iter().map(Tag::from).collect())
+}
+
+pub async fn list_tags(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Query(query): Query<TagListQuery>,
+) -> AppResult<impl IntoResponse> {
+    Ok(Json(get_user_tags(&conn, &user.id, query.kind).await?))
+}
+
+pub async fn add_tag(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Json(new_tag): Json<Tag>,
+) -> AppResult<impl IntoResponse> {
+    let tag = create_tag(&conn, &user.id, new_tag).await?;
+    Ok((StatusCode::CREATED, Json(tag)))
+}
+
+async fn create_tag(conn: &Surreal<Db>, owner: &RecordId, new_tag: Tag) -> AppResult<Tag> {
+    let new_tag = new_tag.normalized();
+    new_tag.validate().map_err(AppError::BadRequest)?;
+    ensure_unique_name(conn, owner, &new_tag, None).await?;
+    let mut tag_db = TagDB::new(new_tag, owner.clone());
+    tag_db.id = None;
+    let created: Option<TagDB> = conn.create("tag").content(tag_db).await?;
+    created.map(Tag::from).ok_or(AppError::Internal(
+        "Created tag was not returned".to_string(),
+    ))
+}
+
+pub async fn get_tag(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let (_, tag_db) = get_owned_tag(&conn, &id, &user.id).await?;
+    Ok(Json(Tag::from(tag_db)))
+}
+
+pub async fn update_tag(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(tag): Json<Tag>,
+) -> AppResult<impl IntoResponse> {
+    let tag = tag.normalized();
+    tag.validate().map_err(AppError::BadRequest)?;
+    let (record_id, before) = get_owned_tag(&conn, &id, &user.id).await?;
+    ensure_unique_name(&conn, &user.id, &tag, Some(&record_id)).await?;
+    // Turning a tag into a project must not leave a task in two projects
+    if tag.kind == TagKind::Project && before.kind != TagKind::Project {
+        let mut clashing_dbresp = conn
+            .query(
+                "SELECT VALUE id FROM Tasks WHERE $tag IN ->tagged->tag
+                 AND count(->tagged->(tag WHERE kind = 'project')) > 0",
+            )
+            .bind(("tag", record_id.clone()))
+            .await?;
+        let clashing: Vec<RecordId> = clashing_dbresp.take(0)?;
+        if !clashing.is_empty() {
+            return Err(AppError::Conflict(format!(
+                "{} task(s) filed under '{}' already belong to a project",
+                clashing.len(),
+                before.name
+            )));
+        }
+    }
+    let mut tag_db = TagDB::new(tag, user.id.clone());
+    tag_db.id = Some(record_id.clone());
+    let updated: Option<TagDB> = conn.update(record_id).content(tag_db).await?;
+    let tag = updated
+        .map(Tag::from)
+        .ok_or(AppError::NotFound(format!("Tag '{}'", id)))?;
+    Ok(Json(tag))
+}
+
+// Tasks keep existing without the tag, and committed days that were limited to it forget it
+pub async fn delete_tag(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let (record_id, _) = get_owned_tag(&conn, &id, &user.id).await?;
+    conn.query(
+        "DELETE tagged WHERE out = $tag;
+         UPDATE committed_day SET focus -= $tag WHERE owner = $owner AND $tag IN focus;
+         UPDATE day_schedule SET focus -= $tag WHERE owner = $owner AND $tag IN focus;
+         DELETE $tag;",
+    )
+    .bind(("tag", record_id))
+    .bind(("owner", user.id.clone()))
+    .await?
+    .check()?;
+    refresh_schedules(&conn, &user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_task_tags(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let (record_id, _) = get_owned_task_db(&conn, &id, &user.id).await?;
+    Ok(Json(tags_of(&conn, &record_id).await?))
+}
+
+// Replaces everything the task is filed under with the given tag ids
+pub async fn set_task_tags(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(tag_ids): Json<Vec<String>>,
+) -> AppResult<impl IntoResponse> {
+    Ok(Json(file_task(&conn, &user.id, &id, &tag_ids).await?))
+}
+
+async fn file_task(
+    conn: &Surreal<Db>,
+    owner: &RecordId,
+    id: &str,
+    tag_ids: &[String],
+) -> AppResult<Vec<Tag>> {
+    let (record_id, _) = get_owned_task_db(conn, id, owner).await?;
+    let mut tags = Vec::new();
+    let mut seen = HashSet::new();
+    let mut projects = 0;
+    for tag_id in tag_ids {
+        let (tag, tag_db) = get_owned_tag(conn, tag_id, owner).await?;
+        if seen.insert(tag.clone()) {
+            projects += (tag_db.kind == TagKind::Project) as u32;
+            tags.push(tag);
+        }
+    }
+    if projects > 1 {
+        return Err(AppError::BadRequest(
+            "A task can belong to only one project".to_string(),
+        ));
+    }
+    conn.query(
+        "DELETE tagged WHERE in = $task;
+         FOR $tag IN $tags { RELATE $task->tagged->$tag; };",
+    )
+    .bind(("task", record_id.clone()))
+    .bind(("tags", tags))
+    .await?
+    .check()?;
+    // Days limited to a project or context may take the task in or drop it now
+    refresh_schedules(conn, owner).await?;
+    tags_of(conn, &record_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_helper::{TaskDB, task_record_id};
+    use crate::test_db::{test_db, test_user};
+    use chrono::{NaiveDateTime, NaiveTime};
+    use taskflow_types::Task;
+
+    async fn add(conn: &Surreal<Db>, owner: &RecordId, name: &str, kind: TagKind) -> String {
+        let tag = Tag {
+            id: None,
+            name: name.to_string(),
+            kind,
+            color: None,
+        };
+        create_tag(conn, owner, tag).await.unwrap().id.unwrap()
+    }
+
+    async fn add_task(conn: &Surreal<Db>, owner: &RecordId, name: &str) -> String {
+        let task = Task {
+            id: None,
+            name: name.to_string(),
+            description: String::new(),
+            due_by: NaiveDateTime::parse_from_str("2025-03-14 17:00", "%Y-%m-%d %H:%M").unwrap(),
+            imp_lvl: 5,
+            req_time: NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: None,
+            tags: Vec::new(),
+            reminders: Vec::new(),
+        };
+        let created: Option<TaskDB> = conn
+            .create("Tasks")
+            .content(TaskDB::new(task, owner.clone()))
+            .await
+            .unwrap();
+        created.and_then(|task| task.id).unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn tasks_belong_to_one_project_at_most() {
+        let conn = test_db().await;
+        let owner = test_user(&conn, "ada").await;
+        let thesis = add(&conn, &owner, "Thesis", TagKind::Project).await;
+        let garden = add(&conn, &owner, "Garden", TagKind::Project).await;
+        let office = add(&conn, &owner, "office", TagKind::Context).await;
+        let urgent = add(&conn, &owner, "urgent", TagKind::Tag).await;
+        let task = add_task(&conn, &owner, "Draft").await;
+
+        let filed = file_task(
+            &conn,
+            &owner,
+            &task,
+            &[thesis.clone(), office.clone(), urgent.clone()],
+        )
+        .await
+        .unwrap();
+        assert_eq!(filed.len(), 3);
+        assert!(matches!(
+            file_task(&conn, &owner, &task, &[thesis.clone(), garden.clone()]).await,
+            Err(AppError::BadRequest(_))
+        ));
+        // The refused change left the task as it was
+        let kept: Vec<String> = tags_of(&conn, &task_record_id(&task))
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|tag| tag.id)
+            .collect();
+        assert_eq!(kept.len(), 3);
+        assert!(!kept.contains(&garden));
+
+        // Moving the task to another project is fine
+        let moved = file_task(&conn, &owner, &task, std::slice::from_ref(&garden))
+            .await
+            .unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].id.as_ref(), Some(&garden));
+    }
+
+    #[tokio::test]
+    async fn tag_names_are_unique_per_owner_and_kind() {
+        let conn = test_db().await;
+        let ada = test_user(&conn, "ada").await;
+        let bob = test_user(&conn, "bob").await;
+        add(&conn, &ada, "office", TagKind::Context).await;
+
+        let again = Tag {
+            id: None,
+            name: "@office".to_string(),
+            kind: TagKind::Context,
+            color: None,
+        };
+        assert!(matches!(
+            create_tag(&conn, &ada, again.clone()).await,
+            Err(AppError::Conflict(_))
+        ));
+        // A project of the same name, and another user's context, are different tags
+        add(&conn, &ada, "office", TagKind::Project).await;
+        create_tag(&conn, &bob, again).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn days_focus_on_projects_and_contexts_only() {
+        let conn = test_db().await;
+        let ada = test_user(&conn, "ada").await;
+        let bob = test_user(&conn, "bob").await;
+        let thesis = add(&conn, &ada, "Thesis", TagKind::Project).await;
+        let office = add(&conn, &ada, "office", TagKind::Context).await;
+        let urgent = add(&conn, &ada, "urgent", TagKind::Tag).await;
+
+        let focus = resolve_focus(&conn, &ada, &[&thesis, &office, &thesis])
+            .await
+            .unwrap();
+        assert_eq!(focus.len(), 2);
+        assert!(matches!(
+            resolve_focus(&conn, &ada, &[&thesis, &urgent]).await,
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            resolve_focus(&conn, &bob, &[&thesis]).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
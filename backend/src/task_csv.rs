@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+
+use chrono::NaiveTime;
+use csv::{ReaderBuilder, Trim, WriterBuilder};
+use taskflow_types::{CsvColumn, CsvImportQuery, Task, TaskColumn, parse_due_by, parse_req_time};
+
+// Rows that don't say how important a task is or how long it takes
+const DEFAULT_IMP_LVL: u8 = 5;
+const DEFAULT_REQ_HOURS: u32 = 1;
+
+fn cell(task: &Task, column: TaskColumn, tag_names: &HashMap<String, String>) -> String {
+    match column {
+        TaskColumn::Id => task.id.clone().unwrap_or_default(),
+        TaskColumn::Name => task.name.clone(),
+        TaskColumn::Description => task.description.clone(),
+        TaskColumn::DueBy => task.due_by.format("%Y-%m-%d %H:%M").to_string(),
+        TaskColumn::ImpLvl => task.imp_lvl.to_string(),
+        TaskColumn::ReqTime => task.req_time.format("%H:%M").to_string(),
+        TaskColumn::TimeAlloted => task.time_alloted.format("%H:%M").to_string(),
+        TaskColumn::IsDone => task.is_done.to_string(),
+        TaskColumn::Recurrence => task
+            .recurrence
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        TaskColumn::Tags => task
+            .tags
+            .iter()
+            .filter_map(|id| tag_names.get(id).map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+// The tasks as CSV with a header row of the column names. Tags are written by name.
+pub fn write_tasks(
+    tasks: &[Task],
+    columns: &[TaskColumn],
+    tag_names: &HashMap<String, String>,
+) -> Result<String, String> {
+    let mut writer = WriterBuilder::new().from_writer(Vec::new());
+    let write_err = |err: csv::Error| format!("Couldn't write the CSV: {}", err);
+    writer
+        .write_record(columns.iter().map(|column| column.as_str()))
+        .map_err(write_err)?;
+    for task in tasks {
+        writer
+            .write_record(columns.iter().map(|column| cell(task, *column, tag_names)))
+            .map_err(write_err)?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|err| format!("Couldn't write the CSV: {}", err))?;
+    String::from_utf8(bytes).map_err(|err| format!("Couldn't write the CSV: {}", err))
+}
+
+// Cells copied out of a spreadsheet are separated by tabs, and spreadsheets set to a language
+// with decimal commas save with semicolons
+fn detect_delimiter(header_line: &str) -> u8 {
+    [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|delimiter| {
+            let count = header_line.bytes().filter(|byte| byte == delimiter).count();
+            // Ties go to the comma
+            (count, *delimiter == b',')
+        })
+        .unwrap_or(b',')
+}
+
+// The rows of an imported file, each with its line number and the task read from it
+pub struct CsvRows {
+    pub columns: Vec<CsvColumn>,
+    pub rows: Vec<(u64, Result<Task, String>)>,
+}
+
+// Which column every header is read into. Mapped headers win over recognized ones, and only the
+// first header of a column is used.
+fn map_headers(headers: &[String], query: &CsvImportQuery) -> Result<Vec<CsvColumn>, String> {
+    let mapping = query.mapping()?;
+    for (header, _) in &mapping {
+        if !headers.iter().any(|name| name.eq_ignore_ascii_case(header)) {
+            return Err(format!("The file has no column '{}'", header));
+        }
+    }
+    let mut columns: Vec<CsvColumn> = Vec::with_capacity(headers.len());
+    for header in headers {
+        let column = mapping
+            .iter()
+            .find(|(mapped, _)| mapped.eq_ignore_ascii_case(header))
+            .map(|(_, column)| *column)
+            .or_else(|| {
+                TaskColumn::from_header(header)
+                    .filter(|column| !mapping.iter().any(|(_, mapped)| mapped == column))
+            })
+            .filter(|column| !columns.iter().any(|taken| taken.column == Some(*column)));
+        columns.push(CsvColumn {
+            header: header.clone(),
+            column,
+        });
+    }
+    for required in [TaskColumn::Name, TaskColumn::DueBy] {
+        if !columns.iter().any(|column| column.column == Some(required)) {
+            return Err(format!(
+                "No column was recognized as {}, name one with map=<header>:{}",
+                required.as_str(),
+                required.as_str()
+            ));
+        }
+    }
+    Ok(columns)
+}
+
+fn read_task(cells: &HashMap<TaskColumn, &str>, query: &CsvImportQuery) -> Result<Task, String> {
+    let cell = |column| cells.get(&column).copied().unwrap_or_default();
+    let due_by = match cell(TaskColumn::DueBy) {
+        "" => return Err("The due date is empty".to_string()),
+        due_by => parse_due_by(due_by, query.month_first)?,
+    };
+    let imp_lvl = match cell(TaskColumn::ImpLvl) {
+        "" => DEFAULT_IMP_LVL,
+        imp_lvl => imp_lvl
+            .parse()
+            .map_err(|_| format!("'{}' is not an importance from 1 to 10", imp_lvl))?,
+    };
+    let req_time = match cell(TaskColumn::ReqTime) {
+        "" => NaiveTime::from_hms_opt(DEFAULT_REQ_HOURS, 0, 0).unwrap(),
+        req_time => parse_req_time(req_time)?,
+    };
+    let task = Task {
+        id: None,
+        name: cell(TaskColumn::Name).to_string(),
+        description: cell(TaskColumn::Description).to_string(),
+        due_by,
+        imp_lvl,
+        req_time,
+        time_alloted: NaiveTime::MIN,
+        is_done: false,
+        auto_complete: false,
+        recurrence: None,
+        tags: Vec::new(),
+        reminders: Vec::new(),
+    };
+    task.validate()?;
+    Ok(task)
+}
+
+// Reads a CSV file with a header row into tasks. Errors of the whole file, like missing
+// columns, are returned as Err, the ones of single rows stay with their row.
+pub fn read_tasks(text: &str, query: &CsvImportQuery) -> Result<CsvRows, String> {
+    // Excel starts UTF-8 files with a byte order mark
+    let text = text.trim_start_matches('\u{feff}');
+    let header_line = text.lines().next().unwrap_or_default();
+    if header_line.trim().is_empty() {
+        return Err("The file is empty, its first line has to name the columns".to_string());
+    }
+    let mut reader = ReaderBuilder::new()
+        .delimiter(detect_delimiter(header_line))
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(text.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|err| format!("Couldn't read the header row: {}", err))?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let columns = map_headers(&headers, query)?;
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                let line = err.position().map_or(0, |position| position.line());
+                rows.push((line, Err(format!("Couldn't read the row: {}", err))));
+                continue;
+            }
+        };
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let line = record.position().map_or(0, |position| position.line());
+        let cells: HashMap<TaskColumn, &str> = columns
+            .iter()
+            .zip(record.iter())
+            .filter_map(|(column, value)| Some((column.column?, value)))
+            .collect();
+        rows.push((line, read_task(&cells, query)));
+    }
+    Ok(CsvRows { columns, rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+    use taskflow_types::Recurrence;
+
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn task(name: &str) -> Task {
+        Task {
+            id: Some(format!("Tasks:{}", name.to_lowercase())),
+            name: name.to_string(),
+            description: "Needs \"quotes\", commas\nand a second line".to_string(),
+            due_by: at("2025-03-14 17:00"),
+            imp_lvl: 8,
+            req_time: NaiveTime::from_hms_opt(1, 30, 0).unwrap(),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: Some(Recurrence::Daily),
+            tags: vec!["tag:work".to_string()],
+            reminders: Vec::new(),
+        }
+    }
+
+    fn tasks_of(rows: CsvRows) -> Vec<Task> {
+        rows.rows
+            .into_iter()
+            .map(|(line, task)| task.unwrap_or_else(|err| panic!("line {}: {}", line, err)))
+            .collect()
+    }
+
+    #[test]
+    fn exports_read_back() {
+        let tag_names = HashMap::from([("tag:work".to_string(), "#work".to_string())]);
+        let text = write_tasks(
+            &[task("Report"), task("Slides")],
+            &TaskColumn::ALL,
+            &tag_names,
+        )
+        .unwrap();
+        assert!(text.starts_with(
+            "id,name,description,due_by,imp_lvl,req_time,time_alloted,is_done,recurrence,tags\n"
+        ));
+        assert!(text.contains(",#work\n"));
+
+        let rows = read_tasks(&text, &CsvImportQuery::default()).unwrap();
+        let ignored: Vec<&str> = rows
+            .columns
+            .iter()
+            .filter(|column| column.column.is_none())
+            .map(|column| column.header.as_str())
+            .collect();
+        assert_eq!(
+            ignored,
+            ["id", "time_alloted", "is_done", "recurrence", "tags"]
+        );
+        let read = tasks_of(rows);
+        assert_eq!(read.len(), 2);
+        let original = task("Slides");
+        assert_eq!(read[1].name, original.name);
+        assert_eq!(read[1].description, original.description);
+        assert_eq!(read[1].due_by, original.due_by);
+        assert_eq!(read[1].imp_lvl, original.imp_lvl);
+        assert_eq!(read[1].req_time, original.req_time);
+    }
+
+    #[test]
+    fn spreadsheet_headers_and_delimiters() {
+        let text = "\u{feff}Title;Deadline;Priority;Estimate;Owner\n\
+                    Taxes;15.04.2025;9;2h;Sam\n\
+                    \n\
+                    Laundry;2025-04-16 18:00;;;\n";
+        let read = tasks_of(read_tasks(text, &CsvImportQuery::default()).unwrap());
+        assert_eq!(read[0].due_by, at("2025-04-15 23:59"));
+        assert_eq!(read[0].imp_lvl, 9);
+        assert_eq!(read[0].req_time, NaiveTime::from_hms_opt(2, 0, 0).unwrap());
+        assert_eq!(read[1].imp_lvl, DEFAULT_IMP_LVL);
+        assert_eq!(read[1].req_time, NaiveTime::from_hms_opt(1, 0, 0).unwrap());
+
+        let pasted = "Aufgabe\tWann\nTaxes\t04/15/2025 09:00\n";
+        let query = CsvImportQuery {
+            month_first: true,
+            map: Some("Aufgabe:name,Wann:due_by".to_string()),
+            ..CsvImportQuery::default()
+        };
+        let read = tasks_of(read_tasks(pasted, &query).unwrap());
+        assert_eq!(read[0].name, "Taxes");
+        assert_eq!(read[0].due_by, at("2025-04-15 09:00"));
+    }
+
+    #[test]
+    fn bad_rows_are_rejected_with_their_line() {
+        let text = "name,due_by,imp_lvl,req_time\n\
+                    Fine,2025-04-15,5,30\n\
+                    ,2025-04-15,5,30\n\
+                    No date,,5,30\n\
+                    Too important,2025-04-15,11,30\n\
+                    Odd time,2025-04-15,5,soon\n\
+                    Bad date,someday,5,30\n";
+        let rows = read_tasks(text, &CsvImportQuery::default()).unwrap();
+        let rejected: Vec<u64> = rows
+            .rows
+            .iter()
+            .filter(|(_, task)| task.is_err())
+            .map(|(line, _)| *line)
+            .collect();
+        assert_eq!(rejected, [3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn files_without_the_needed_columns_are_refused() {
+        let query = CsvImportQuery::default();
+        assert!(read_tasks("", &query).is_err());
+        assert!(
+            read_tasks("name,owner\nTaxes,Sam\n", &query)
+                .err()
+                .unwrap()
+                .contains("due_by")
+        );
+        let query = CsvImportQuery {
+            map: Some("Wann:due_by".to_string()),
+            ..CsvImportQuery::default()
+        };
+        assert!(read_tasks("name,due\nTaxes,2025-04-15\n", &query).is_err());
+    }
+}
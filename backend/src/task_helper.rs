@@ -1,37 +1,69 @@
-use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
-};
-use chrono::{NaiveDateTime, NaiveTime};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{Local, NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
 use surrealdb::{RecordId, Surreal, engine::local::Db};
+use taskflow_types::{
+    Recurrence, Reminder, SortOrder, Task, TaskPage, TaskPatch, TaskQuery, TaskSort,
+};
+
+use crate::auth_helper::AuthUser;
+use crate::error::{AppError, AppResult};
+use crate::extract::{Json, Path, Query};
+use crate::schedule_helper::refresh_schedules;
+use crate::subtask_helper::{descendants_of, parent_of, roll_up_completion};
+use crate::tag_helper::tag_record_id;
+
+// Every read of a task also collects the tags it is filed under
+const TASK_FIELDS: &str = "*, ->tagged->tag AS tags";
 
 // Separate struct for database with RecordId
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TaskDB {
-    id: Option<RecordId>,
+    pub id: Option<RecordId>,
     name: String,
     description: String,
     pub due_by: NaiveDateTime,
     imp_lvl: u8,
     req_time: NaiveTime,
     time_alloted: NaiveTime,
-    is_done: bool,
+    pub is_done: bool,
+    // Records from before subtasks existed don't have it
+    #[serde(default)]
+    pub auto_complete: bool,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    // Records from before accounts existed have none until the first sign up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<RecordId>,
+    // Read through the `tagged` relation, never stored on the task itself
+    #[serde(default, skip_serializing)]
+    pub tags: Vec<RecordId>,
+    #[serde(default)]
+    pub reminders: Vec<Reminder>,
+    // UID of the to-do the task was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ical_uid: Option<String>,
 }
 
-// API struct with String ID for frontend
-#[derive(Clone, Serialize, Deserialize)]
-pub struct Task {
-    pub id: Option<String>,
-    pub name: String,
-    description: String,
-    pub due_by: NaiveDateTime,
-    pub imp_lvl: u8,
-    pub req_time: NaiveTime,
-    pub time_alloted: NaiveTime,
-    pub is_done: bool,
+impl TaskDB {
+    pub fn new(api: Task, owner: RecordId) -> Self {
+        Self {
+            id: api.id.and_then(|s| s.parse().ok()),
+            name: api.name,
+            description: api.description,
+            due_by: api.due_by,
+            imp_lvl: api.imp_lvl,
+            req_time: api.req_time,
+            time_alloted: api.time_alloted,
+            is_done: api.is_done,
+            auto_complete: api.auto_complete,
+            recurrence: api.recurrence,
+            owner: Some(owner),
+            tags: api.tags.iter().filter_map(|s| s.parse().ok()).collect(),
+            reminders: api.reminders,
+            ical_uid: None,
+        }
+    }
 }
 
 // Conversions
@@ -46,87 +78,539 @@ impl From<TaskDB> for Task {
             req_time: db.req_time,
             time_alloted: db.time_alloted,
             is_done: db.is_done,
+            auto_complete: db.auto_complete,
+            recurrence: db.recurrence,
+            tags: db.tags.iter().map(|rid| rid.to_string()).collect(),
+            reminders: db.reminders,
         }
     }
 }
 
-impl From<Task> for TaskDB {
-    fn from(api: Task) -> Self {
-        Self {
-            id: api.id.and_then(|s| s.parse().ok()),
-            name: api.name,
-            description: api.description,
-            due_by: api.due_by,
-            imp_lvl: api.imp_lvl,
-            req_time: api.req_time,
-            time_alloted: api.time_alloted,
-            is_done: api.is_done,
-        }
-    }
+// Ids may be given with or without the table name. The id of a record in another table is taken
+// as a key of this one, so it matches no task instead of reading someone else's record.
+pub fn task_record_id(id: &str) -> RecordId {
+    RecordId::from_table_key("Tasks", id.strip_prefix("Tasks:").unwrap_or(id))
+}
+
+// Tasks of other users are reported as missing, so ids can't be used to probe for them
+pub async fn get_owned_task_db(
+    conn: &Surreal<Db>,
+    id: &str,
+    owner: &RecordId,
+) -> AppResult<(RecordId, TaskDB)> {
+    let record_id = task_record_id(id);
+    let task_db = select_task(conn, &record_id).await?;
+    task_db
+        .filter(|task| task.owner.as_ref() == Some(owner))
+        .map(|task| (record_id, task))
+        .ok_or(AppError::NotFound(format!("Task '{}'", id)))
+}
+
+pub async fn select_task(conn: &Surreal<Db>, record_id: &RecordId) -> AppResult<Option<TaskDB>> {
+    let mut task_dbresp = conn
+        .query(format!("SELECT {TASK_FIELDS} FROM $task"))
+        .bind(("task", record_id.clone()))
+        .await?;
+    let tasks: Vec<TaskDB> = task_dbresp.take(0)?;
+    Ok(tasks.into_iter().next())
+}
+
+async fn get_owned_task(conn: &Surreal<Db>, id: &str, owner: &RecordId) -> AppResult<RecordId> {
+    Ok(get_owned_task_db(conn, id, owner).await?.0)
 }
 
-pub async fn get_task(State(conn): State<Surreal<Db>>) -> impl IntoResponse {
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let values: Vec<TaskDB> = conn.select("Tasks").await.unwrap();
+pub async fn get_user_tasks(conn: &Surreal<Db>, owner: &RecordId) -> AppResult<Vec<TaskDB>> {
+    let mut tasks_dbresp = conn
+        .query(format!(
+            "SELECT {TASK_FIELDS} FROM Tasks WHERE owner = $owner"
+        ))
+        .bind(("owner", owner.clone()))
+        .await?;
+    let tasks: Vec<TaskDB> = tasks_dbresp.take(0)?;
+    Ok(tasks)
+}
+
+// Every task of the user in one array, only used by the deprecated /get_tasks
+pub async fn get_task(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+) -> AppResult<impl IntoResponse> {
+    let values = get_user_tasks(&conn, &user.id).await?;
     let task: Vec<Task> = values.into_iter().map(Task::from).collect();
-    Json(task)
+    Ok(Json(task))
+}
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 200;
+
+pub async fn list_tasks(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Query(query): Query<TaskQuery>,
+) -> AppResult<impl IntoResponse> {
+    Ok(Json(task_page(&conn, &user.id, &query).await?))
+}
+
+async fn task_page(conn: &Surreal<Db>, owner: &RecordId, query: &TaskQuery) -> AppResult<TaskPage> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let field = query.sort.field();
+    let (direction, past) = match query.order {
+        SortOrder::Asc => ("ASC", ">"),
+        SortOrder::Desc => ("DESC", "<"),
+    };
+
+    let mut conditions = vec!["owner = $owner".to_string()];
+    if query.is_done.is_some() {
+        conditions.push("is_done = $is_done".to_string());
+    }
+    if query.due_after.is_some() {
+        conditions.push("due_by >= $due_after".to_string());
+    }
+    if query.due_before.is_some() {
+        conditions.push("due_by <= $due_before".to_string());
+    }
+    if query.min_imp.is_some() {
+        conditions.push("imp_lvl >= $min_imp".to_string());
+    }
+    match query.top_level {
+        Some(true) => conditions.push("count(<-subtask) = 0".to_string()),
+        Some(false) => conditions.push("count(<-subtask) > 0".to_string()),
+        None => {}
+    }
+    let tags: Vec<RecordId> = query.tag_ids().into_iter().map(tag_record_id).collect();
+    if !tags.is_empty() {
+        conditions.push("$tags ALLINSIDE ->tagged->tag".to_string());
+    }
+    let search = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|search| !search.is_empty())
+        .map(str::to_string);
+    // Answered by the full-text indexes, every word has to start a word of the name or of
+    // the description
+    if search.is_some() {
+        conditions.push("(name @0@ $search OR description @1@ $search)".to_string());
+    }
+    // Keyset pagination: continue right after the last task of the previous page
+    let cursor = query.cursor.as_deref().map(decode_cursor).transpose()?;
+    if cursor.is_some() {
+        conditions.push(format!(
+            "({field} {past} $cursor_value OR ({field} = $cursor_value AND id {past} $cursor_id))"
+        ));
+    }
+    let (cursor_value, cursor_id) = cursor.unzip();
+
+    let sql = format!(
+        "SELECT {TASK_FIELDS} FROM Tasks WHERE {} ORDER BY {field} {direction}, id {direction} LIMIT $limit",
+        conditions.join(" AND ")
+    );
+    // One extra row tells whether another page follows
+    let mut tasks_dbresp = conn
+        .query(sql)
+        .bind(("owner", owner.clone()))
+        .bind(("is_done", query.is_done))
+        .bind(("due_after", query.due_after))
+        .bind(("due_before", query.due_before))
+        .bind(("min_imp", query.min_imp))
+        .bind(("tags", tags))
+        .bind(("search", search))
+        .bind(("cursor_value", cursor_value))
+        .bind(("cursor_id", cursor_id))
+        .bind(("limit", limit + 1))
+        .await?;
+    let tasks_db: Vec<TaskDB> = tasks_dbresp.take(0)?;
+    let mut items: Vec<Task> = tasks_db.into_iter().map(Task::from).collect();
+    let next_cursor = if items.len() > limit as usize {
+        items.truncate(limit as usize);
+        items.last().map(|task| encode_cursor(task, query.sort))
+    } else {
+        None
+    };
+    Ok(TaskPage { items, next_cursor })
+}
+
+// A cursor is the sort value and id of the last task on a page, as hex encoded JSON
+fn encode_cursor(task: &Task, sort: TaskSort) -> String {
+    let value = match sort {
+        TaskSort::DueBy => serde_json::json!(task.due_by),
+        TaskSort::ImpLvl => serde_json::json!(task.imp_lvl),
+        TaskSort::Name => serde_json::json!(task.name),
+        TaskSort::ReqTime => serde_json::json!(task.req_time),
+    };
+    let json = serde_json::json!([value, task.id]).to_string();
+    json.bytes().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_cursor(cursor: &str) -> AppResult<(serde_json::Value, RecordId)> {
+    let invalid = || AppError::BadRequest(format!("Invalid cursor '{}'", cursor));
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+    let (value, id): (serde_json::Value, String) =
+        serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    Ok((value, task_record_id(&id)))
+}
+
+async fn create_task(conn: &Surreal<Db>, owner: &RecordId, new_task: Task) -> AppResult<Task> {
+    new_task.validate().map_err(AppError::BadRequest)?;
+    let db_task = TaskDB::new(new_task, owner.clone());
+    let created: Option<TaskDB> = conn.create("Tasks").content(db_task).await?;
+    let task = created.map(Task::from).ok_or(AppError::Internal(
+        "Created task was not returned".to_string(),
+    ))?;
+    refresh_schedules(conn, owner).await?;
+    Ok(task)
 }
 
 pub async fn add_task(
     State(conn): State<Surreal<Db>>,
+    user: AuthUser,
     Json(new_task): Json<Task>,
-) -> impl IntoResponse {
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let db_task = TaskDB::from(new_task);
-    let _: Option<TaskDB> = conn.create("Tasks").content(db_task).await.unwrap();
-    StatusCode::CREATED
+) -> AppResult<impl IntoResponse> {
+    let task = create_task(&conn, &user.id, new_task).await?;
+    Ok((StatusCode::CREATED, Json(task)))
 }
 
-pub async fn mark_done(
+pub async fn delete_task(
     State(conn): State<Surreal<Db>>,
-    Json(id): Json<String>,
-) -> impl IntoResponse {
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let record_id: RecordId = id.parse().unwrap();
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    remove_task(&conn, &user, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Subtasks go together with the task they belong to, dependencies on any of them and their tags
+// are dropped
+async fn remove_task(conn: &Surreal<Db>, user: &AuthUser, id: &str) -> AppResult<()> {
+    let record_id = get_owned_task(conn, id, &user.id).await?;
+    let parent = parent_of(conn, &record_id).await?;
+    let mut doomed = descendants_of(conn, &user.id, &record_id).await?;
+    doomed.push(record_id);
+    conn.query(
+        "DELETE subtask WHERE in IN $doomed OR out IN $doomed;
+         DELETE depends_on WHERE in IN $doomed OR out IN $doomed;
+         DELETE tagged WHERE in IN $doomed;
+         DELETE Tasks WHERE id IN $doomed;",
+    )
+    .bind(("doomed", doomed))
+    .await?
+    .check()?;
+    if let Some(parent) = parent {
+        roll_up_completion(conn, parent).await?;
+    }
+    refresh_schedules(conn, &user.id).await
+}
+
+async fn set_done(conn: &Surreal<Db>, user: &AuthUser, id: &str, is_done: bool) -> AppResult<()> {
+    let (record_id, before) = get_owned_task_db(conn, id, &user.id).await?;
+    let updated: Option<TaskDB> = conn
+        .update(record_id.clone())
+        .merge(serde_json::json!({ "is_done": is_done }))
+        .await?;
+    if let Some(updated) = updated
+        && is_done
+        && !before.is_done
+    {
+        continue_series(conn, &record_id, updated).await?;
+    }
+    if let Some(parent) = parent_of(conn, &record_id).await? {
+        roll_up_completion(conn, parent).await?;
+    }
+    refresh_schedules(conn, &user.id).await
+}
+
+// Finishing an instance of a recurring task starts the next one. The rule moves over to the new
+// instance, so reopening and finishing the old one again doesn't start a second copy.
+pub async fn continue_series(
+    conn: &Surreal<Db>,
+    record_id: &RecordId,
+    finished: TaskDB,
+) -> AppResult<()> {
+    let Some(recurrence) = finished.recurrence.clone() else {
+        return Ok(());
+    };
+    let next = TaskDB {
+        id: None,
+        due_by: recurrence.next_due(finished.due_by, Local::now().naive_local()),
+        time_alloted: NaiveTime::MIN,
+        is_done: false,
+        ..finished
+    };
+    let created: Option<TaskDB> = conn.create("Tasks").content(next).await?;
+    let next_id = created.and_then(|task| task.id);
     let _: Option<TaskDB> = conn
-        .update(record_id)
-        .merge(serde_json::json!({"is_done": true}))
-        .await
-        .unwrap();
-    StatusCode::ACCEPTED
+        .update(record_id.clone())
+        .merge(serde_json::json!({ "recurrence": null }))
+        .await?;
+    // The next instance is filed under the same tags
+    if let Some(next_id) = &next_id {
+        conn.query(
+            "FOR $tag IN (SELECT VALUE out FROM tagged WHERE in = $finished) {
+                 RELATE $next->tagged->$tag;
+             };",
+        )
+        .bind(("finished", record_id.clone()))
+        .bind(("next", next_id.clone()))
+        .await?
+        .check()?;
+    }
+    // The next instance of a recurring subtask goes under the same parent
+    if let Some(parent) = parent_of(conn, record_id).await?
+        && let Some(next_id) = next_id
+    {
+        conn.query("RELATE $parent->subtask->$child")
+            .bind(("parent", parent))
+            .bind(("child", next_id))
+            .await?
+            .check()?;
+    }
+    Ok(())
+}
+
+// Deprecated RPC-style handlers of the unversioned API, they keep its status codes and take the
+// task id as a JSON string body
+pub async fn legacy_add_task(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Json(new_task): Json<Task>,
+) -> AppResult<impl IntoResponse> {
+    create_task(&conn, &user.id, new_task).await?;
+    Ok(StatusCode::CREATED)
 }
 
-pub async fn mark_undone(
+pub async fn legacy_mark_done(
     State(conn): State<Surreal<Db>>,
+    user: AuthUser,
     Json(id): Json<String>,
-) -> impl IntoResponse {
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let record_id: RecordId = id.parse().unwrap();
-    let _: Option<TaskDB> = conn
-        .update(record_id)
-        .merge(serde_json::json!({"is_done": false}))
-        .await
-        .unwrap();
-    StatusCode::ACCEPTED
+) -> AppResult<impl IntoResponse> {
+    set_done(&conn, &user, &id, true).await?;
+    Ok(StatusCode::ACCEPTED)
 }
 
-pub async fn delete_task(
+pub async fn legacy_mark_undone(
     State(conn): State<Surreal<Db>>,
+    user: AuthUser,
     Json(id): Json<String>,
-) -> impl IntoResponse {
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let record_id: RecordId = id.parse().unwrap();
-    let _: Option<TaskDB> = conn.delete(record_id).await.unwrap();
-    StatusCode::ACCEPTED
+) -> AppResult<impl IntoResponse> {
+    set_done(&conn, &user, &id, false).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn legacy_delete_task(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Json(id): Json<String>,
+) -> AppResult<impl IntoResponse> {
+    remove_task(&conn, &user, &id).await?;
+    Ok(StatusCode::ACCEPTED)
 }
 
 pub async fn get_task_by_id(
     State(conn): State<Surreal<Db>>,
+    user: AuthUser,
     Path(id): Path<String>,
-) -> impl IntoResponse {
-    conn.use_ns("core").use_db("main").await.unwrap();
-    let (table, key) = id.split_once(':').unwrap_or(("Tasks", id.as_str()));
-    let task_db: Option<TaskDB> = conn.select((table, key)).await.unwrap();
-    let task_frontend: Option<Task> = task_db.map(Task::from);
-    Json(task_frontend)
+) -> AppResult<impl IntoResponse> {
+    let (_, task_db) = get_owned_task_db(&conn, &id, &user.id).await?;
+    Ok(Json(Task::from(task_db)))
+}
+
+pub async fn edit_task(
+    State(conn): State<Surreal<Db>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(patch): Json<TaskPatch>,
+) -> AppResult<impl IntoResponse> {
+    patch.validate().map_err(AppError::BadRequest)?;
+    let (record_id, before) = get_owned_task_db(&conn, &id, &user.id).await?;
+    let task_db: Option<TaskDB> = conn.update(record_id.clone()).merge(patch.clone()).await?;
+    let updated = task_db.ok_or(AppError::NotFound(format!("Task '{}'", id)))?;
+    if patch.is_done == Some(true) && !before.is_done {
+        continue_series(&conn, &record_id, updated).await?;
+    }
+    if patch.auto_complete == Some(true) {
+        roll_up_completion(&conn, record_id.clone()).await?;
+    } else if patch.is_done.is_some()
+        && let Some(parent) = parent_of(&conn, &record_id).await?
+    {
+        roll_up_completion(&conn, parent).await?;
+    }
+    refresh_schedules(&conn, &user.id).await?;
+    // Read back, the roll-up may have changed the task itself
+    let task = select_task(&conn, &record_id)
+        .await?
+        .map(Task::from)
+        .ok_or(AppError::NotFound(format!("Task '{}'", id)))?;
+    Ok(Json(task))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db::{test_db, test_user};
+
+    fn task(name: &str) -> Task {
+        Task {
+            id: Some(format!("Tasks:{}", name.to_lowercase())),
+            name: name.to_string(),
+            description: String::new(),
+            due_by: NaiveDateTime::parse_from_str("2025-03-14 17:00", "%Y-%m-%d %H:%M").unwrap(),
+            imp_lvl: 5,
+            req_time: NaiveTime::from_hms_opt(1, 30, 0).unwrap(),
+            time_alloted: NaiveTime::MIN,
+            is_done: false,
+            auto_complete: false,
+            recurrence: None,
+            tags: Vec::new(),
+            reminders: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn ids_of_other_tables_match_no_task() {
+        assert_eq!(
+            task_record_id("Tasks:abc"),
+            RecordId::from_table_key("Tasks", "abc")
+        );
+        assert_eq!(
+            task_record_id("abc"),
+            RecordId::from_table_key("Tasks", "abc")
+        );
+        for foreign in ["static_schedule:abc", "user:abc"] {
+            let record_id = task_record_id(foreign);
+            assert_eq!(record_id.table(), "Tasks");
+            assert_eq!(record_id, RecordId::from_table_key("Tasks", foreign));
+        }
+    }
+
+    #[test]
+    fn cursors_read_back() {
+        let task = task("Report");
+        let expected = [
+            (TaskSort::DueBy, serde_json::json!("2025-03-14T17:00:00")),
+            (TaskSort::ImpLvl, serde_json::json!(5)),
+            (TaskSort::Name, serde_json::json!("Report")),
+            (TaskSort::ReqTime, serde_json::json!("01:30:00")),
+        ];
+        for (sort, value) in expected {
+            let (decoded, id) = decode_cursor(&encode_cursor(&task, sort)).unwrap();
+            assert_eq!(decoded, value, "{:?}", sort);
+            assert_eq!(id, RecordId::from_table_key("Tasks", "report"));
+        }
+    }
+
+    #[test]
+    fn malformed_cursors_are_bad_requests() {
+        let hex =
+            |text: &str| -> String { text.bytes().map(|byte| format!("{:02x}", byte)).collect() };
+        for cursor in [
+            String::new(),
+            "zz".to_string(),
+            "5b3".to_string(),
+            "5bé".to_string(),
+            hex("not json"),
+            hex("[\"2025-03-14T17:00:00\"]"),
+            hex("[5, 7]"),
+        ] {
+            assert!(
+                matches!(decode_cursor(&cursor), Err(AppError::BadRequest(_))),
+                "{}",
+                cursor
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_neither_repeat_nor_skip_tasks_with_the_same_sort_key() {
+        let conn = test_db().await;
+        let owner = test_user(&conn, "ada").await;
+        let mut created = Vec::new();
+        for i in 0..7 {
+            let mut new_task = task(&format!("Task {}", i));
+            new_task.id = None;
+            created.push(
+                create_task(&conn, &owner, new_task)
+                    .await
+                    .unwrap()
+                    .id
+                    .unwrap(),
+            );
+        }
+        created.sort();
+
+        for sort in [TaskSort::DueBy, TaskSort::ImpLvl, TaskSort::ReqTime] {
+            for order in [SortOrder::Asc, SortOrder::Desc] {
+                let mut query = TaskQuery {
+                    sort,
+                    order,
+                    limit: Some(2),
+                    ..TaskQuery::default()
+                };
+                let mut seen = Vec::new();
+                loop {
+                    let page = task_page(&conn, &owner, &query).await.unwrap();
+                    assert!(page.items.len() <= 2);
+                    seen.extend(page.items.into_iter().filter_map(|task| task.id));
+                    match page.next_cursor {
+                        Some(cursor) => query.cursor = Some(cursor),
+                        None => break,
+                    }
+                }
+                assert_eq!(seen.len(), created.len(), "{:?} {:?}", sort, order);
+                seen.sort();
+                assert_eq!(seen, created, "{:?} {:?}", sort, order);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn search_finds_words_by_their_beginning() {
+        let conn = test_db().await;
+        let owner = test_user(&conn, "ada").await;
+        let other = test_user(&conn, "bob").await;
+        let tasks = [
+            ("Quarterly report", "", &owner),
+            ("Call Bob", "About the Report", &owner),
+            ("Café visit", "", &owner),
+            ("Groceries", "", &owner),
+            ("Report of Bob", "", &other),
+        ];
+        for (name, description, owner) in tasks {
+            let mut new_task = task(name);
+            new_task.id = None;
+            new_task.description = description.to_string();
+            create_task(&conn, owner, new_task).await.unwrap();
+        }
+
+        let search = |text: &str| {
+            let query = TaskQuery {
+                search: Some(text.to_string()),
+                sort: TaskSort::Name,
+                ..TaskQuery::default()
+            };
+            let conn = conn.clone();
+            let owner = owner.clone();
+            async move {
+                let page = task_page(&conn, &owner, &query).await.unwrap();
+                page.items
+                    .into_iter()
+                    .map(|task| task.name)
+                    .collect::<Vec<String>>()
+            }
+        };
+        assert_eq!(search("rep").await, vec!["Call Bob", "Quarterly report"]);
+        assert_eq!(
+            search("  REPORT ").await,
+            vec!["Call Bob", "Quarterly report"]
+        );
+        assert_eq!(search("quarterly rep").await, vec!["Quarterly report"]);
+        assert_eq!(search("cafe").await, vec!["Café visit"]);
+        assert!(search("port").await.is_empty());
+    }
 }
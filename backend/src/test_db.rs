@@ -0,0 +1,30 @@
+use chrono::Local;
+use surrealdb::{RecordId, Surreal, engine::local::Db, engine::local::Mem};
+
+use crate::auth_helper::UserDB;
+use crate::migrations::run_migrations;
+
+// A migrated in-memory database for tests that need SurrealDB itself
+pub async fn test_db() -> Surreal<Db> {
+    let conn = unmigrated_db().await;
+    run_migrations(&conn).await.unwrap();
+    conn
+}
+
+// An in-memory database as an older build left it, for records that predate the migrations
+pub async fn unmigrated_db() -> Surreal<Db> {
+    let conn = Surreal::new::<Mem>(()).await.unwrap();
+    conn.use_ns("test").use_db("test").await.unwrap();
+    conn
+}
+
+pub async fn test_user(conn: &Surreal<Db>, username: &str) -> RecordId {
+    let user = UserDB {
+        id: None,
+        username: username.to_string(),
+        password_hash: String::new(),
+        created_at: Local::now().naive_local(),
+    };
+    let created: Option<UserDB> = conn.create("user").content(user).await.unwrap();
+    created.and_then(|user| user.id).unwrap()
+}
@@ -0,0 +1,169 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+
+pub const MINUTES_IN_DAY: u32 = 24 * 60;
+
+// Free minutes of a single day, kept as sorted and non-overlapping [start, end) intervals
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    free: Vec<(u32, u32)>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self {
+            free: vec![(0, MINUTES_IN_DAY)],
+        }
+    }
+
+    pub fn free_minutes(&self) -> u32 {
+        self.free.iter().map(|(start, end)| end - start).sum()
+    }
+
+    // Marks [start, end) as busy, splitting any free interval it cuts through
+    pub fn reserve(&mut self, start: u32, end: u32) {
+        let end = end.min(MINUTES_IN_DAY);
+        if start >= end {
+            return;
+        }
+        let mut free = Vec::with_capacity(self.free.len() + 1);
+        for &(free_start, free_end) in &self.free {
+            if free_end <= start || free_start >= end {
+                free.push((free_start, free_end));
+                continue;
+            }
+            if free_start < start {
+                free.push((free_start, start));
+            }
+            if end < free_end {
+                free.push((end, free_end));
+            }
+        }
+        self.free = free;
+    }
+
+    // Takes up to `minutes` from the earliest free intervals and returns the slots used.
+    // Gaps shorter than `min_slot` are skipped unless they cover everything that is left.
+    // Time before minute `from` is left alone.
+    pub fn allocate_from(&mut self, from: u32, minutes: u32, min_slot: u32) -> Vec<(u32, u32)> {
+        let mut left = minutes;
+        let mut slots = Vec::new();
+        for &(free_start, free_end) in &self.free {
+            if left == 0 {
+                break;
+            }
+            let free_start = free_start.max(from);
+            if free_start >= free_end {
+                continue;
+            }
+            let len = free_end - free_start;
+            if len < min_slot && len < left {
+                continue;
+            }
+            let taken = len.min(left);
+            slots.push((free_start, free_start + taken));
+            left -= taken;
+        }
+        for &(start, end) in &slots {
+            self.reserve(start, end);
+        }
+        slots
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn minute_of_day(time: NaiveTime) -> u32 {
+    time.hour() * 60 + time.minute()
+}
+
+// Minute offsets can reach MINUTES_IN_DAY, which lands on midnight of the following day
+pub fn at_minute(date: NaiveDate, minute: u32) -> NaiveDateTime {
+    date.and_time(NaiveTime::MIN) + TimeDelta::minutes(minute as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_routines_reserve_their_union() {
+        let mut timeline = Timeline::new();
+        timeline.reserve(9 * 60, 10 * 60);
+        timeline.reserve(9 * 60 + 30, 11 * 60);
+        // Entirely inside what is already busy
+        timeline.reserve(9 * 60 + 45, 10 * 60 + 15);
+        assert_eq!(timeline.free, vec![(0, 9 * 60), (11 * 60, MINUTES_IN_DAY)]);
+        assert_eq!(timeline.free_minutes(), MINUTES_IN_DAY - 2 * 60);
+    }
+
+    #[test]
+    fn tasks_are_split_across_gaps() {
+        let mut timeline = Timeline::new();
+        timeline.reserve(0, 9 * 60);
+        timeline.reserve(10 * 60, 10 * 60 + 3);
+        timeline.reserve(10 * 60 + 5, 12 * 60);
+        // The 2 minute gap at 10:03 is too short to be worth it
+        let slots = timeline.allocate_from(0, 90, 5);
+        assert_eq!(slots, vec![(9 * 60, 10 * 60), (12 * 60, 12 * 60 + 30)]);
+        assert_eq!(
+            timeline.free_minutes(),
+            MINUTES_IN_DAY - 9 * 60 - 60 - 3 - 115 - 30
+        );
+        // A short gap is used when it covers everything that is left
+        assert_eq!(
+            timeline.allocate_from(0, 2, 5),
+            vec![(10 * 60 + 3, 10 * 60 + 5)]
+        );
+        // Time before `from` is left alone
+        assert_eq!(
+            timeline.allocate_from(13 * 60, 30, 5),
+            vec![(13 * 60, 13 * 60 + 30)]
+        );
+    }
+
+    #[test]
+    fn a_full_day_has_no_time_left() {
+        let mut timeline = Timeline::new();
+        timeline.reserve(0, 8 * 60);
+        timeline.reserve(8 * 60, MINUTES_IN_DAY);
+        assert_eq!(timeline.free_minutes(), 0);
+        assert!(timeline.allocate_from(0, 30, 5).is_empty());
+
+        // Whatever fits is booked, the rest of the task stays unbooked
+        let mut evening = Timeline::new();
+        evening.reserve(0, 23 * 60);
+        assert_eq!(
+            evening.allocate_from(0, 120, 5),
+            vec![(23 * 60, MINUTES_IN_DAY)]
+        );
+        assert_eq!(evening.free_minutes(), 0);
+    }
+
+    #[test]
+    fn midnight_boundaries() {
+        let mut timeline = Timeline::new();
+        // Reservations past midnight are cut at the end of the day, empty ones change nothing
+        timeline.reserve(23 * 60 + 30, MINUTES_IN_DAY + 60);
+        timeline.reserve(60, 60);
+        assert_eq!(timeline.free, vec![(0, 23 * 60 + 30)]);
+        assert_eq!(timeline.allocate_from(0, 15, 5), vec![(0, 15)]);
+
+        let date = NaiveDate::from_ymd_opt(2025, 3, 9).unwrap();
+        assert_eq!(at_minute(date, 0), date.and_hms_opt(0, 0, 0).unwrap());
+        assert_eq!(
+            at_minute(date, MINUTES_IN_DAY),
+            NaiveDate::from_ymd_opt(2025, 3, 10)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(
+            minute_of_day(NaiveTime::from_hms_opt(23, 59, 59).unwrap()),
+            1439
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+// Everything that can go wrong talking to the backend
+#[derive(Debug)]
+pub enum ApiError {
+    // The request never got a usable response: connection refused, timeout, undecodable body...
+    Transport(reqwest::Error),
+    // The backend answered with an error status, `message` comes from its `{"error": ...}` body
+    Status { status: StatusCode, message: String },
+    // The caller handed us something that can't be turned into a request
+    InvalidInput(String),
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+impl ApiError {
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ApiError::Status { status, .. } if *status == StatusCode::NOT_FOUND)
+    }
+
+    // Missing, expired or revoked login
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, ApiError::Status { status, .. } if *status == StatusCode::UNAUTHORIZED)
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::Transport(err) => write!(f, "Could not reach the backend: {}", err),
+            ApiError::Status { status, message } => write!(f, "{} ({})", message, status),
+            ApiError::InvalidInput(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Transport(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(err: reqwest::Error) -> Self {
+        ApiError::Transport(err)
+    }
+}
+
+// Shape of the body the backend sends along with every error status
+#[derive(Deserialize)]
+pub(crate) struct ErrorBody {
+    pub error: String,
+}
@@ -0,0 +1,53 @@
+use reqwest::Response;
+use taskflow_types::LiveEvent;
+
+use crate::ApiResult;
+
+// Live changes from `ApiClient::events`, read one at a time with `next`
+#[derive(Debug)]
+pub struct EventStream {
+    response: Response,
+    buffer: Vec<u8>,
+}
+
+impl EventStream {
+    pub(crate) fn new(response: Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+        }
+    }
+
+    // The next event, None once the backend closed the stream
+    pub async fn next(&mut self) -> Option<ApiResult<LiveEvent>> {
+        loop {
+            // Events end with an empty line, a chunk may hold several or only part of one
+            if let Some(end) = self.buffer.windows(2).position(|pair| pair == b"\n\n") {
+                let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+                match parse_event(&String::from_utf8_lossy(&block)) {
+                    Some(event) => return Some(Ok(event)),
+                    None => continue,
+                }
+            }
+            match self.response.chunk().await {
+                Ok(Some(chunk)) => self.buffer.extend_from_slice(&chunk),
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+    }
+}
+
+// The JSON in the `data:` lines of one event. Keep-alive comments and events of a newer backend
+// that this client doesn't know yield nothing.
+fn parse_event(block: &str) -> Option<LiveEvent> {
+    let data: Vec<&str> = block
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim_start)
+        .collect();
+    if data.is_empty() {
+        return None;
+    }
+    serde_json::from_str(&data.join("\n")).ok()
+}
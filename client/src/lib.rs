@@ -0,0 +1,529 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+
+mod error;
+use error::ErrorBody;
+pub use error::{ApiError, ApiResult};
+mod events;
+pub use events::EventStream;
+pub use taskflow_types::*;
+
+pub const DEFAULT_BASE_URL: &str = "http://localhost:3000";
+// Every path the client uses lives under this prefix of the base URL
+const API_PREFIX: &str = "/api/v1";
+// Overrides the base URL in `ClientConfig::from_env`
+pub const BASE_URL_ENV: &str = "TASKFLOW_API_URL";
+// The request timeout would cut the event stream, it gets this long instead and is reopened
+// by the caller when it ends
+#[cfg(not(target_arch = "wasm32"))]
+const EVENT_STREAM_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    pub base_url: String,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    // Extra attempts for idempotent requests that failed to connect, timed out or hit a 5xx
+    pub retries: u32,
+    // Wait before the first retry, doubled for every retry after that
+    pub retry_backoff: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(30),
+            retries: 2,
+            retry_backoff: Duration::from_millis(250),
+        }
+    }
+}
+
+impl ClientConfig {
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(base_url) = std::env::var(BASE_URL_ENV)
+            && !base_url.trim().is_empty()
+        {
+            config.base_url = base_url;
+        }
+        config
+    }
+}
+
+// Typed client for the TaskFlow backend. Cloning is cheap and every clone shares one connection
+// pool and one login: a token set through any clone is sent by all of them.
+#[derive(Clone, Debug)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+    retries: u32,
+    retry_backoff: Duration,
+    token: Arc<RwLock<Option<String>>>,
+}
+
+impl ApiClient {
+    pub fn new(config: ClientConfig) -> ApiResult<Self> {
+        let builder = reqwest::Client::builder();
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = builder
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout);
+        let http = builder.build()?;
+        Ok(Self {
+            http,
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            retries: config.retries,
+            retry_backoff: config.retry_backoff,
+            token: Arc::default(),
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // ===== Accounts =====
+
+    // Bearer token sent with every request, e.g. one saved from an earlier login
+    pub fn set_token(&self, token: Option<String>) {
+        *self.token.write().unwrap_or_else(|err| err.into_inner()) = token;
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    // Creates the account and logs it in
+    pub async fn signup(&self, credentials: &Credentials) -> ApiResult<AuthSession> {
+        credentials.validate().map_err(ApiError::InvalidInput)?;
+        let request = self.request(Method::POST, "/auth/signup").json(credentials);
+        let session: AuthSession = self.json(request).await?;
+        self.set_token(Some(session.token.clone()));
+        Ok(session)
+    }
+
+    pub async fn login(&self, credentials: &Credentials) -> ApiResult<AuthSession> {
+        let request = self.request(Method::POST, "/auth/login").json(credentials);
+        let session: AuthSession = self.json(request).await?;
+        self.set_token(Some(session.token.clone()));
+        Ok(session)
+    }
+
+    // The local token is dropped even if the backend could not be told
+    pub async fn logout(&self) -> ApiResult<()> {
+        let result = self.send(self.request(Method::POST, "/auth/logout")).await;
+        self.set_token(None);
+        result.map(drop)
+    }
+
+    pub async fn me(&self) -> ApiResult<User> {
+        self.json(self.request(Method::GET, "/me")).await
+    }
+
+    // Changes to the user's tasks and day plans as they happen, including those of other clients,
+    // and reminders as they fire
+    pub async fn events(&self) -> ApiResult<EventStream> {
+        let request = self
+            .request(Method::GET, "/events")
+            .header(reqwest::header::ACCEPT, "text/event-stream");
+        #[cfg(not(target_arch = "wasm32"))]
+        let request = request.timeout(EVENT_STREAM_TIMEOUT);
+        Ok(EventStream::new(self.send(request).await?))
+    }
+
+    // ===== Tasks =====
+
+    // One page of the task list, see `TaskQuery` for the filters
+    pub async fn list_tasks(&self, query: &TaskQuery) -> ApiResult<TaskPage> {
+        self.json(self.request(Method::GET, "/tasks").query(query))
+            .await
+    }
+
+    // Every task, following the cursors until the last page
+    pub async fn get_tasks(&self) -> ApiResult<Vec<Task>> {
+        let mut query = TaskQuery::default();
+        let mut tasks = Vec::new();
+        loop {
+            let page = self.list_tasks(&query).await?;
+            tasks.extend(page.items);
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => return Ok(tasks),
+            }
+        }
+    }
+
+    // A task that doesn't exist is `Ok(None)` rather than an error
+    pub async fn get_task(&self, id: &str) -> ApiResult<Option<Task>> {
+        let request = self.request(Method::GET, &format!("/tasks/{}", id));
+        match self.json(request).await {
+            Ok(task) => Ok(Some(task)),
+            Err(err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn add_task(&self, task: &Task) -> ApiResult<Task> {
+        task.validate().map_err(ApiError::InvalidInput)?;
+        let request = self.request(Method::POST, "/tasks").json(task);
+        self.json(request).await
+    }
+
+    pub async fn edit_task(&self, id: &str, patch: &TaskPatch) -> ApiResult<Task> {
+        patch.validate().map_err(ApiError::InvalidInput)?;
+        let request = self
+            .request(Method::PATCH, &format!("/tasks/{}", id))
+            .json(patch);
+        self.json(request).await
+    }
+
+    pub async fn set_done(&self, id: &str, is_done: bool) -> ApiResult<Task> {
+        let patch = TaskPatch {
+            is_done: Some(is_done),
+            ..TaskPatch::default()
+        };
+        self.edit_task(id, &patch).await
+    }
+
+    pub async fn delete_task(&self, id: &str) -> ApiResult<()> {
+        let request = self.request(Method::DELETE, &format!("/tasks/{}", id));
+        self.send(request).await.map(drop)
+    }
+
+    // The task with all of its subtasks and how far along they are
+    pub async fn get_subtasks(&self, id: &str) -> ApiResult<TaskTree> {
+        let request = self.request(Method::GET, &format!("/tasks/{}/subtasks", id));
+        self.json(request).await
+    }
+
+    pub async fn add_subtask(&self, parent_id: &str, subtask: &NewSubtask) -> ApiResult<Task> {
+        subtask.validate().map_err(ApiError::InvalidInput)?;
+        let request = self
+            .request(Method::POST, &format!("/tasks/{}/subtasks", parent_id))
+            .json(subtask);
+        self.json(request).await
+    }
+
+    pub async fn get_dependencies(&self, id: &str) -> ApiResult<TaskDependencies> {
+        let request = self.request(Method::GET, &format!("/tasks/{}/dependencies", id));
+        self.json(request).await
+    }
+
+    // `id` can't start before `prerequisite_id` is done. Fails with a conflict if that would
+    // make the two wait for each other.
+    pub async fn add_dependency(
+        &self,
+        id: &str,
+        prerequisite_id: &str,
+    ) -> ApiResult<TaskDependencies> {
+        let request = self.request(
+            Method::PUT,
+            &format!("/tasks/{}/dependencies/{}", id, prerequisite_id),
+        );
+        self.json(request).await
+    }
+
+    pub async fn remove_dependency(&self, id: &str, prerequisite_id: &str) -> ApiResult<()> {
+        let request = self.request(
+            Method::DELETE,
+            &format!("/tasks/{}/dependencies/{}", id, prerequisite_id),
+        );
+        self.send(request).await.map(drop)
+    }
+
+    // Open tasks still waiting for a prerequisite
+    pub async fn get_blocked_tasks(&self) -> ApiResult<Vec<BlockedTask>> {
+        self.json(self.request(Method::GET, "/tasks/blocked")).await
+    }
+
+    // Everything the task is filed under
+    pub async fn get_task_tags(&self, id: &str) -> ApiResult<Vec<Tag>> {
+        let request = self.request(Method::GET, &format!("/tasks/{}/tags", id));
+        self.json(request).await
+    }
+
+    // Replaces the task's tags, project and contexts with `tag_ids`
+    pub async fn set_task_tags(&self, id: &str, tag_ids: &[String]) -> ApiResult<Vec<Tag>> {
+        let request = self
+            .request(Method::PUT, &format!("/tasks/{}/tags", id))
+            .json(tag_ids);
+        self.json(request).await
+    }
+
+    // ===== Tags =====
+
+    // Tags, projects and contexts, or only those of one kind
+    pub async fn list_tags(&self, kind: Option<TagKind>) -> ApiResult<Vec<Tag>> {
+        let mut request = self.request(Method::GET, "/tags");
+        if let Some(kind) = kind {
+            request = request.query(&[("kind", kind.as_str())]);
+        }
+        self.json(request).await
+    }
+
+    pub async fn add_tag(&self, tag: &Tag) -> ApiResult<Tag> {
+        tag.validate().map_err(ApiError::InvalidInput)?;
+        let request = self.request(Method::POST, "/tags").json(tag);
+        self.json(request).await
+    }
+
+    // The tag's own id decides which record gets replaced
+    pub async fn update_tag(&self, tag: &Tag) -> ApiResult<Tag> {
+        tag.validate().map_err(ApiError::InvalidInput)?;
+        let id = tag
+            .id
+            .as_deref()
+            .ok_or_else(|| ApiError::InvalidInput("Tag has no id".to_string()))?;
+        let request = self
+            .request(Method::PUT, &format!("/tags/{}", id))
+            .json(tag);
+        self.json(request).await
+    }
+
+    pub async fn delete_tag(&self, id: &str) -> ApiResult<()> {
+        let request = self.request(Method::DELETE, &format!("/tags/{}", id));
+        self.send(request).await.map(drop)
+    }
+
+    // ===== Routines =====
+
+    pub async fn get_routines(&self) -> ApiResult<Vec<Routine>> {
+        self.json(self.request(Method::GET, "/routines")).await
+    }
+
+    pub async fn get_routine(&self, id: &str) -> ApiResult<Option<Routine>> {
+        let request = self.request(Method::GET, &format!("/routines/{}", id));
+        match self.json(request).await {
+            Ok(routine) => Ok(Some(routine)),
+            Err(err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn add_routine(&self, routine: &Routine) -> ApiResult<Routine> {
+        routine.validate().map_err(ApiError::InvalidInput)?;
+        let request = self.request(Method::POST, "/routines").json(routine);
+        self.json(request).await
+    }
+
+    // The routine's own id decides which record gets replaced
+    pub async fn update_routine(&self, routine: &Routine) -> ApiResult<Routine> {
+        routine.validate().map_err(ApiError::InvalidInput)?;
+        let id = routine
+            .id
+            .as_deref()
+            .ok_or_else(|| ApiError::InvalidInput("Routine has no id".to_string()))?;
+        let request = self
+            .request(Method::PUT, &format!("/routines/{}", id))
+            .json(routine);
+        self.json(request).await
+    }
+
+    pub async fn delete_routine(&self, id: &str) -> ApiResult<()> {
+        let request = self.request(Method::DELETE, &format!("/routines/{}", id));
+        self.send(request).await.map(drop)
+    }
+
+    // ===== Schedules =====
+
+    // The committed plan for `date` if there is one, otherwise a preview built from `query`
+    pub async fn get_day_schedule(
+        &self,
+        date: NaiveDate,
+        query: &ScheduleQuery,
+    ) -> ApiResult<DayPlan> {
+        let request = self
+            .request(Method::GET, &format!("/schedules/{}", date))
+            .query(query);
+        self.json(request).await
+    }
+
+    pub async fn commit_schedule(
+        &self,
+        date: NaiveDate,
+        query: &ScheduleQuery,
+    ) -> ApiResult<DayPlan> {
+        let request = self
+            .request(Method::POST, &format!("/schedules/{}", date))
+            .query(query);
+        self.json(request).await
+    }
+
+    pub async fn rollback_schedule(&self, date: NaiveDate) -> ApiResult<()> {
+        let request = self.request(Method::DELETE, &format!("/schedules/{}", date));
+        self.send(request).await.map(drop)
+    }
+
+    // ===== Notifications =====
+
+    // Reminders that fired, newest first
+    pub async fn list_notifications(
+        &self,
+        query: &NotificationQuery,
+    ) -> ApiResult<Vec<Notification>> {
+        let request = self.request(Method::GET, "/notifications").query(query);
+        self.json(request).await
+    }
+
+    pub async fn mark_notification_read(&self, id: &str) -> ApiResult<Notification> {
+        let request = self.request(Method::POST, &format!("/notifications/{}/read", id));
+        self.json(request).await
+    }
+
+    pub async fn mark_all_notifications_read(&self) -> ApiResult<()> {
+        let request = self.request(Method::POST, "/notifications/read");
+        self.send(request).await.map(drop)
+    }
+
+    // ===== Export and import =====
+
+    // Committed blocks, routines and tasks as the text of an iCalendar (.ics) file
+    pub async fn export_calendar(&self) -> ApiResult<String> {
+        let request = self.request(Method::GET, "/export/calendar.ics");
+        Ok(self.send(request).await?.text().await?)
+    }
+
+    // Events of an iCalendar file become routines and to-dos become tasks. With `dry_run` set the
+    // report only says what would be imported.
+    pub async fn import_calendar(
+        &self,
+        ics: impl Into<String>,
+        query: &ImportQuery,
+    ) -> ApiResult<ImportReport> {
+        let request = self
+            .request(Method::POST, "/import/calendar")
+            .query(query)
+            .header(reqwest::header::CONTENT_TYPE, "text/calendar")
+            .body(ics.into());
+        self.json(request).await
+    }
+
+    // Every task as CSV, `query.columns` picks the columns
+    pub async fn export_tasks_csv(&self, query: &CsvExportQuery) -> ApiResult<String> {
+        let request = self.request(Method::GET, "/export/tasks.csv").query(query);
+        Ok(self.send(request).await?.text().await?)
+    }
+
+    // Creates a task from every row of a CSV file that can be read. With `preview` set nothing
+    // is created, and the report shows what would be.
+    pub async fn import_tasks_csv(
+        &self,
+        csv: impl Into<String>,
+        query: &CsvImportQuery,
+    ) -> ApiResult<CsvImportReport> {
+        let request = self
+            .request(Method::POST, "/import/tasks")
+            .query(query)
+            .header(reqwest::header::CONTENT_TYPE, "text/csv")
+            .body(csv.into());
+        self.json(request).await
+    }
+
+    // ===== Admin =====
+
+    // The whole database of the backend as a versioned archive. Needs the backend's admin token
+    // rather than a login.
+    pub async fn export_backup(&self, admin_token: &str) -> ApiResult<Backup> {
+        let request = self.admin_request(Method::GET, "/admin/export", admin_token);
+        self.json(request).await
+    }
+
+    pub async fn import_backup(
+        &self,
+        admin_token: &str,
+        backup: &Backup,
+        mode: RestoreMode,
+    ) -> ApiResult<RestoreReport> {
+        let request = self
+            .admin_request(Method::POST, "/admin/import", admin_token)
+            .query(&RestoreQuery { mode })
+            .json(backup);
+        self.json(request).await
+    }
+
+    // ===== Plumbing =====
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}{}{}", self.base_url, API_PREFIX, path));
+        match self.token() {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    fn admin_request(&self, method: Method, path: &str, admin_token: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{}{}", self.base_url, API_PREFIX, path))
+            .bearer_auth(admin_token)
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> ApiResult<T> {
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    // Sends the request, retrying the ones that are safe to repeat, and turns error statuses into ApiError
+    async fn send(&self, request: RequestBuilder) -> ApiResult<Response> {
+        let request = request.build()?;
+        // POST and PATCH may already have taken effect when the response got lost
+        let idempotent = !matches!(*request.method(), Method::POST | Method::PATCH);
+        let attempts = if idempotent && cfg!(not(target_arch = "wasm32")) {
+            self.retries + 1
+        } else {
+            1
+        };
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let last = attempt >= attempts;
+            // Bodies are always buffered JSON or text, so cloning only fails if that ever changes
+            let Some(try_request) = request.try_clone() else {
+                return Err(ApiError::InvalidInput(
+                    "Request body cannot be sent more than once".to_string(),
+                ));
+            };
+            match self.http.execute(try_request).await {
+                Ok(response) if response.status().is_server_error() && !last => {}
+                Ok(response) => return error_for_status(response).await,
+                Err(err) if (err.is_connect() || err.is_timeout()) && !last => {}
+                Err(err) => return Err(err.into()),
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
+
+async fn error_for_status(response: Response) -> ApiResult<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<ErrorBody>(&body)
+        .map(|body| body.error)
+        .unwrap_or_else(|_| {
+            if body.trim().is_empty() {
+                status
+                    .canonical_reason()
+                    .unwrap_or("Request failed")
+                    .to_string()
+            } else {
+                body
+            }
+        });
+    Err(ApiError::Status { status, message })
+}
@@ -1,143 +1,189 @@
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-use reqwest::{get, Client};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::error::Error;
-
-#[derive(Clone, Serialize, Deserialize)]
-pub struct Task {
-    pub id: Option<String>,
-    pub name: String,
-    pub description: String,
-    pub due_by: NaiveDateTime,
-    pub imp_lvl: u8,
-    pub req_time: NaiveTime,
-    pub time_alloted: NaiveTime,
-    pub is_done: bool,
-}
+use std::sync::LazyLock;
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct Routine {
-    pub id: Option<String>,
-    pub title: String,
-    pub start_date: NaiveDate,
-    pub end_date: NaiveDate,
-    pub imp: String,
-    pub start_time: NaiveTime,
-    pub end_time: NaiveTime,
-    pub weekdays: Vec<String>,
-}
+use chrono::{Datelike, NaiveDateTime, NaiveTime};
+pub use taskflow_client::{
+    ApiClient, ApiError, ApiResult, BlockKind, ClientConfig, Credentials, CsvImportQuery,
+    CsvImportReport, LiveEvent, NewSubtask, Notification, NotificationQuery, Recurrence, Reminder,
+    Routine, ScheduleQuery, StrategyKind, Tag, TagKind, Task, TaskPage, TaskPatch, TaskQuery,
+    TaskTree, User, Weekday,
+};
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct SchedItem {
-    pub id: Option<String>,
-    pub date: NaiveDate,
-    pub represented_hour_start: u8,
-    pub has_time: bool,
-    pub time_left_mins: u8,
-    pub title: Vec<String>,
-}
+// One client for the whole app so every request shares the same connection pool.
+// Point it at another backend with the TASKFLOW_API_URL environment variable.
+static API: LazyLock<ApiClient> = LazyLock::new(|| {
+    ApiClient::new(ClientConfig::from_env()).expect("Failed to build the HTTP client")
+});
 
-pub async fn get_todos() -> Vec<Task> {
-    get("http://localhost:3000/get_tasks")
-        .await
-        .unwrap()
-        .json()
-        .await
-        .unwrap()
+pub fn api() -> &'static ApiClient {
+    &API
 }
 
-pub async fn get_todo_by_id(id: String) -> Option<Task> {
-    get(format!("http://localhost:3000/get_task/{}", id))
-        .await
-        .unwrap()
-        .json()
-        .await
-        .unwrap()
+pub const TASK_PAGE_SIZE: u32 = 25;
+
+// Loads the first `pages` pages of the task list in one go, so reloading after a change keeps
+// everything "Load more" had already shown
+pub async fn load_task_pages(mut query: TaskQuery, pages: usize) -> ApiResult<TaskPage> {
+    query.limit = Some(TASK_PAGE_SIZE);
+    let mut loaded = TaskPage::default();
+    for _ in 0..pages.max(1) {
+        let page = api().list_tasks(&query).await?;
+        loaded.items.extend(page.items);
+        loaded.next_cursor = page.next_cursor;
+        match &loaded.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor.clone()),
+            None => break,
+        }
+    }
+    Ok(loaded)
 }
 
+// Turns the add-task form fields into a Task and sends it
 pub async fn add_todo(
     name: String,
     description: String,
     due_by: String,
     req_time: String,
     imp_lvl: String,
-) -> Result<(), Box<dyn Error>> {
-    let client = Client::new();
-    let vec_time: Vec<&str> = req_time.split(":").collect();
-    let hour = vec_time[0].parse::<u32>().unwrap();
-    let min = vec_time[1].parse::<u32>().unwrap();
-    let req_time = NaiveTime::from_hms_opt(hour, min, 00).unwrap();
-    let body = json!(Task {
+    repeat: String,
+    interval: String,
+    reminder: String,
+) -> ApiResult<()> {
+    let due_by = convert_to_datetime(&due_by)?;
+    let task = Task {
         id: None,
         name,
         description,
-        due_by: convert_to_datetime(due_by),
-        time_alloted: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+        due_by,
+        time_alloted: NaiveTime::MIN,
+        req_time: parse_req_time(&req_time)?,
+        imp_lvl: parse_imp_lvl(&imp_lvl)?,
+        is_done: false,
+        auto_complete: false,
+        recurrence: parse_recurrence(&repeat, &interval, due_by)?,
+        tags: Vec::new(),
+        reminders: reminders_from_choice(&[], &reminder),
+    };
+    api().add_task(&task).await.map(drop)
+}
+
+pub async fn edit_todo(
+    id: String,
+    name: String,
+    description: String,
+    due_by: String,
+    req_time: String,
+    imp_lvl: String,
+    reminders: Vec<Reminder>,
+) -> ApiResult<()> {
+    let patch = TaskPatch {
+        name: Some(name),
+        description: Some(description),
+        due_by: Some(convert_to_datetime(&due_by)?),
+        req_time: Some(parse_req_time(&req_time)?),
+        imp_lvl: Some(parse_imp_lvl(&imp_lvl)?),
+        is_done: None,
+        auto_complete: None,
+        recurrence: None,
+        reminders: Some(reminders),
+    };
+    api().edit_task(&id, &patch).await.map(drop)
+}
+
+// Subtasks only need a name, an empty duration makes them a checklist item
+pub async fn add_subtask_todo(parent_id: String, name: String, minutes: String) -> ApiResult<()> {
+    let req_time = match minutes.trim() {
+        "" => None,
+        minutes => Some(parse_minutes(minutes)?),
+    };
+    let subtask = NewSubtask {
+        name,
         req_time,
-        imp_lvl: imp_lvl.parse::<u8>()?,
-        is_done: false
-    });
-    client
-        .post("http://localhost:3000/add_task")
-        .json(&body)
-        .send()
-        .await?;
-    Ok(())
+        ..NewSubtask::default()
+    };
+    api().add_subtask(&parent_id, &subtask).await.map(drop)
 }
 
-pub async fn mark_done(id: String) {
-    let client = Client::new();
-    let body = json!(id);
-    client
-        .post("http://localhost:3000/mark_done")
-        .json(&body)
-        .send()
-        .await
-        .unwrap();
+// Creates a tag and files the task under it, a new project takes the place of the old one
+pub async fn add_tag_to_task(task_id: &str, tag: Tag) -> ApiResult<()> {
+    let created = api().add_tag(&tag).await?;
+    let mut tag_ids: Vec<String> = api()
+        .get_task_tags(task_id)
+        .await?
+        .into_iter()
+        .filter(|current| created.kind != TagKind::Project || current.kind != TagKind::Project)
+        .filter_map(|current| current.id)
+        .collect();
+    tag_ids.extend(created.id);
+    api().set_task_tags(task_id, &tag_ids).await.map(drop)
 }
-pub async fn mark_undone(id: String) {
-    let client = Client::new();
-    let body = json!(id);
-    client
-        .post("http://localhost:3000/mark_undone")
-        .json(&body)
-        .send()
-        .await
-        .unwrap();
+
+pub fn convert_to_datetime(dt: &str) -> ApiResult<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(dt, "%Y-%m-%dT%H:%M")
+        .map_err(|err| ApiError::InvalidInput(format!("Invalid due date: {}", err)))
+}
+
+// Required time comes from the form as "HH:MM"
+fn parse_req_time(req_time: &str) -> ApiResult<NaiveTime> {
+    NaiveTime::parse_from_str(req_time, "%H:%M")
+        .map_err(|err| ApiError::InvalidInput(format!("Invalid required time: {}", err)))
+}
+
+// The repeat choices of the add-task form, weekly and monthly repeats follow the due date
+fn parse_recurrence(
+    repeat: &str,
+    interval: &str,
+    due_by: NaiveDateTime,
+) -> ApiResult<Option<Recurrence>> {
+    let recurrence = match repeat {
+        "" => return Ok(None),
+        "daily" => Recurrence::Daily,
+        "weekly" => Recurrence::weekly([Weekday::from(due_by.weekday())]),
+        "monthly" => Recurrence::Monthly(due_by.day()),
+        "interval" => Recurrence::EveryNDays(interval.trim().parse().map_err(|_| {
+            ApiError::InvalidInput(format!("Invalid number of days '{}'", interval))
+        })?),
+        other => {
+            return Err(ApiError::InvalidInput(format!(
+                "Unknown repeat '{}'",
+                other
+            )))
+        }
+    };
+    Ok(Some(recurrence))
 }
 
-pub async fn delete_todo(id: String) {
-    let client = Client::new();
-    let body = json!(id);
-    client
-        .post("http://localhost:3000/delete")
-        .json(&body)
-        .send()
-        .await
-        .unwrap();
+// The forms offer a single reminder, its choice is the lead time in minutes or empty for none
+pub fn reminder_choice(reminders: &[Reminder]) -> String {
+    reminders
+        .first()
+        .map(|reminder| reminder.minutes_before.to_string())
+        .unwrap_or_default()
 }
 
-pub async fn get_day_schedule(day: &String) -> Vec<SchedItem> {
-    get(format!("http://localhost:3000/get_schedule/{}", day))
-        .await
-        .unwrap()
-        .json()
-        .await
-        .unwrap()
+// Reminders set through the API are kept as long as the form's choice isn't changed
+pub fn reminders_from_choice(current: &[Reminder], choice: &str) -> Vec<Reminder> {
+    if choice == reminder_choice(current) {
+        return current.to_vec();
+    }
+    choice
+        .parse()
+        .ok()
+        .map(Reminder::before)
+        .into_iter()
+        .collect()
 }
 
-pub fn convert_to_datetime(dt: String) -> NaiveDateTime {
-    NaiveDateTime::parse_from_str(&dt, "%Y-%m-%dT%H:%M").expect("Failed to parse datetime")
+fn parse_minutes(minutes: &str) -> ApiResult<NaiveTime> {
+    minutes
+        .parse::<u32>()
+        .ok()
+        .filter(|minutes| *minutes > 0)
+        .and_then(|minutes| NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0))
+        .ok_or_else(|| ApiError::InvalidInput(format!("Invalid duration '{}'", minutes)))
 }
 
-pub async fn add_sched(sched_item: Routine) -> Result<(), Box<dyn Error>> {
-    let client = Client::new();
-    let body = json!(sched_item);
-    client
-        .post("http://localhost:3000/add_sched")
-        .json(&body)
-        .send()
-        .await?;
-    Ok(())
+fn parse_imp_lvl(imp_lvl: &str) -> ApiResult<u8> {
+    imp_lvl
+        .parse()
+        .map_err(|_| ApiError::InvalidInput(format!("Invalid importance '{}'", imp_lvl)))
 }
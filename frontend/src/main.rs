@@ -1,12 +1,15 @@
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
-use chrono::{Datelike, NaiveDate, NaiveTime, Utc};
+use chrono::{Datelike, Local, NaiveDate, NaiveTime, Timelike, Utc};
 use dioxus::prelude::*;
 
 mod backend_helper;
 use backend_helper::{
-    add_sched, add_todo, delete_todo, get_day_schedule, get_todo_by_id, get_todos, mark_done,
-    mark_undone, Routine, Task,
+    add_subtask_todo, add_tag_to_task, add_todo, api, edit_todo, load_task_pages, reminder_choice,
+    reminders_from_choice, ApiError, ApiResult, BlockKind, Credentials, CsvImportQuery,
+    CsvImportReport, LiveEvent, Notification, NotificationQuery, Reminder, Routine, ScheduleQuery,
+    StrategyKind, Tag, TagKind, Task, TaskPage, TaskPatch, TaskQuery, TaskTree, User, Weekday,
 };
 
 const MAIN_CSS: Asset = asset!("/assets/main.css");
@@ -62,6 +65,9 @@ const INPUT_STYLE: &str = "
     transition-all duration-200 hover:border-slate-600
 ";
 
+// Pixel height of one hour in the day timeline
+const HOUR_HEIGHT_PX: i64 = 64;
+
 const CHECKBOX_STYLE: &str = "
     w-5 h-5 rounded border-2 border-slate-600
     checked:bg-teal-500 checked:border-teal-500
@@ -78,25 +84,237 @@ enum Router {
     DateInfo { date: String },
 }
 
-// TODO: Update UI and backend to modify/delete routines
-
 fn main() {
     dioxus::launch(RouteHandler);
 }
 
+// What the task list on the home page shows
+#[derive(Clone, PartialEq)]
+struct TaskListView {
+    show_done: bool,
+    show_overdue: bool,
+    search: String,
+    // Id of the tag, project or context to list, empty for all tasks
+    tag: String,
+    // Number of pages loaded through "Load more"
+    pages: usize,
+}
+
+impl Default for TaskListView {
+    fn default() -> Self {
+        Self {
+            show_done: false,
+            show_overdue: true,
+            search: String::new(),
+            tag: String::new(),
+            pages: 1,
+        }
+    }
+}
+
+impl TaskListView {
+    fn query(&self) -> TaskQuery {
+        TaskQuery {
+            is_done: (!self.show_done).then_some(false),
+            due_after: (!self.show_overdue).then(|| Local::now().naive_local()),
+            search: Some(self.search.trim().to_string()).filter(|search| !search.is_empty()),
+            tags: Some(self.tag.clone()).filter(|tag| !tag.is_empty()),
+            // Subtasks are listed under their task on its details page
+            top_level: Some(true),
+            ..TaskQuery::default()
+        }
+    }
+}
+
+// Who is logged in, global so it survives moving between pages
+static CURRENT_USER: GlobalSignal<Option<User>> = Signal::global(|| None);
+
+// The latest change pushed by the backend, pages reload what it touches
+static LIVE_EVENT: GlobalSignal<Option<LiveEvent>> = Signal::global(|| None);
+
 #[component]
 fn RouteHandler() -> Element {
-    rsx!(Router::<Router> {})
+    if CURRENT_USER.read().is_none() {
+        return rsx!(Login {});
+    }
+    rsx! {
+        LiveUpdates {}
+        Router::<Router> {}
+    }
+}
+
+// Listens to the backend's event stream while someone is logged in, reopening it whenever it
+// ends. The client's retry backoff keeps this from spinning while the backend is down.
+#[component]
+fn LiveUpdates() -> Element {
+    use_future(|| async {
+        loop {
+            let mut events = match api().events().await {
+                Ok(events) => events,
+                Err(err) if err.is_unauthorized() => break,
+                Err(_) => continue,
+            };
+            while let Some(Ok(event)) = events.next().await {
+                if let LiveEvent::NotificationCreated { notification } = &event {
+                    show_desktop_notification(notification);
+                }
+                *LIVE_EVENT.write() = Some(event);
+            }
+        }
+    });
+    rsx!()
+}
+
+// Fired reminders also pop up outside the window in the desktop app
+#[cfg(feature = "desktop")]
+fn show_desktop_notification(notification: &Notification) {
+    let shown = notify_rust::Notification::new()
+        .appname("TaskFlow")
+        .summary(&notification.title)
+        .body(&notification.message)
+        .show();
+    if let Err(err) = shown {
+        eprintln!("Couldn't show the notification: {}", err);
+    }
+}
+
+#[cfg(not(feature = "desktop"))]
+fn show_desktop_notification(_notification: &Notification) {}
+
+#[component]
+fn Login() -> Element {
+    let mut is_signup = use_signal(|| false);
+    let mut username = use_signal(String::new);
+    let mut password = use_signal(String::new);
+    let mut info = use_signal(String::new);
+
+    rsx! {
+        document::Link { rel: "stylesheet", href: MAIN_CSS }
+        document::Link { rel: "stylesheet", href: TAILWIND_CSS }
+        div { class: "min-h-screen bg-gradient-to-br from-slate-900 via-slate-800 to-slate-900 text-white flex items-center justify-center px-4",
+            div { class: "w-full max-w-md animate-fade-in-scale",
+                div { class: CARD_STYLE,
+                    // Header
+                    div { class: "mb-8 text-center",
+                        h1 { class: "text-5xl font-black mb-3 text-gradient", "TaskFlow" }
+                        p { class: "text-slate-400",
+                            if is_signup() {
+                                "Create your account"
+                            } else {
+                                "Log in to see your tasks"
+                            }
+                        }
+                    }
+
+                    // Info Message
+                    if !info.read().is_empty() {
+                        div { class: "mb-6 p-4 bg-red-500/10 border border-red-500/30 rounded-lg text-red-400 animate-fade-in",
+                            "{info}"
+                        }
+                    }
+
+                    // Form
+                    form {
+                        class: "space-y-6",
+                        onsubmit: move |_| async move {
+                            let credentials = Credentials {
+                                username: username.read().trim().to_string(),
+                                password: password.read().clone(),
+                            };
+                            let result = if is_signup() {
+                                api().signup(&credentials).await
+                            } else {
+                                api().login(&credentials).await
+                            };
+                            password.set(String::new());
+                            match result {
+                                Ok(session) => {
+                                    info.set(String::new());
+                                    *CURRENT_USER.write() = Some(session.user);
+                                }
+                                Err(err) => info.set(format!("❌ {}", err)),
+                            }
+                        },
+
+                        div { class: "space-y-2",
+                            label { class: "block text-sm font-semibold text-slate-300", "Username" }
+                            input {
+                                class: INPUT_STYLE,
+                                placeholder: "Your username",
+                                autocomplete: "username",
+                                value: "{username}",
+                                oninput: move |e| username.set(e.value()),
+                            }
+                        }
+                        div { class: "space-y-2",
+                            label { class: "block text-sm font-semibold text-slate-300", "Password" }
+                            input {
+                                class: INPUT_STYLE,
+                                r#type: "password",
+                                placeholder: "Your password",
+                                autocomplete: if is_signup() { "new-password" } else { "current-password" },
+                                value: "{password}",
+                                oninput: move |e| password.set(e.value()),
+                            }
+                        }
+                        button {
+                            class: "w-full {BUTTON_PRIMARY}",
+                            disabled: "{username.read().trim().is_empty() || password.read().is_empty()}",
+                            r#type: "submit",
+                            if is_signup() {
+                                "Sign Up"
+                            } else {
+                                "Log In"
+                            }
+                        }
+                    }
+
+                    button {
+                        class: "mt-6 w-full text-sm text-slate-400 hover:text-teal-400 transition-colors duration-200",
+                        onclick: move |_| {
+                            is_signup.toggle();
+                            info.set(String::new());
+                        },
+                        if is_signup() {
+                            "Already have an account? Log in"
+                        } else {
+                            "New here? Create an account"
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[component]
 fn App() -> Element {
     let is_add_task: Signal<bool> = use_signal(|| false);
+    let is_import_csv: Signal<bool> = use_signal(|| false);
     let open_sched_editor: Signal<bool> = use_signal(|| false);
     let curr_task_id: Signal<String> = use_signal(String::new);
-    let todos = use_resource(get_todos);
+    let task_view = use_signal(TaskListView::default);
+    let mut todos = use_resource(move || async move {
+        let view = task_view();
+        load_task_pages(view.query(), view.pages).await
+    });
+    // Tasks added, edited or removed by another client or by an import
+    use_effect(move || {
+        if LIVE_EVENT
+            .read()
+            .as_ref()
+            .is_some_and(LiveEvent::is_task_change)
+        {
+            todos.restart();
+        }
+    });
     let current_year = use_signal(|| Utc::now().year());
     let current_month = use_signal(|| Utc::now().month());
+    let username = CURRENT_USER
+        .read()
+        .as_ref()
+        .map(|user| user.username.clone())
+        .unwrap_or_default();
 
     rsx! {
         document::Link { rel: "stylesheet", href: MAIN_CSS }
@@ -104,12 +322,29 @@ fn App() -> Element {
         div { class: "min-h-screen bg-gradient-to-br from-slate-900 via-slate-800 to-slate-900 text-white p-6",
             if is_add_task() {
                 AddTodo { is_add_task, todos }
+            } else if is_import_csv() {
+                ImportCsv { is_import_csv, todos }
             } else if open_sched_editor() {
                 SchedEditor { open_sched_editor }
             } else if !curr_task_id.read().is_empty() {
                 Task_details { curr_task_id }
             } else {
                 div { class: "max-w-7xl mx-auto",
+                    // Account Bar
+                    div { class: "flex items-center justify-end gap-4 mb-4 text-sm text-slate-400",
+                        Notifications {}
+                        span { "👤 {username}" }
+                        button {
+                            class: BUTTON_ICON,
+                            onclick: move |_| async move {
+                                // Logged out locally even if the backend can't be reached
+                                let _ = api().logout().await;
+                                *CURRENT_USER.write() = None;
+                            },
+                            "Log Out"
+                        }
+                    }
+
                     // Main Header
                     div { class: "mb-12 text-center animate-fade-in",
                         h1 { class: "text-6xl font-black mb-3 text-gradient", "TaskFlow" }
@@ -127,8 +362,17 @@ fn App() -> Element {
                                     div { class: "flex items-center gap-2 text-sm text-slate-400",
                                         span { "🎯" }
                                         match &*todos.read() {
-                                            Some(todos_vec) => rsx! {
-                                                span { "{todos_vec.len()} tasks" }
+                                            Some(Ok(page)) => rsx! {
+                                                span {
+                                                    "{page.items.len()}"
+                                                    if page.next_cursor.is_some() {
+                                                        "+"
+                                                    }
+                                                    " tasks"
+                                                }
+                                            },
+                                            Some(Err(_)) => rsx! {
+                                                span { "Offline" }
                                             },
                                             None => rsx! {
                                                 span { "Loading..." }
@@ -138,8 +382,10 @@ fn App() -> Element {
                                 }
                                 Home {
                                     is_add_task,
+                                    is_import_csv,
                                     open_sched_editor,
                                     todos,
+                                    task_view,
                                     curr_task_id,
                                 }
                             }
@@ -163,19 +409,191 @@ fn App() -> Element {
     }
 }
 
+// Unread reminders behind a bell in the account bar
+#[component]
+fn Notifications() -> Element {
+    let mut is_open = use_signal(|| false);
+    let mut unread = use_resource(|| async {
+        api()
+            .list_notifications(&NotificationQuery { unread: true })
+            .await
+    });
+    use_effect(move || {
+        if matches!(
+            &*LIVE_EVENT.read(),
+            Some(LiveEvent::NotificationCreated { .. })
+        ) {
+            unread.restart();
+        }
+    });
+    let notifications = match &*unread.read() {
+        Some(Ok(notifications)) => notifications.clone(),
+        _ => Vec::new(),
+    };
+
+    rsx! {
+        div { class: "relative",
+            button {
+                class: BUTTON_ICON,
+                onclick: move |_| is_open.toggle(),
+                "🔔"
+                if !notifications.is_empty() {
+                    span { class: "ml-1 px-1.5 rounded-full bg-teal-500 text-white text-xs",
+                        "{notifications.len()}"
+                    }
+                }
+            }
+            if is_open() {
+                div { class: "absolute right-0 mt-2 w-80 z-10 {CARD_STYLE}",
+                    div { class: "flex items-center justify-between mb-3",
+                        span { class: "font-semibold text-slate-300", "Reminders" }
+                        if !notifications.is_empty() {
+                            button {
+                                class: "text-xs text-slate-400 hover:text-teal-400",
+                                onclick: move |_| async move {
+                                    if api().mark_all_notifications_read().await.is_ok() {
+                                        unread.restart();
+                                    }
+                                },
+                                "Mark all read"
+                            }
+                        }
+                    }
+                    if notifications.is_empty() {
+                        p { class: "text-slate-500", "Nothing new" }
+                    }
+                    for notification in notifications {
+                        {
+                            let key = notification.id.clone().unwrap_or_default();
+                            let at = notification.at.format("%b %d, %H:%M").to_string();
+                            rsx! {
+                                div {
+                                    key: "{key}",
+                                    class: "flex items-start justify-between gap-3 py-2 border-t border-slate-700",
+                                    div {
+                                        p { class: "text-white font-medium", "{notification.title}" }
+                                        p { class: "text-xs text-slate-400", "{notification.message} · {at}" }
+                                    }
+                                    button {
+                                        class: "text-xs text-slate-400 hover:text-teal-400",
+                                        title: "Mark as read",
+                                        onclick: move |_| {
+                                            let id = notification.id.clone().unwrap_or_default();
+                                            async move {
+                                                if api().mark_notification_read(&id).await.is_ok() {
+                                                    unread.restart();
+                                                }
+                                            }
+                                        },
+                                        "✓"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn Home(
     is_add_task: Signal<bool>,
+    is_import_csv: Signal<bool>,
     open_sched_editor: Signal<bool>,
-    todos: Resource<Vec<Task>>,
+    todos: Resource<ApiResult<TaskPage>>,
+    task_view: Signal<TaskListView>,
     curr_task_id: Signal<String>,
 ) -> Element {
+    // Reloads together with the list, so finishing a prerequisite unlocks its dependents
+    let blocked_ids = use_resource(move || async move {
+        let _ = todos.read();
+        api().get_blocked_tasks().await.map(|blocked| {
+            blocked
+                .into_iter()
+                .filter_map(|blocked| blocked.task.id)
+                .collect::<HashSet<String>>()
+        })
+    });
+    let all_tags = use_resource(|| async { api().list_tags(None).await });
+    let tag_names: HashMap<String, String> = match &*all_tags.read() {
+        Some(Ok(tags)) => tags
+            .iter()
+            .filter_map(|tag| Some((tag.id.clone()?, tag.display_name())))
+            .collect(),
+        _ => HashMap::new(),
+    };
+
     rsx! {
         div { class: "space-y-6",
+            // Filters, any change starts over from the first page
+            div { class: "flex flex-wrap items-center gap-4",
+                input {
+                    class: "flex-1 min-w-[12rem] {INPUT_STYLE}",
+                    placeholder: "🔍 Search tasks",
+                    value: "{task_view.read().search}",
+                    oninput: move |e| {
+                        let mut view = task_view.write();
+                        view.search = e.value();
+                        view.pages = 1;
+                    },
+                }
+                label { class: "flex items-center gap-2 text-sm text-slate-400",
+                    input {
+                        class: CHECKBOX_STYLE,
+                        r#type: "checkbox",
+                        checked: task_view.read().show_done,
+                        oninput: move |_| {
+                            let mut view = task_view.write();
+                            view.show_done = !view.show_done;
+                            view.pages = 1;
+                        },
+                    }
+                    "Completed"
+                }
+                label { class: "flex items-center gap-2 text-sm text-slate-400",
+                    input {
+                        class: CHECKBOX_STYLE,
+                        r#type: "checkbox",
+                        checked: task_view.read().show_overdue,
+                        oninput: move |_| {
+                            let mut view = task_view.write();
+                            view.show_overdue = !view.show_overdue;
+                            view.pages = 1;
+                        },
+                    }
+                    "Overdue"
+                }
+                if let Some(Ok(tags)) = &*all_tags.read() {
+                    if !tags.is_empty() {
+                        select {
+                            class: INPUT_STYLE,
+                            value: "{task_view.read().tag}",
+                            onchange: move |e| {
+                                let mut view = task_view.write();
+                                view.tag = e.value();
+                                view.pages = 1;
+                            },
+                            option { value: "", "🏷️ All tags" }
+                            for tag in tags.iter() {
+                                option {
+                                    key: "{tag.id.clone().unwrap_or_default()}",
+                                    value: "{tag.id.clone().unwrap_or_default()}",
+                                    "{tag.kind.label()}: {tag.display_name()}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
             // Task List
             div { class: "space-y-3 max-h-[500px] overflow-y-auto pr-2",
                 match &*todos.read() {
-                    Some(todos_vec) => {
+                    Some(Ok(page)) => {
+                        let todos_vec = &page.items;
+                        let has_more = page.next_cursor.is_some();
                         if !todos_vec.is_empty() {
                             let todo_elements = todos_vec
                                 .iter()
@@ -188,7 +606,16 @@ fn Home(
                                     let del_id = id.clone();
                                     let show_id = id.clone();
                                     let is_done = todo.is_done;
+                                    let is_blocked = matches!(
+                                        &*blocked_ids.read(), Some(Ok(ids)) if ids.contains(&id)
+                                    );
                                     let name = todo.name.clone();
+                                    let is_recurring = todo.recurrence.is_some();
+                                    let labels: Vec<String> = todo
+                                        .tags
+                                        .iter()
+                                        .filter_map(|tag| tag_names.get(tag).cloned())
+                                        .collect();
                                     let imp = todo.imp_lvl;
                                     let stagger_class = format!("stagger-{}", (index % 5) + 1);
                                     rsx! {
@@ -204,11 +631,8 @@ fn Home(
                                                     oninput: move |_| {
                                                         let id = id.clone();
                                                         async move {
-                                                            if is_done {
-                                                                mark_undone(id.clone()).await;
-                                                            } else {
-                                                                mark_done(id.clone()).await;
-                                                            }
+                                                            // The reloaded list shows whether the change went through
+                                                            let _ = api().set_done(&id, !is_done).await;
                                                             todos.restart();
                                                         }
                                                     },
@@ -224,6 +648,23 @@ fn Home(
                                                         span { class: if is_done { "text-slate-500 line-through text-lg" } else { "text-white text-lg font-medium group-hover:text-teal-400 transition-colors duration-200" },
                                                             "{name}"
                                                         }
+                                                        if is_recurring {
+                                                            span { class: "text-slate-400", title: "Repeats", "🔁" }
+                                                        }
+                                                        if is_blocked && !is_done {
+                                                            span {
+                                                                class: "px-2 py-0.5 text-xs font-semibold rounded-full bg-slate-500/20 text-slate-300 border border-slate-500/30",
+                                                                title: "Waiting for another task to be done",
+                                                                "🔒 Blocked"
+                                                            }
+                                                        }
+                                                        for label in labels {
+                                                            span {
+                                                                key: "{label}",
+                                                                class: "px-2 py-0.5 text-xs rounded-full bg-teal-500/10 text-teal-300 border border-teal-500/30",
+                                                                "{label}"
+                                                            }
+                                                        }
                                                         // Importance Badge
                                                         if imp >= 7 {
                                                             span { class: "px-2 py-0.5 text-xs font-semibold rounded-full bg-red-500/20 text-red-400 border border-red-500/30",
@@ -242,7 +683,7 @@ fn Home(
                                                     onclick: move |_| {
                                                         let id = del_id.clone();
                                                         async move {
-                                                            delete_todo(id.clone()).await;
+                                                            let _ = api().delete_task(&id).await;
                                                             todos.restart();
                                                         }
                                                     },
@@ -254,17 +695,36 @@ fn Home(
                                 });
                             rsx! {
                                 {todo_elements}
+                                if has_more {
+                                    button {
+                                        class: "w-full {BUTTON_SECONDARY}",
+                                        onclick: move |_| task_view.write().pages += 1,
+                                        "Load more"
+                                    }
+                                }
                             }
                         } else {
                             rsx! {
                                 div { class: "text-center py-12 animate-fade-in",
                                     div { class: "text-6xl mb-4", "📭" }
-                                    p { class: "text-slate-400 text-lg mb-2", "No tasks yet!" }
-                                    p { class: "text-slate-500 text-sm", "Start by adding your first task below" }
+                                    p { class: "text-slate-400 text-lg mb-2", "No tasks to show" }
+                                    p { class: "text-slate-500 text-sm", "Add a task below or change the filters above" }
                                 }
                             }
                         }
                     }
+                    Some(Err(err)) => rsx! {
+                        div { class: "text-center py-12 animate-fade-in",
+                            div { class: "text-6xl mb-4", "🔌" }
+                            p { class: "text-slate-400 text-lg mb-2", "Couldn't load your tasks" }
+                            p { class: "text-slate-500 text-sm mb-4", "{err}" }
+                            button {
+                                class: BUTTON_SECONDARY,
+                                onclick: move |_| todos.restart(),
+                                "Retry"
+                            }
+                        }
+                    },
                     None => rsx! {
                         div { class: "space-y-3",
                             for _ in 0..3 {
@@ -284,6 +744,13 @@ fn Home(
                     },
                     "➕ Add Task"
                 }
+                button {
+                    class: "flex-1 {BUTTON_SECONDARY}",
+                    onclick: move |_| {
+                        is_import_csv.set(true);
+                    },
+                    "📥 Import CSV"
+                }
                 button {
                     class: "flex-1 {BUTTON_SECONDARY}",
                     onclick: move |_| {
@@ -297,7 +764,7 @@ fn Home(
 }
 
 #[component]
-fn AddTodo(is_add_task: Signal<bool>, todos: Resource<Vec<Task>>) -> Element {
+fn AddTodo(is_add_task: Signal<bool>, todos: Resource<ApiResult<TaskPage>>) -> Element {
     let mut info = use_signal(String::new);
     let mut new_todo_name = use_signal(String::new);
     let mut new_todo_desc = use_signal(String::new);
@@ -305,6 +772,9 @@ fn AddTodo(is_add_task: Signal<bool>, todos: Resource<Vec<Task>>) -> Element {
     let mut new_todo_imp = use_signal(|| "5".to_string());
     let mut new_todo_req_time_hours = use_signal(|| 0u8);
     let mut new_todo_req_time_mins = use_signal(|| 0u8);
+    let mut new_todo_repeat = use_signal(String::new);
+    let mut new_todo_interval = use_signal(|| "2".to_string());
+    let mut new_todo_reminder = use_signal(String::new);
 
     rsx! {
         div { class: "max-w-2xl mx-auto animate-fade-in-scale",
@@ -344,6 +814,9 @@ fn AddTodo(is_add_task: Signal<bool>, todos: Resource<Vec<Task>>) -> Element {
                                 new_todo_due.read().clone(),
                                 formatted_time,
                                 new_todo_imp.read().clone(),
+                                new_todo_repeat.read().clone(),
+                                new_todo_interval.read().clone(),
+                                new_todo_reminder.read().clone(),
                             )
                             .await
                         {
@@ -352,7 +825,7 @@ fn AddTodo(is_add_task: Signal<bool>, todos: Resource<Vec<Task>>) -> Element {
                                     format!("✅ Task '{}' added successfully!", new_todo_name.read()),
                                 )
                             }
-                            Err(_) => info.set("❌ An error occurred while adding the task".to_string()),
+                            Err(err) => info.set(format!("❌ Couldn't add the task: {}", err)),
                         };
                         new_todo_name.set(String::new());
                         new_todo_desc.set(String::new());
@@ -360,6 +833,8 @@ fn AddTodo(is_add_task: Signal<bool>, todos: Resource<Vec<Task>>) -> Element {
                         new_todo_imp.set("5".to_string());
                         new_todo_req_time_hours.set(0);
                         new_todo_req_time_mins.set(0);
+                        new_todo_repeat.set(String::new());
+                        new_todo_reminder.set(String::new());
                         todos.restart();
                     },
 
@@ -462,6 +937,35 @@ fn AddTodo(is_add_task: Signal<bool>, todos: Resource<Vec<Task>>) -> Element {
                         }
                     }
 
+                    // Recurrence, completing the task then creates the next one
+                    div { class: "space-y-2",
+                        label { class: "block text-sm font-semibold text-slate-300", "Repeats" }
+                        div { class: "flex items-center gap-3",
+                            select {
+                                class: INPUT_STYLE,
+                                value: "{new_todo_repeat}",
+                                onchange: move |e| new_todo_repeat.set(e.value()),
+                                option { value: "", "Never" }
+                                option { value: "daily", "Every day" }
+                                option { value: "weekly", "Every week on the due weekday" }
+                                option { value: "monthly", "Every month on the due day" }
+                                option { value: "interval", "Every few days" }
+                            }
+                            if new_todo_repeat() == "interval" {
+                                input {
+                                    class: "w-28 {INPUT_STYLE}",
+                                    r#type: "number",
+                                    min: "1",
+                                    value: "{new_todo_interval}",
+                                    oninput: move |e| new_todo_interval.set(e.value()),
+                                }
+                                span { class: "text-slate-400", "days" }
+                            }
+                        }
+                    }
+
+                    ReminderSelect { label: "Remind me", choice: new_todo_reminder }
+
                     // Submit Buttons
                     div { class: "flex gap-3 pt-6",
                         button {
@@ -488,6 +992,185 @@ fn AddTodo(is_add_task: Signal<bool>, todos: Resource<Vec<Task>>) -> Element {
     }
 }
 
+// Tasks from a spreadsheet, pasted as CSV or copied cells. A preview shows how the columns were
+// read and which rows would be rejected before anything is created.
+#[component]
+fn ImportCsv(is_import_csv: Signal<bool>, todos: Resource<ApiResult<TaskPage>>) -> Element {
+    let mut csv_text = use_signal(String::new);
+    let mut column_map = use_signal(String::new);
+    let mut month_first = use_signal(|| false);
+    let mut report: Signal<Option<CsvImportReport>> = use_signal(|| None);
+    let mut info = use_signal(String::new);
+
+    let run_import = move |preview: bool| async move {
+        let query = CsvImportQuery {
+            preview,
+            month_first: month_first(),
+            map: Some(column_map.read().trim().to_string()).filter(|map| !map.is_empty()),
+        };
+        match api()
+            .import_tasks_csv(csv_text.read().clone(), &query)
+            .await
+        {
+            Ok(result) => {
+                if preview {
+                    info.set(format!(
+                        "🔍 {} task(s) ready, {} row(s) rejected",
+                        result.tasks.len(),
+                        result.rejected.len()
+                    ));
+                } else {
+                    info.set(format!(
+                        "✅ Imported {} task(s), {} row(s) rejected",
+                        result.tasks.len(),
+                        result.rejected.len()
+                    ));
+                    todos.restart();
+                }
+                report.set(Some(result));
+            }
+            Err(err) => {
+                info.set(format!("❌ {}", err));
+                report.set(None);
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "max-w-4xl mx-auto animate-fade-in-scale",
+            div { class: CARD_STYLE,
+                // Header
+                div { class: "flex items-center justify-between mb-8",
+                    h1 { class: HEADING_SECONDARY, "📥 Import Tasks from CSV" }
+                    button {
+                        class: BUTTON_ICON,
+                        onclick: move |_| is_import_csv.set(false),
+                        "✕"
+                    }
+                }
+
+                // Info Message
+                if !info.read().is_empty() {
+                    div { class: "mb-6 p-4 bg-teal-500/10 border border-teal-500/30 rounded-lg text-teal-400 animate-fade-in",
+                        "{info}"
+                    }
+                }
+
+                div { class: "space-y-6",
+                    div { class: "space-y-2",
+                        label { class: "block text-sm font-semibold text-slate-300",
+                            "CSV with a header row, or cells copied from a spreadsheet"
+                        }
+                        textarea {
+                            class: "font-mono text-sm {INPUT_STYLE}",
+                            rows: "10",
+                            placeholder: "name,due_by,imp_lvl,req_time\nQuarterly report,2025-03-31 17:00,8,2:30",
+                            value: "{csv_text}",
+                            oninput: move |e| {
+                                csv_text.set(e.value());
+                                report.set(None);
+                            },
+                        }
+                    }
+                    div { class: "space-y-2",
+                        label { class: "block text-sm font-semibold text-slate-300",
+                            "Column mapping (optional)"
+                        }
+                        input {
+                            class: INPUT_STYLE,
+                            r#type: "text",
+                            placeholder: "e.g. Wann:due_by, Aufwand:req_time",
+                            value: "{column_map}",
+                            oninput: move |e| column_map.set(e.value()),
+                        }
+                    }
+                    label { class: "flex items-center gap-2 text-sm text-slate-400",
+                        input {
+                            class: CHECKBOX_STYLE,
+                            r#type: "checkbox",
+                            checked: month_first(),
+                            oninput: move |_| month_first.set(!month_first()),
+                        }
+                        "Dates like 03/04/2025 put the month first"
+                    }
+
+                    if let Some(result) = &*report.read() {
+                        // How the headers were read
+                        div { class: "flex flex-wrap gap-2 text-xs",
+                            for column in result.columns.iter() {
+                                span {
+                                    key: "{column.header}",
+                                    class: if column.column.is_some() { "px-2 py-1 rounded bg-teal-500/20 text-teal-300" } else { "px-2 py-1 rounded bg-slate-700 text-slate-500 line-through" },
+                                    "{column.header}"
+                                    if let Some(field) = column.column {
+                                        " → {field.as_str()}"
+                                    }
+                                }
+                            }
+                        }
+                        if !result.tasks.is_empty() {
+                            div { class: "max-h-64 overflow-y-auto",
+                                table { class: "w-full text-sm text-left",
+                                    thead { class: "text-slate-400",
+                                        tr {
+                                            th { "Name" }
+                                            th { "Due" }
+                                            th { "Importance" }
+                                            th { "Required" }
+                                        }
+                                    }
+                                    tbody {
+                                        for (index, task) in result.tasks.iter().enumerate() {
+                                            {
+                                                let due_by = task.due_by.format("%Y-%m-%d %H:%M").to_string();
+                                                let req_time = task.req_time.format("%H:%M").to_string();
+                                                rsx! {
+                                                    tr { key: "{index}", class: "border-t border-slate-700",
+                                                        td { "{task.name}" }
+                                                        td { "{due_by}" }
+                                                        td { "{task.imp_lvl}/10" }
+                                                        td { "{req_time}" }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if !result.rejected.is_empty() {
+                            div { class: "p-4 bg-red-500/10 border border-red-500/30 rounded-lg text-sm text-red-300 space-y-1",
+                                for row in result.rejected.iter() {
+                                    p { key: "{row.line}", "Line {row.line}: {row.reason}" }
+                                }
+                            }
+                        }
+                    }
+
+                    div { class: "flex gap-3 pt-6",
+                        button {
+                            class: "flex-1 {BUTTON_SECONDARY}",
+                            disabled: csv_text.read().trim().is_empty(),
+                            onclick: move |_| run_import(true),
+                            "🔍 Preview"
+                        }
+                        button {
+                            class: "flex-1 {BUTTON_PRIMARY}",
+                            // Importing only after a look at the preview
+                            disabled: report.read().as_ref().is_none_or(|result| !result.preview || result.tasks.is_empty()),
+                            onclick: move |_| async move {
+                                run_import(false).await;
+                                csv_text.set(String::new());
+                            },
+                            "📥 Import"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn Calendar(current_month: Signal<u32>, current_year: Signal<i32>) -> Element {
     let curr_month = *current_month.read();
@@ -579,12 +1262,63 @@ fn Calendar(current_month: Signal<u32>, current_year: Signal<i32>) -> Element {
 #[component]
 fn DateInfo(date: String) -> Element {
     let attempted_to_date = NaiveDate::from_str(&date);
-    let schedule = use_resource(use_reactive!(|(date,)| async move {
-        get_day_schedule(&date).await
+    let mut strategy = use_signal(StrategyKind::default);
+    // Project or context the day is limited to, empty for every task
+    let mut only = use_signal(String::new);
+    let schedule_query = move || ScheduleQuery {
+        strategy: strategy(),
+        only: Some(only()).filter(|only| !only.is_empty()),
+    };
+    let mut schedule = use_resource(use_reactive!(|(date,)| async move {
+        let day =
+            NaiveDate::from_str(&date).map_err(|err| ApiError::InvalidInput(err.to_string()))?;
+        api().get_day_schedule(day, &schedule_query()).await
     }));
+    // A replanned day, or a task change that alters a plan that isn't committed yet
+    use_effect(use_reactive!(|(date,)| {
+        let touches_day = match &*LIVE_EVENT.read() {
+            Some(LiveEvent::ScheduleRegenerated { dates }) => {
+                dates.iter().any(|day| day.to_string() == date)
+            }
+            Some(event) => event.is_task_change(),
+            None => false,
+        };
+        if touches_day {
+            schedule.restart();
+        }
+    }));
+    let focus_tags = use_resource(|| async {
+        api().list_tags(None).await.map(|tags| {
+            tags.into_iter()
+                .filter(|tag| tag.kind != TagKind::Tag)
+                .collect::<Vec<Tag>>()
+        })
+    });
+    let mut info = use_signal(String::new);
 
     if let Ok(parsed_date) = attempted_to_date {
         let date_string = parsed_date.format("%A, %B %-d, %Y").to_string();
+        let is_past = parsed_date < Local::now().date_naive();
+        let plan = schedule
+            .read()
+            .as_ref()
+            .and_then(|plan| plan.as_ref().ok())
+            .cloned();
+        let committed = plan.as_ref().map(|plan| plan.committed);
+        let focus = match (&plan, &*focus_tags.read()) {
+            (Some(plan), Some(Ok(tags))) => tags
+                .iter()
+                .filter(|tag| tag.id.as_ref().is_some_and(|id| plan.only.contains(id)))
+                .map(|tag| tag.display_name())
+                .collect::<Vec<String>>()
+                .join(", "),
+            _ => String::new(),
+        };
+        let blocks = plan.map(|plan| plan.blocks).unwrap_or_default();
+        let load_error = match &*schedule.read() {
+            Some(Err(err)) => format!("❌ Couldn't load the schedule: {}", err),
+            _ => String::new(),
+        };
         rsx! {
             div { class: "min-h-screen bg-gradient-to-br from-slate-900 via-slate-800 to-slate-900 text-white p-6",
                 div { class: "max-w-5xl mx-auto animate-fade-in",
@@ -598,53 +1332,132 @@ fn DateInfo(date: String) -> Element {
                             "← Back to Home"
                         }
                         h1 { class: "text-4xl font-bold text-teal-400 mb-2", "{date_string}" }
-                        p { class: "text-slate-400", "Your schedule for the day" }
-                    }
-
-                    // Schedule Table
-                    div { class: CARD_STYLE,
-                        div { class: "overflow-x-auto",
-                            table { class: "w-full",
-                                thead {
-                                    tr { class: "border-b-2 border-slate-700",
-                                        th { class: "p-4 text-left font-semibold text-teal-400 w-32",
-                                            "Time"
-                                        }
-                                        th { class: "p-4 text-left font-semibold text-teal-400",
-                                            "Activities"
-                                        }
-                                    }
+                        div { class: "flex items-center justify-between gap-4",
+                            p { class: "text-slate-400",
+                                if committed == Some(true) {
+                                    "Your committed schedule for the day"
+                                } else if committed == Some(false) {
+                                    "A preview of your day, nothing is booked until you commit it"
+                                } else {
+                                    "Your schedule for the day"
                                 }
-                                tbody {
-                                    for x in 0..24 {
-                                        tr { class: "border-b border-slate-700 hover:bg-slate-900/50 transition-colors duration-200",
-                                            td { class: "p-4 text-slate-400 font-medium",
-                                                "{x:02}:00 - {x+1:02}:00"
+                                if !focus.is_empty() {
+                                    ", limited to {focus}"
+                                }
+                            }
+                            if !is_past {
+                                if committed == Some(false) {
+                                    select {
+                                        class: "px-4 py-2.5 bg-slate-800 border border-slate-700 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-teal-500",
+                                        value: strategy().as_str(),
+                                        onchange: move |e| {
+                                            if let Some(kind) = StrategyKind::ALL
+                                                .into_iter()
+                                                .find(|kind| kind.as_str() == e.value())
+                                            {
+                                                strategy.set(kind);
                                             }
-                                            td { class: "p-4",
-                                                div { class: "flex flex-wrap gap-2",
-                                                    for vec_sched_sub_items in schedule.read().clone().into_iter() {
-                                                        for item in vec_sched_sub_items {
-                                                            {
-                                                                let should_show = item.represented_hour_start == x;
-                                                                if should_show {
-                                                                    rsx! {
-                                                                        for title in item.title {
-                                                                            div { class: "px-3 py-1.5 bg-teal-500/20 border border-teal-500/30 rounded-lg text-teal-400 font-medium text-sm",
-                                                                                " {title} "
-                                                                            }
-                                                                        }
-                                                                    }
-                                                                } else {
-                                                                    rsx! {}
-                                                                }
-                                                            }
-                                                        }
+                                        },
+                                        for kind in StrategyKind::ALL {
+                                            option {
+                                                key: "{kind.as_str()}",
+                                                value: kind.as_str(),
+                                                "{kind.label()}"
+                                            }
+                                        }
+                                    }
+                                    if let Some(Ok(tags)) = &*focus_tags.read() {
+                                        if !tags.is_empty() {
+                                            select {
+                                                class: "px-4 py-2.5 bg-slate-800 border border-slate-700 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-teal-500",
+                                                value: "{only}",
+                                                onchange: move |e| only.set(e.value()),
+                                                option { value: "", "All projects & contexts" }
+                                                for tag in tags.iter() {
+                                                    option {
+                                                        key: "{tag.id.clone().unwrap_or_default()}",
+                                                        value: "{tag.id.clone().unwrap_or_default()}",
+                                                        "Only {tag.display_name()}"
                                                     }
                                                 }
                                             }
                                         }
                                     }
+                                    button {
+                                        class: BUTTON_PRIMARY,
+                                        onclick: move |_| async move {
+                                            match api().commit_schedule(parsed_date, &schedule_query()).await {
+                                                Ok(_) => info.set(String::new()),
+                                                Err(err) => info.set(format!("❌ Couldn't commit the plan: {}", err)),
+                                            }
+                                            schedule.restart();
+                                        },
+                                        "📌 Commit Plan"
+                                    }
+                                } else if committed == Some(true) {
+                                    button {
+                                        class: BUTTON_SECONDARY,
+                                        onclick: move |_| async move {
+                                            match api().rollback_schedule(parsed_date).await {
+                                                Ok(_) => info.set(String::new()),
+                                                Err(err) => info.set(format!("❌ Couldn't roll back the plan: {}", err)),
+                                            }
+                                            schedule.restart();
+                                        },
+                                        "↩️ Roll Back"
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Info Message
+                    if !info.read().is_empty() {
+                        div { class: "mb-6 p-4 bg-teal-500/10 border border-teal-500/30 rounded-lg text-teal-400 animate-fade-in",
+                            "{info}"
+                        }
+                    }
+                    if !load_error.is_empty() {
+                        div { class: "mb-6 p-4 bg-red-500/10 border border-red-500/30 rounded-lg text-red-400 animate-fade-in",
+                            "{load_error}"
+                        }
+                    }
+
+                    // Day Timeline
+                    div { class: CARD_STYLE,
+                        div {
+                            class: "relative ml-16",
+                            style: "height: {24 * HOUR_HEIGHT_PX}px;",
+                            // Hour grid
+                            for x in 0..24 {
+                                div {
+                                    class: "absolute left-0 right-0 border-t border-slate-700",
+                                    style: "top: {x * HOUR_HEIGHT_PX}px;",
+                                    span { class: "absolute -left-16 -top-2.5 w-14 text-right text-slate-400 text-sm font-medium",
+                                        "{x:02}:00"
+                                    }
+                                }
+                            }
+                            // Scheduled blocks
+                            for block in blocks {
+                                {
+                                    let day_start = parsed_date.and_time(NaiveTime::MIN);
+                                    let top = (block.start - day_start).num_minutes() * HOUR_HEIGHT_PX / 60;
+                                    let height = ((block.end - block.start).num_minutes() * HOUR_HEIGHT_PX / 60)
+                                        .max(18);
+                                    let time_range = format!(
+                                        "{} - {}",
+                                        block.start.format("%H:%M"),
+                                        block.end.format("%H:%M"),
+                                    );
+                                    rsx! {
+                                        div {
+                                            class: if block.kind == BlockKind::Routine { "absolute left-1 right-1 overflow-hidden px-3 py-1 rounded-lg bg-blue-500/20 border border-blue-500/30 text-blue-300 text-sm" } else { "absolute left-1 right-1 overflow-hidden px-3 py-1 rounded-lg bg-teal-500/20 border border-teal-500/30 text-teal-400 text-sm" },
+                                            style: "top: {top}px; height: {height}px;",
+                                            span { class: "font-medium", "{block.title}" }
+                                            span { class: "ml-2 text-xs text-slate-400", "{time_range}" }
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -667,9 +1480,14 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
     let mut new_scheditem_imp = use_signal(|| "5".to_string());
     let mut new_scheditem_time_start = use_signal(String::new);
     let mut new_scheditem_time_end = use_signal(String::new);
-    let mut new_scheditem_weekdays: Signal<Vec<String>> = use_signal(Vec::new);
+    let mut new_scheditem_weekdays: Signal<Vec<Weekday>> = use_signal(Vec::new);
+    let mut new_scheditem_reminder = use_signal(String::new);
+    // Reminders of the routine being edited
+    let mut new_scheditem_reminders: Signal<Vec<Reminder>> = use_signal(Vec::new);
+    let mut editing_routine_id: Signal<Option<String>> = use_signal(|| None);
+    let mut routines = use_resource(|| async { api().get_routines().await });
 
-    let mut toggle_weekday = move |day: String| {
+    let mut toggle_weekday = move |day: Weekday| {
         let mut weekdays = new_scheditem_weekdays.write();
         if let Some(pos) = weekdays.iter().position(|d| d == &day) {
             weekdays.remove(pos);
@@ -683,7 +1501,13 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
             div { class: CARD_STYLE,
                 // Header
                 div { class: "flex items-center justify-between mb-8",
-                    h1 { class: HEADING_SECONDARY, "⚡ Create Routine" }
+                    h1 { class: HEADING_SECONDARY,
+                        if editing_routine_id.read().is_some() {
+                            "✏️ Edit Routine"
+                        } else {
+                            "⚡ Create Routine"
+                        }
+                    }
                     button {
                         class: BUTTON_ICON,
                         onclick: move |_| {
@@ -715,7 +1539,7 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
                             .map(|string| string.parse::<u32>().unwrap())
                             .collect();
                         let sched_item = Routine {
-                            id: None,
+                            id: editing_routine_id.read().clone(),
                             title: new_scheditem_title.read().clone(),
                             start_date: NaiveDate::parse_from_str(
                                     &new_scheditem_start_date.read(),
@@ -727,7 +1551,7 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
                                     "%Y-%m-%d",
                                 )
                                 .unwrap(),
-                            imp: new_scheditem_imp.read().clone(),
+                            imp: new_scheditem_imp.read().parse().unwrap_or(5),
                             start_time: NaiveTime::from_hms_opt(
                                     start_time_split[0],
                                     start_time_split[1],
@@ -737,20 +1561,37 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
                             end_time: NaiveTime::from_hms_opt(end_time_split[0], end_time_split[1], 0)
                                 .unwrap(),
                             weekdays: new_scheditem_weekdays.read().clone(),
+                            reminders: reminders_from_choice(
+                                &new_scheditem_reminders.read(),
+                                &new_scheditem_reminder.read(),
+                            ),
                         };
-                        match add_sched(sched_item).await {
-                            Ok(_) => {
-                                info.set(
-                                    format!(
-                                        "✅ Routine '{}' created successfully!",
-                                        new_scheditem_title.read(),
-                                    ),
-                                )
-                            }
-                            Err(_) => {
-                                info.set("❌ An error occurred while creating the routine".to_string())
-                            }
-                        };
+                        if sched_item.id.is_some() {
+                            match api().update_routine(&sched_item).await {
+                                Ok(_) => {
+                                    info.set(
+                                        format!(
+                                            "✅ Routine '{}' updated successfully!",
+                                            new_scheditem_title.read(),
+                                        ),
+                                    )
+                                }
+                                Err(err) => info.set(format!("❌ Couldn't update the routine: {}", err)),
+                            };
+                        } else {
+                            match api().add_routine(&sched_item).await {
+                                Ok(_) => {
+                                    info.set(
+                                        format!(
+                                            "✅ Routine '{}' created successfully!",
+                                            new_scheditem_title.read(),
+                                        ),
+                                    )
+                                }
+                                Err(err) => info.set(format!("❌ Couldn't create the routine: {}", err)),
+                            };
+                        }
+                        editing_routine_id.set(None);
                         new_scheditem_title.set(String::new());
                         new_scheditem_start_date.set(String::new());
                         new_scheditem_end_date.set(String::new());
@@ -758,6 +1599,9 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
                         new_scheditem_time_start.set(String::new());
                         new_scheditem_time_end.set(String::new());
                         new_scheditem_weekdays.set(Vec::new());
+                        new_scheditem_reminder.set(String::new());
+                        new_scheditem_reminders.set(Vec::new());
+                        routines.restart();
                     },
 
                     // Title
@@ -840,26 +1684,20 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
                         }
                     }
 
+                    ReminderSelect { label: "Remind me before it starts", choice: new_scheditem_reminder }
+
                     // Weekdays Selection
                     div { class: "space-y-3",
                         label { class: "block text-sm font-semibold text-slate-300", "Repeat on Days" }
                         div { class: "flex flex-wrap gap-2",
-                            for (day , label , color) in [
-                                ("Sunday", "Sun", "red"),
-                                ("Monday", "Mon", "blue"),
-                                ("Tuesday", "Tue", "blue"),
-                                ("Wednesday", "Wed", "blue"),
-                                ("Thursday", "Thu", "blue"),
-                                ("Friday", "Fri", "blue"),
-                                ("Saturday", "Sat", "blue"),
-                            ]
-                            {
+                            for day in Weekday::ALL {
                                 {
-                                    let is_selected = new_scheditem_weekdays.read().contains(&day.to_string());
-                                    let day_string = day.to_string();
+                                    let is_selected = new_scheditem_weekdays.read().contains(&day);
+                                    let color = if day == Weekday::Sunday { "red" } else { "blue" };
+                                    let label = day.short_name();
                                     rsx! {
                                         button {
-                                            key: "{day}",
+                                            key: "{label}",
                                             r#type: "button",
                                             class: if is_selected { format!(
                                                 "px-4 py-2 rounded-lg font-semibold text-sm bg-{}-500 text-white border-2 border-{}-500 transform scale-105 transition-all duration-200 shadow-lg",
@@ -867,7 +1705,7 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
                                                 color,
                                             ) } else { "px-4 py-2 rounded-lg font-medium text-sm bg-slate-900 text-slate-400 border-2 border-slate-700 hover:border-slate-600 hover:text-white transition-all duration-200"
                                                 .to_string() },
-                                            onclick: move |_| toggle_weekday(day_string.clone()),
+                                            onclick: move |_| toggle_weekday(day),
                                             "{label}"
                                         }
                                     }
@@ -887,18 +1725,129 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
                             new_scheditem_time_end.read().is_empty() ||
                             new_scheditem_weekdays.read().is_empty() }",
                             r#type: "submit",
-                            "✅ Create Routine"
+                            if editing_routine_id.read().is_some() {
+                                "💾 Save Routine"
+                            } else {
+                                "✅ Create Routine"
+                            }
                         }
                         button {
                             class: "flex-1 {BUTTON_SECONDARY}",
                             r#type: "button",
                             onclick: move |_| {
-                                open_sched_editor.set(false);
+                                if editing_routine_id.read().is_some() {
+                                    editing_routine_id.set(None);
+                                    new_scheditem_title.set(String::new());
+                                    new_scheditem_start_date.set(String::new());
+                                    new_scheditem_end_date.set(String::new());
+                                    new_scheditem_imp.set("5".to_string());
+                                    new_scheditem_time_start.set(String::new());
+                                    new_scheditem_time_end.set(String::new());
+                                    new_scheditem_weekdays.set(Vec::new());
+                                    new_scheditem_reminder.set(String::new());
+                                    new_scheditem_reminders.set(Vec::new());
+                                } else {
+                                    open_sched_editor.set(false);
+                                }
                             },
                             "Cancel"
                         }
                     }
                 }
+
+                // Existing Routines
+                div { class: "mt-10 pt-6 border-t border-slate-700 space-y-3",
+                    h2 { class: "text-xl font-bold text-teal-400 mb-4", "📆 Your Routines" }
+                    match &*routines.read() {
+                        Some(Ok(routines_vec)) => {
+                            if !routines_vec.is_empty() {
+                                let routine_elements = routines_vec
+                                    .iter()
+                                    .cloned()
+                                    .map(|routine| {
+                                        let id = routine.id.clone().unwrap_or_default();
+                                        let del_id = id.clone();
+                                        let title = routine.title.clone();
+                                        let date_range = format!(
+                                            "{} → {}",
+                                            routine.start_date.format("%d %b %Y"),
+                                            routine.end_date.format("%d %b %Y"),
+                                        );
+                                        let time_range = format!(
+                                            "{} - {}",
+                                            routine.start_time.format("%H:%M"),
+                                            routine.end_time.format("%H:%M"),
+                                        );
+                                        let weekdays = routine
+                                            .weekdays
+                                            .iter()
+                                            .map(|day| day.short_name())
+                                            .collect::<Vec<&str>>()
+                                            .join(", ");
+                                        rsx! {
+                                            div {
+                                                key: "{id}",
+                                                class: "group bg-slate-900/50 hover:bg-slate-900 border border-slate-700 hover:border-teal-500/50 rounded-lg p-4 transition-all duration-200 animate-fade-in",
+                                                div { class: "flex items-center gap-4",
+                                                    div { class: "flex-1",
+                                                        p { class: "text-white text-lg font-medium", "{title}" }
+                                                        p { class: "text-slate-400 text-sm", "{date_range} · {time_range}" }
+                                                        p { class: "text-slate-500 text-xs", "{weekdays}" }
+                                                    }
+                                                    button {
+                                                        class: BUTTON_ICON,
+                                                        onclick: move |_| {
+                                                            info.set(String::new());
+                                                            editing_routine_id.set(routine.id.clone());
+                                                            new_scheditem_title.set(routine.title.clone());
+                                                            new_scheditem_start_date.set(routine.start_date.to_string());
+                                                            new_scheditem_end_date.set(routine.end_date.to_string());
+                                                            new_scheditem_imp.set(routine.imp.to_string());
+                                                            new_scheditem_reminder.set(reminder_choice(&routine.reminders));
+                                                            new_scheditem_reminders.set(routine.reminders.clone());
+                                                            new_scheditem_time_start
+                                                                .set(routine.start_time.format("%H:%M").to_string());
+                                                            new_scheditem_time_end
+                                                                .set(routine.end_time.format("%H:%M").to_string());
+                                                            new_scheditem_weekdays.set(routine.weekdays.clone());
+                                                        },
+                                                        "✏️"
+                                                    }
+                                                    button {
+                                                        class: "opacity-0 group-hover:opacity-100 {BUTTON_DANGER}",
+                                                        onclick: move |_| {
+                                                            let id = del_id.clone();
+                                                            async move {
+                                                                let _ = api().delete_routine(&id).await;
+                                                                if editing_routine_id.read().as_ref() == Some(&id) {
+                                                                    editing_routine_id.set(None);
+                                                                }
+                                                                routines.restart();
+                                                            }
+                                                        },
+                                                        "🗑️"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    });
+                                rsx! {
+                                    {routine_elements}
+                                }
+                            } else {
+                                rsx! {
+                                    p { class: "text-slate-500 text-sm text-center py-6", "No routines yet" }
+                                }
+                            }
+                        }
+                        Some(Err(err)) => rsx! {
+                            p { class: "text-red-400 text-sm text-center py-6", "Couldn't load your routines: {err}" }
+                        },
+                        None => rsx! {
+                            div { class: "loading-skeleton h-16 rounded-lg" }
+                        },
+                    }
+                }
             }
         }
     }
@@ -906,25 +1855,37 @@ fn SchedEditor(open_sched_editor: Signal<bool>) -> Element {
 #[component]
 fn Task_details(curr_task_id: Signal<String>) -> Element {
     let curr_task = use_resource(use_reactive!(|curr_task_id| async move {
-        get_todo_by_id(curr_task_id()).await
+        api().get_task(&curr_task_id()).await
     }));
+    let mut is_editing = use_signal(|| false);
 
     rsx! {
         div { class: "min-h-screen bg-gradient-to-br from-slate-900 via-slate-800 to-slate-900 flex items-center justify-center px-4",
             div { class: "w-full max-w-2xl animate-fade-in-scale",
                 div { class: CARD_STYLE,
                     match &*curr_task.read() {
-                        Some(Some(task)) => {
+                        Some(Ok(Some(task))) if is_editing() => rsx! {
+                            EditTodo { task: task.clone(), is_editing, curr_task }
+                        },
+                        Some(Ok(Some(task))) => {
+                            let task = task.clone();
                             let due_formatted = task.due_by.format("%d %b %Y, %H:%M").to_string();
                             let imp_level: u8 = task.imp_lvl;
                             rsx! {
                                 // Header
                                 div { class: "flex items-center justify-between mb-8",
                                     h1 { class: "text-2xl font-bold text-teal-400", "📋 Task Details" }
-                                    button {
-                                        class: BUTTON_SECONDARY,
-                                        onclick: move |_| curr_task_id.set(String::new()),
-                                        "← Back"
+                                    div { class: "flex gap-3",
+                                        button {
+                                            class: BUTTON_PRIMARY,
+                                            onclick: move |_| is_editing.set(true),
+                                            "✏️ Edit"
+                                        }
+                                        button {
+                                            class: BUTTON_SECONDARY,
+                                            onclick: move |_| curr_task_id.set(String::new()),
+                                            "← Back"
+                                        }
                                     }
                                 }
 
@@ -996,9 +1957,52 @@ fn Task_details(curr_task_id: Signal<String>) -> Element {
                                         }
                                     }
                                 }
+
+                                if let Some(recurrence) = &task.recurrence {
+                                    div { class: "mb-6 p-4 bg-slate-900/50 rounded-lg border border-slate-700 flex items-center justify-between",
+                                        div {
+                                            div { class: "text-sm font-semibold text-slate-400 mb-2 uppercase tracking-wide",
+                                                "🔁 Repeats"
+                                            }
+                                            p { class: "text-white font-medium", "{recurrence.describe()}" }
+                                        }
+                                        button {
+                                            class: BUTTON_SECONDARY,
+                                            onclick: {
+                                                let id = task.id.clone().unwrap_or_default();
+                                                move |_| {
+                                                    let id = id.clone();
+                                                    async move {
+                                                        let patch = TaskPatch {
+                                                            recurrence: Some(None),
+                                                            ..TaskPatch::default()
+                                                        };
+                                                        let _ = api().edit_task(&id, &patch).await;
+                                                        curr_task.restart();
+                                                    }
+                                                }
+                                            },
+                                            "Stop repeating"
+                                        }
+                                    }
+                                }
+
+                                Subtasks {
+                                    task_id: task.id.clone().unwrap_or_default(),
+                                    curr_task_id,
+                                    curr_task,
+                                }
+
+                                Dependencies { task_id: task.id.clone().unwrap_or_default(), curr_task_id }
+
+                                TaskTags {
+                                    task_id: task.id.clone().unwrap_or_default(),
+                                    tag_ids: task.tags.clone(),
+                                    curr_task,
+                                }
                             }
                         }
-                        Some(None) => rsx! {
+                        Some(Ok(None)) => rsx! {
                             div { class: "flex items-center justify-between mb-8",
                                 h1 { class: "text-2xl font-bold text-teal-400", "Task not found for the given id" }
                             }
@@ -1010,6 +2014,18 @@ fn Task_details(curr_task_id: Signal<String>) -> Element {
                                 "Go Back"
                             }
                         },
+                        Some(Err(err)) => rsx! {
+                            div { class: "flex items-center justify-between mb-8",
+                                h1 { class: "text-2xl font-bold text-red-400", "Couldn't load the task: {err}" }
+                            }
+                            button {
+                                class: BUTTON_PRIMARY,
+                                onclick: move |_| {
+                                    curr_task_id.set(String::new());
+                                },
+                                "Go Back"
+                            }
+                        },
                         None => rsx! {
                             div { class: "text-center py-12",
                                 div { class: "loading-skeleton h-64 rounded-lg" }
@@ -1022,3 +2038,676 @@ fn Task_details(curr_task_id: Signal<String>) -> Element {
         }
     }
 }
+
+// Checklist of a task: its subtasks as a tree, the rolled up progress and a form to add steps
+#[component]
+fn Subtasks(
+    task_id: String,
+    curr_task_id: Signal<String>,
+    curr_task: Resource<ApiResult<Option<Task>>>,
+) -> Element {
+    let tree = use_resource(use_reactive!(|task_id| async move {
+        api().get_subtasks(&task_id).await
+    }));
+    let mut new_name = use_signal(String::new);
+    let mut new_minutes = use_signal(String::new);
+    let mut info = use_signal(String::new);
+
+    rsx! {
+        div { class: "p-4 bg-slate-900/50 rounded-lg border border-slate-700",
+            div { class: "text-sm font-semibold text-slate-400 mb-3 uppercase tracking-wide",
+                "🧩 Subtasks"
+            }
+            match &*tree.read() {
+                Some(Ok(root)) => {
+                    let root_id = root.task.id.clone().unwrap_or_default();
+                    let auto_complete = root.task.auto_complete;
+                    rsx! {
+                        if let Some(progress) = root.progress {
+                            div { class: "mb-4",
+                                div { class: "flex justify-between text-sm text-slate-400 mb-1",
+                                    span { "{progress.done} of {progress.total} done" }
+                                    span { "{progress.percent()}%" }
+                                }
+                                div { class: "h-2 rounded-full bg-slate-700 overflow-hidden",
+                                    div {
+                                        class: "h-full bg-teal-500 transition-all duration-300",
+                                        style: "width: {progress.percent()}%",
+                                    }
+                                }
+                            }
+                        }
+                        div { class: "space-y-1 mb-4",
+                            for node in root.subtasks.iter() {
+                                SubtaskNode {
+                                    key: "{node.task.id.clone().unwrap_or_default()}",
+                                    node: node.clone(),
+                                    depth: 0,
+                                    tree,
+                                    curr_task_id,
+                                    curr_task,
+                                }
+                            }
+                        }
+                        label { class: "flex items-center gap-2 text-sm text-slate-400 mb-4",
+                            input {
+                                class: CHECKBOX_STYLE,
+                                r#type: "checkbox",
+                                checked: auto_complete,
+                                onclick: move |_| {
+                                    let root_id = root_id.clone();
+                                    async move {
+                                        let patch = TaskPatch {
+                                            auto_complete: Some(!auto_complete),
+                                            ..TaskPatch::default()
+                                        };
+                                        match api().edit_task(&root_id, &patch).await {
+                                            Ok(_) => {
+                                                tree.restart();
+                                                curr_task.restart();
+                                            }
+                                            Err(err) => info.set(format!("❌ {}", err)),
+                                        }
+                                    }
+                                },
+                            }
+                            "Complete this task when all subtasks are done"
+                        }
+                    }
+                }
+                Some(Err(err)) => rsx! {
+                    p { class: "text-red-400 mb-4", "Couldn't load the subtasks: {err}" }
+                },
+                None => rsx! {
+                    div { class: "loading-skeleton h-10 rounded-lg mb-4" }
+                },
+            }
+
+            if !info.read().is_empty() {
+                div { class: "mb-3 p-3 bg-teal-500/10 border border-teal-500/30 rounded-lg text-teal-400 text-sm",
+                    "{info}"
+                }
+            }
+            form {
+                class: "flex gap-3",
+                onsubmit: move |_| {
+                    let task_id = task_id.clone();
+                    async move {
+                        match add_subtask_todo(task_id, new_name(), new_minutes()).await {
+                            Ok(_) => {
+                                new_name.set(String::new());
+                                new_minutes.set(String::new());
+                                info.set(String::new());
+                                tree.restart();
+                                curr_task.restart();
+                            }
+                            Err(err) => info.set(format!("❌ Couldn't add the subtask: {}", err)),
+                        }
+                    }
+                },
+                input {
+                    class: "flex-1 {INPUT_STYLE}",
+                    placeholder: "New subtask",
+                    value: "{new_name}",
+                    oninput: move |e| new_name.set(e.value()),
+                }
+                input {
+                    class: "w-32 {INPUT_STYLE}",
+                    r#type: "number",
+                    min: "1",
+                    placeholder: "Minutes",
+                    title: "Leave empty for a checklist item that doesn't get scheduled",
+                    value: "{new_minutes}",
+                    oninput: move |e| new_minutes.set(e.value()),
+                }
+                button {
+                    class: BUTTON_PRIMARY,
+                    r#type: "submit",
+                    disabled: "{new_name.read().trim().is_empty()}",
+                    "＋ Add"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SubtaskNode(
+    node: TaskTree,
+    depth: usize,
+    tree: Resource<ApiResult<TaskTree>>,
+    curr_task_id: Signal<String>,
+    curr_task: Resource<ApiResult<Option<Task>>>,
+) -> Element {
+    let id = node.task.id.clone().unwrap_or_default();
+    let open_id = id.clone();
+    let is_done = node.task.is_done;
+    let indent = depth * 24;
+    let req_time = node.task.req_time.format("%H:%M").to_string();
+
+    rsx! {
+        div {
+            class: "flex items-center gap-3 p-2 rounded-lg hover:bg-slate-800 transition-colors duration-200",
+            style: "margin-left: {indent}px",
+            input {
+                class: CHECKBOX_STYLE,
+                r#type: "checkbox",
+                checked: is_done,
+                onclick: move |_| {
+                    let id = id.clone();
+                    async move {
+                        let _ = api().set_done(&id, !is_done).await;
+                        tree.restart();
+                        curr_task.restart();
+                    }
+                },
+            }
+            button {
+                class: if is_done { "flex-1 text-left text-slate-500 line-through" } else { "flex-1 text-left text-white hover:text-teal-400" },
+                onclick: move |_| curr_task_id.set(open_id.clone()),
+                "{node.task.name}"
+            }
+            if let Some(progress) = node.progress {
+                span { class: "text-xs text-slate-400", "{progress.done}/{progress.total}" }
+            }
+            if node.task.req_time == NaiveTime::MIN {
+                span { class: "text-xs text-slate-500", "checklist" }
+            } else {
+                span { class: "text-xs text-slate-400", "{req_time}" }
+            }
+        }
+        for child in node.subtasks.iter() {
+            SubtaskNode {
+                key: "{child.task.id.clone().unwrap_or_default()}",
+                node: child.clone(),
+                depth: depth + 1,
+                tree,
+                curr_task_id,
+                curr_task,
+            }
+        }
+    }
+}
+
+// What a task waits for and what it unblocks, with controls to add and remove prerequisites
+#[component]
+fn Dependencies(task_id: String, curr_task_id: Signal<String>) -> Element {
+    let deps = use_resource(use_reactive!(|task_id| async move {
+        api().get_dependencies(&task_id).await
+    }));
+    let candidates = use_resource(|| async { api().get_tasks().await });
+    let mut chosen = use_signal(String::new);
+    let mut info = use_signal(String::new);
+
+    rsx! {
+        div { class: "mt-6 p-4 bg-slate-900/50 rounded-lg border border-slate-700",
+            div { class: "text-sm font-semibold text-slate-400 mb-3 uppercase tracking-wide",
+                "🔗 Dependencies"
+            }
+            match &*deps.read() {
+                Some(Ok(deps_info)) => {
+                    let known: HashSet<String> = deps_info
+                        .prerequisites
+                        .iter()
+                        .filter_map(|task| task.id.clone())
+                        .collect();
+                    rsx! {
+                        if deps_info.blocked {
+                            p { class: "mb-3 text-orange-400 text-sm",
+                                "🔒 Blocked until the tasks below are done, the scheduler leaves it out until then"
+                            }
+                        }
+                        div { class: "space-y-1 mb-4",
+                            if deps_info.prerequisites.is_empty() {
+                                p { class: "text-slate-500 text-sm", "Doesn't wait for any other task" }
+                            }
+                            for prerequisite in deps_info.prerequisites.iter() {
+                                {
+                                    let prerequisite_id = prerequisite.id.clone().unwrap_or_default();
+                                    let open_id = prerequisite_id.clone();
+                                    let task_id = task_id.clone();
+                                    rsx! {
+                                        div {
+                                            key: "{prerequisite_id}",
+                                            class: "flex items-center gap-3 p-2 rounded-lg hover:bg-slate-800",
+                                            span { if prerequisite.is_done { "✅" } else { "⏳" } }
+                                            button {
+                                                class: "flex-1 text-left text-white hover:text-teal-400",
+                                                onclick: move |_| curr_task_id.set(open_id.clone()),
+                                                "{prerequisite.name}"
+                                            }
+                                            button {
+                                                class: BUTTON_ICON,
+                                                title: "Remove this dependency",
+                                                onclick: move |_| {
+                                                    let task_id = task_id.clone();
+                                                    let prerequisite_id = prerequisite_id.clone();
+                                                    async move {
+                                                        if let Err(err) = api()
+                                                            .remove_dependency(&task_id, &prerequisite_id)
+                                                            .await
+                                                        {
+                                                            info.set(format!("❌ {}", err));
+                                                        }
+                                                        deps.restart();
+                                                    }
+                                                },
+                                                "✕"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if !deps_info.dependents.is_empty() {
+                            div { class: "mb-4 text-sm text-slate-400",
+                                "Unblocks: "
+                                for (index, dependent) in deps_info.dependents.iter().enumerate() {
+                                    if index > 0 {
+                                        ", "
+                                    }
+                                    span { class: "text-slate-200", "{dependent.name}" }
+                                }
+                            }
+                        }
+                        if let Some(Ok(tasks)) = &*candidates.read() {
+                            div { class: "flex gap-3",
+                                select {
+                                    class: "flex-1 {INPUT_STYLE}",
+                                    value: "{chosen}",
+                                    onchange: move |e| chosen.set(e.value()),
+                                    option { value: "", "Wait for another task…" }
+                                    for candidate in tasks.iter().filter(|candidate| {
+                                        candidate.id.as_ref().is_some_and(|id| *id != task_id && !known.contains(id))
+                                    })
+                                    {
+                                        option {
+                                            value: "{candidate.id.clone().unwrap_or_default()}",
+                                            "{candidate.name}"
+                                        }
+                                    }
+                                }
+                                button {
+                                    class: BUTTON_PRIMARY,
+                                    disabled: "{chosen.read().is_empty()}",
+                                    onclick: {
+                                        let task_id = task_id.clone();
+                                        move |_| {
+                                            let task_id = task_id.clone();
+                                            async move {
+                                                match api().add_dependency(&task_id, &chosen()).await {
+                                                    Ok(_) => {
+                                                        chosen.set(String::new());
+                                                        info.set(String::new());
+                                                    }
+                                                    Err(err) => info.set(format!("❌ {}", err)),
+                                                }
+                                                deps.restart();
+                                            }
+                                        }
+                                    },
+                                    "＋ Add"
+                                }
+                            }
+                        }
+                    }
+                }
+                Some(Err(err)) => rsx! {
+                    p { class: "text-red-400", "Couldn't load the dependencies: {err}" }
+                },
+                None => rsx! {
+                    div { class: "loading-skeleton h-10 rounded-lg" }
+                },
+            }
+            if !info.read().is_empty() {
+                div { class: "mt-3 p-3 bg-teal-500/10 border border-teal-500/30 rounded-lg text-teal-400 text-sm",
+                    "{info}"
+                }
+            }
+        }
+    }
+}
+
+// What a task is filed under. Clicking a tag files the task under it or takes it off again,
+// picking a project replaces the one the task had.
+#[component]
+fn TaskTags(
+    task_id: String,
+    tag_ids: Vec<String>,
+    curr_task: Resource<ApiResult<Option<Task>>>,
+) -> Element {
+    let mut all_tags = use_resource(|| async { api().list_tags(None).await });
+    let mut new_name = use_signal(String::new);
+    let mut new_kind = use_signal(TagKind::default);
+    let mut info = use_signal(String::new);
+
+    rsx! {
+        div { class: "mt-6 p-4 bg-slate-900/50 rounded-lg border border-slate-700",
+            div { class: "text-sm font-semibold text-slate-400 mb-3 uppercase tracking-wide",
+                "🏷️ Tags, Project & Contexts"
+            }
+            match &*all_tags.read() {
+                Some(Ok(tags)) => {
+                    let projects: HashSet<String> = tags
+                        .iter()
+                        .filter(|tag| tag.kind == TagKind::Project)
+                        .filter_map(|tag| tag.id.clone())
+                        .collect();
+                    rsx! {
+                        if tags.is_empty() {
+                            p { class: "mb-4 text-slate-500 text-sm", "No tags yet, create the first one below" }
+                        }
+                        div { class: "flex flex-wrap gap-2 mb-4",
+                            for tag in tags.iter() {
+                                {
+                                    let tag_id = tag.id.clone().unwrap_or_default();
+                                    let selected = tag_ids.contains(&tag_id);
+                                    let is_project = projects.contains(&tag_id);
+                                    let projects = projects.clone();
+                                    let task_id = task_id.clone();
+                                    let tag_ids = tag_ids.clone();
+                                    rsx! {
+                                        button {
+                                            key: "{tag_id}",
+                                            class: if selected { "px-3 py-1 text-sm rounded-full bg-teal-500/20 text-teal-300 border border-teal-500/50" } else { "px-3 py-1 text-sm rounded-full bg-slate-800 text-slate-400 border border-slate-700 hover:border-teal-500/50" },
+                                            title: "{tag.kind.label()}",
+                                            onclick: move |_| {
+                                                let mut next = tag_ids.clone();
+                                                if selected {
+                                                    next.retain(|id| *id != tag_id);
+                                                } else {
+                                                    if is_project {
+                                                        next.retain(|id| !projects.contains(id));
+                                                    }
+                                                    next.push(tag_id.clone());
+                                                }
+                                                let task_id = task_id.clone();
+                                                async move {
+                                                    match api().set_task_tags(&task_id, &next).await {
+                                                        Ok(_) => info.set(String::new()),
+                                                        Err(err) => info.set(format!("❌ {}", err)),
+                                                    }
+                                                    curr_task.restart();
+                                                }
+                                            },
+                                            "{tag.display_name()}"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Some(Err(err)) => rsx! {
+                    p { class: "mb-4 text-red-400", "Couldn't load the tags: {err}" }
+                },
+                None => rsx! {
+                    div { class: "loading-skeleton h-10 rounded-lg mb-4" }
+                },
+            }
+            // New tags are filed under right away
+            div { class: "flex gap-3",
+                select {
+                    class: INPUT_STYLE,
+                    value: new_kind().as_str(),
+                    onchange: move |e| {
+                        if let Some(kind) = TagKind::ALL.into_iter().find(|kind| kind.as_str() == e.value()) {
+                            new_kind.set(kind);
+                        }
+                    },
+                    for kind in TagKind::ALL {
+                        option { key: "{kind.as_str()}", value: kind.as_str(), "{kind.label()}" }
+                    }
+                }
+                input {
+                    class: "flex-1 {INPUT_STYLE}",
+                    placeholder: "New tag, project or @context",
+                    value: "{new_name}",
+                    oninput: move |e| new_name.set(e.value()),
+                }
+                button {
+                    class: BUTTON_PRIMARY,
+                    disabled: "{new_name.read().trim().is_empty()}",
+                    onclick: {
+                        let task_id = task_id.clone();
+                        move |_| {
+                            let task_id = task_id.clone();
+                            async move {
+                                let tag = Tag {
+                                    id: None,
+                                    name: new_name(),
+                                    kind: new_kind(),
+                                    color: None,
+                                };
+                                match add_tag_to_task(&task_id, tag).await {
+                                    Ok(()) => {
+                                        new_name.set(String::new());
+                                        info.set(String::new());
+                                    }
+                                    Err(err) => info.set(format!("❌ {}", err)),
+                                }
+                                all_tags.restart();
+                                curr_task.restart();
+                            }
+                        }
+                    },
+                    "＋ Add"
+                }
+            }
+            if !info.read().is_empty() {
+                div { class: "mt-3 p-3 bg-teal-500/10 border border-teal-500/30 rounded-lg text-teal-400 text-sm",
+                    "{info}"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn EditTodo(
+    task: Task,
+    is_editing: Signal<bool>,
+    curr_task: Resource<ApiResult<Option<Task>>>,
+) -> Element {
+    let id = task.id.clone().unwrap_or_default();
+    let mut info = use_signal(String::new);
+    let mut edit_todo_name = use_signal(|| task.name.clone());
+    let mut edit_todo_desc = use_signal(|| task.description.clone());
+    let mut edit_todo_due = use_signal(|| task.due_by.format("%Y-%m-%dT%H:%M").to_string());
+    let mut edit_todo_imp = use_signal(|| task.imp_lvl.to_string());
+    let mut edit_todo_req_time_hours = use_signal(|| task.req_time.hour() as u8);
+    let mut edit_todo_req_time_mins = use_signal(|| task.req_time.minute() as u8);
+    let edit_todo_reminder = use_signal(|| reminder_choice(&task.reminders));
+
+    rsx! {
+        // Header
+        div { class: "flex items-center justify-between mb-8",
+            h1 { class: HEADING_SECONDARY, "✏️ Edit Task" }
+            button {
+                class: BUTTON_ICON,
+                onclick: move |_| is_editing.set(false),
+                "✕"
+            }
+        }
+
+        // Info Message
+        if !info.read().is_empty() {
+            div { class: "mb-6 p-4 bg-teal-500/10 border border-teal-500/30 rounded-lg text-teal-400 animate-fade-in",
+                "{info}"
+            }
+        }
+
+        // Form
+        form {
+            class: "space-y-6",
+            onsubmit: move |_| {
+                let id = id.clone();
+                async move {
+                    let formatted_time = format!(
+                        "{:02}:{:02}",
+                        edit_todo_req_time_hours(),
+                        edit_todo_req_time_mins(),
+                    );
+                    match edit_todo(
+                            id,
+                            edit_todo_name.read().clone(),
+                            edit_todo_desc.read().clone(),
+                            edit_todo_due.read().clone(),
+                            formatted_time,
+                            edit_todo_imp.read().clone(),
+                            reminders_from_choice(&task.reminders, &edit_todo_reminder.read()),
+                        )
+                        .await
+                    {
+                        Ok(_) => {
+                            is_editing.set(false);
+                            curr_task.restart();
+                        }
+                        Err(err) => info.set(format!("❌ Couldn't save the task: {}", err)),
+                    };
+                }
+            },
+
+            // Task Name
+            div { class: "space-y-2",
+                label { class: "block text-sm font-semibold text-slate-300", "Task Name" }
+                input {
+                    class: INPUT_STYLE,
+                    r#type: "text",
+                    value: "{edit_todo_name}",
+                    oninput: move |e| edit_todo_name.set(e.value()),
+                }
+            }
+
+            // Description
+            div { class: "space-y-2",
+                label { class: "block text-sm font-semibold text-slate-300", "Description" }
+                textarea {
+                    class: INPUT_STYLE,
+                    rows: "4",
+                    value: "{edit_todo_desc}",
+                    oninput: move |e| edit_todo_desc.set(e.value()),
+                }
+            }
+
+            // Importance Level
+            div { class: "space-y-2",
+                label { class: "block text-sm font-semibold text-slate-300",
+                    "Importance Level: "
+                    span { class: "text-teal-400 font-bold", "{edit_todo_imp}/10" }
+                }
+                input {
+                    class: "w-full",
+                    r#type: "range",
+                    min: "1",
+                    max: "10",
+                    value: "{edit_todo_imp}",
+                    oninput: move |e| edit_todo_imp.set(e.value()),
+                }
+            }
+
+            ReminderSelect { label: "Remind me", choice: edit_todo_reminder }
+
+            // Duration
+            div { class: "space-y-2",
+                label { class: "block text-sm font-semibold text-slate-300", "Estimated Duration" }
+                div { class: "flex items-center gap-3",
+                    div { class: "flex-1",
+                        input {
+                            class: INPUT_STYLE,
+                            r#type: "number",
+                            min: "0",
+                            max: "23",
+                            placeholder: "Hours",
+                            value: "{edit_todo_req_time_hours}",
+                            oninput: move |e| {
+                                if let Ok(val) = e.value().parse::<u8>() {
+                                    if val <= 23 {
+                                        edit_todo_req_time_hours.set(val);
+                                    }
+                                }
+                            },
+                        }
+                    }
+                    span { class: "text-slate-400 font-bold", ":" }
+                    div { class: "flex-1",
+                        input {
+                            class: INPUT_STYLE,
+                            r#type: "number",
+                            min: "0",
+                            max: "59",
+                            placeholder: "Minutes",
+                            value: "{edit_todo_req_time_mins}",
+                            oninput: move |e| {
+                                if let Ok(val) = e.value().parse::<u8>() {
+                                    if val <= 59 {
+                                        edit_todo_req_time_mins.set(val);
+                                    }
+                                }
+                            },
+                        }
+                    }
+                }
+            }
+
+            // Due Date
+            div { class: "space-y-2",
+                label { class: "block text-sm font-semibold text-slate-300", "Due Date & Time" }
+                input {
+                    class: INPUT_STYLE,
+                    r#type: "datetime-local",
+                    value: "{edit_todo_due}",
+                    min: "2024-01-01T00:00",
+                    max: "3024-01-01T00:00",
+                    oninput: move |e| edit_todo_due.set(e.value()),
+                }
+            }
+
+            // Submit Buttons
+            div { class: "flex gap-3 pt-6",
+                button {
+                    class: "flex-1 {BUTTON_PRIMARY}",
+                    disabled: "{edit_todo_name.read().trim().is_empty() || edit_todo_due.read().is_empty()}",
+                    r#type: "submit",
+                    "💾 Save Changes"
+                }
+                button {
+                    class: "flex-1 {BUTTON_SECONDARY}",
+                    r#type: "button",
+                    onclick: move |_| is_editing.set(false),
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
+
+// Lead times the forms offer, empty is no reminder. A reminder set some other way is kept as an
+// extra choice so saving the form doesn't drop it.
+#[component]
+fn ReminderSelect(label: String, choice: Signal<String>) -> Element {
+    let mut choices: Vec<(String, String)> = vec![(String::new(), "No reminder".to_string())];
+    choices.extend(
+        [0, 15, 30, 60, 24 * 60]
+            .map(|minutes| (minutes.to_string(), Reminder::before(minutes).label())),
+    );
+    if let Ok(minutes) = choice.read().parse() {
+        if !choices.iter().any(|(value, _)| *value == *choice.read()) {
+            choices.push((choice.read().clone(), Reminder::before(minutes).label()));
+        }
+    }
+
+    rsx! {
+        div { class: "space-y-2",
+            label { class: "block text-sm font-semibold text-slate-300", "{label}" }
+            select {
+                class: INPUT_STYLE,
+                value: "{choice}",
+                onchange: move |e| choice.set(e.value()),
+                for (value, text) in choices {
+                    option { key: "{value}", value: "{value}", selected: value == *choice.read(), "{text}" }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Raised whenever the layout of a stored record changes, older archives are upgraded before
+// they are restored and newer ones are refused
+pub const BACKUP_VERSION: u32 = 3;
+pub const OLDEST_BACKUP_VERSION: u32 = 1;
+
+// The whole database as written by the admin export. Records are kept as the backend stores
+// them, ids included, keyed by table name.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub tables: BTreeMap<String, Vec<Value>>,
+}
+
+impl Backup {
+    pub fn check_version(&self) -> Result<(), String> {
+        match self.version {
+            OLDEST_BACKUP_VERSION..=BACKUP_VERSION => Ok(()),
+            version if version > BACKUP_VERSION => Err(format!(
+                "The archive has version {}, it was written by a newer TaskFlow than this one (version {})",
+                version, BACKUP_VERSION
+            )),
+            version => Err(format!(
+                "The archive has version {}, this TaskFlow restores versions {} to {}",
+                version, OLDEST_BACKUP_VERSION, BACKUP_VERSION
+            )),
+        }
+    }
+
+    // Rewrites the records of an older archive the way the database migrations rewrote them
+    pub fn upgrade(&mut self) {
+        if self.version < 2 {
+            // Version 1 kept the importance of routines as a string
+            let routines = self.tables.get_mut("static_schedule").into_iter().flatten();
+            for imp in routines.filter_map(|routine| routine.get_mut("imp")) {
+                if let Some(text) = imp.as_str() {
+                    let number = text.trim().parse::<u8>().map_or(5, |imp| imp.clamp(1, 10));
+                    *imp = Value::from(number);
+                }
+            }
+        }
+        // Version 2 had no committed_day table, the restore derives the markers from the blocks
+        self.version = BACKUP_VERSION;
+    }
+
+    pub fn record_count(&self) -> usize {
+        self.tables.values().map(Vec::len).sum()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreMode {
+    // Records of the archive overwrite the ones with the same id, everything else stays
+    #[default]
+    Merge,
+    // The database is emptied first
+    Replace,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RestoreQuery {
+    #[serde(default)]
+    pub mode: RestoreMode,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub mode: RestoreMode,
+    // Records written per table
+    pub restored: BTreeMap<String, usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup(version: u32) -> Backup {
+        Backup {
+            version,
+            exported_at: NaiveDateTime::default(),
+            tables: BTreeMap::from([
+                ("Tasks".to_string(), vec![Value::Null, Value::Null]),
+                ("tag".to_string(), vec![Value::Null]),
+            ]),
+        }
+    }
+
+    #[test]
+    fn versions_that_are_restored() {
+        assert!(backup(BACKUP_VERSION).check_version().is_ok());
+        assert!(backup(OLDEST_BACKUP_VERSION).check_version().is_ok());
+        assert!(
+            backup(BACKUP_VERSION + 1)
+                .check_version()
+                .unwrap_err()
+                .contains("newer")
+        );
+        assert!(backup(0).check_version().is_err());
+        assert_eq!(backup(BACKUP_VERSION).record_count(), 3);
+    }
+
+    #[test]
+    fn version_one_routines_get_a_numeric_importance() {
+        let mut archive = backup(1);
+        archive.tables.insert(
+            "static_schedule".to_string(),
+            vec![
+                serde_json::json!({ "title": "Gym", "imp": "7" }),
+                serde_json::json!({ "title": "Read", "imp": "often" }),
+            ],
+        );
+        archive.upgrade();
+        assert_eq!(archive.version, BACKUP_VERSION);
+        let imps: Vec<&Value> = archive.tables["static_schedule"]
+            .iter()
+            .map(|routine| &routine["imp"])
+            .collect();
+        assert_eq!(imps, [&Value::from(7), &Value::from(5)]);
+    }
+}
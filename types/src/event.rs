@@ -0,0 +1,62 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{Notification, Task};
+
+// A change to the signed in user's data, pushed by GET /events. Changes of other clients, of
+// imports and of the scheduler arrive the same way as the client's own.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    TaskCreated { task: Task },
+    TaskUpdated { task: Task },
+    TaskDeleted { id: String },
+    // The stored day plans of these dates were rebuilt
+    ScheduleRegenerated { dates: Vec<NaiveDate> },
+    // A reminder fired
+    NotificationCreated { notification: Notification },
+}
+
+impl LiveEvent {
+    // Name of the SSE event, the same as the `type` of its data
+    pub fn name(&self) -> &'static str {
+        match self {
+            LiveEvent::TaskCreated { .. } => "task_created",
+            LiveEvent::TaskUpdated { .. } => "task_updated",
+            LiveEvent::TaskDeleted { .. } => "task_deleted",
+            LiveEvent::ScheduleRegenerated { .. } => "schedule_regenerated",
+            LiveEvent::NotificationCreated { .. } => "notification_created",
+        }
+    }
+
+    pub fn is_task_change(&self) -> bool {
+        matches!(
+            self,
+            LiveEvent::TaskCreated { .. }
+                | LiveEvent::TaskUpdated { .. }
+                | LiveEvent::TaskDeleted { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_names_match_their_type() {
+        let events = [
+            LiveEvent::TaskDeleted {
+                id: "Tasks:abc".to_string(),
+            },
+            LiveEvent::ScheduleRegenerated {
+                dates: vec![NaiveDate::from_ymd_opt(2025, 3, 4).unwrap()],
+            },
+        ];
+        for event in events {
+            let json = serde_json::to_value(&event).unwrap();
+            assert_eq!(json["type"], event.name());
+            assert_eq!(serde_json::from_value::<LiveEvent>(json).unwrap(), event);
+        }
+    }
+}
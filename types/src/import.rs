@@ -0,0 +1,88 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+// What an imported calendar component turns into
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportTarget {
+    Routine,
+    Task,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    // Would be imported, only reported by dry runs
+    Ready,
+    Imported,
+    // Its UID was imported before, exported from this account or came earlier in the file
+    Duplicate,
+    // Nothing worth importing, like a cancelled or finished event
+    Skipped,
+    // Uses something TaskFlow can't express, `message` says what
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImportItem {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub target: ImportTarget,
+    pub status: ImportStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    // Parts that were approximated or dropped, the item is imported anyway
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    // Id of the created routine or task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    // True when nothing was written
+    pub dry_run: bool,
+    pub items: Vec<ImportItem>,
+}
+
+impl ImportReport {
+    pub fn count(&self, status: ImportStatus) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.status == status)
+            .count()
+    }
+}
+
+// Query string of the calendar import
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportQuery {
+    // Only report what would be imported
+    #[serde(default)]
+    pub dry_run: bool,
+    // Where series without an end stop, a year from today by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_until: Option<NaiveDate>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_default_to_a_real_import() {
+        let query: ImportQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query, ImportQuery::default());
+        let item: ImportItem = serde_json::from_str(
+            r#"{"uid":null,"summary":"Gym","target":"routine","status":"duplicate"}"#,
+        )
+        .unwrap();
+        let report = ImportReport {
+            dry_run: true,
+            items: vec![item],
+        };
+        assert_eq!(report.count(ImportStatus::Duplicate), 1);
+        assert_eq!(report.count(ImportStatus::Ready), 0);
+    }
+}
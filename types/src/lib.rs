@@ -0,0 +1,38 @@
+// API types shared by the backend and the frontend, so both sides agree on the wire format
+
+mod backup;
+mod event;
+mod import;
+mod priority;
+mod recurrence;
+mod reminder;
+mod routine;
+mod schedule;
+mod tag;
+mod task;
+mod task_csv;
+mod user;
+
+pub use backup::{
+    BACKUP_VERSION, Backup, OLDEST_BACKUP_VERSION, RestoreMode, RestoreQuery, RestoreReport,
+};
+pub use event::LiveEvent;
+pub use import::{ImportItem, ImportQuery, ImportReport, ImportStatus, ImportTarget};
+pub use priority::StrategyKind;
+pub use recurrence::{MAX_INTERVAL_DAYS, Recurrence};
+pub use reminder::{
+    MAX_REMINDER_MINUTES, MAX_REMINDERS, Notification, NotificationKind, NotificationQuery,
+    REMINDER_GRACE_MINUTES, Reminder,
+};
+pub use routine::{Routine, Weekday};
+pub use schedule::{BlockKind, DayPlan, SchedBlock, ScheduleQuery};
+pub use tag::{Tag, TagKind};
+pub use task::{
+    BlockedTask, NewSubtask, SortOrder, Task, TaskDependencies, TaskPage, TaskPatch, TaskProgress,
+    TaskQuery, TaskSort, TaskTree,
+};
+pub use task_csv::{
+    CsvColumn, CsvExportQuery, CsvImportQuery, CsvImportReport, RejectedRow, TaskColumn,
+    parse_due_by, parse_req_time,
+};
+pub use user::{AuthSession, Credentials, MIN_PASSWORD_LEN, User};
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+// Task prioritization strategies the scheduler can be asked for with `?strategy=`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    EarliestDeadline,
+    #[default]
+    Weighted,
+    ShortestRemaining,
+    Eisenhower,
+}
+
+impl StrategyKind {
+    pub const ALL: [StrategyKind; 4] = [
+        StrategyKind::Weighted,
+        StrategyKind::EarliestDeadline,
+        StrategyKind::ShortestRemaining,
+        StrategyKind::Eisenhower,
+    ];
+
+    // Same spelling as the serde representation, ready to be put in a query string
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StrategyKind::EarliestDeadline => "earliest_deadline",
+            StrategyKind::Weighted => "weighted",
+            StrategyKind::ShortestRemaining => "shortest_remaining",
+            StrategyKind::Eisenhower => "eisenhower",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StrategyKind::EarliestDeadline => "Earliest deadline first",
+            StrategyKind::Weighted => "Weighted importance/urgency",
+            StrategyKind::ShortestRemaining => "Shortest remaining time",
+            StrategyKind::Eisenhower => "Eisenhower quadrants",
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Weekday;
+
+// Longest interval of an every-N-days rule, ten years
+pub const MAX_INTERVAL_DAYS: u32 = 3650;
+
+// How the deadline of a task repeats. It travels as the matching iCalendar RRULE, e.g.
+// `FREQ=WEEKLY;BYDAY=MO,TH`, and every occurrence keeps the time of day of the first one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Recurrence {
+    Daily,
+    // On each of the given days, kept sorted Sunday first and without duplicates
+    Weekly(Vec<Weekday>),
+    // On this day of every month, or the last day of months that are shorter
+    Monthly(u32),
+    EveryNDays(u32),
+}
+
+impl Recurrence {
+    // Weekly rules with their days in a fixed order, so equal rules compare equal
+    pub fn weekly(days: impl IntoIterator<Item = Weekday>) -> Self {
+        let days: Vec<Weekday> = days.into_iter().collect();
+        Recurrence::Weekly(
+            Weekday::ALL
+                .into_iter()
+                .filter(|day| days.contains(day))
+                .collect(),
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Recurrence::Weekly(days) if days.is_empty() => {
+                Err("A weekly recurrence needs at least one weekday".to_string())
+            }
+            Recurrence::Monthly(day) if !(1..=31).contains(day) => {
+                Err("The day of the month must be between 1 and 31".to_string())
+            }
+            Recurrence::EveryNDays(0) => {
+                Err("A recurrence interval must be at least one day".to_string())
+            }
+            Recurrence::EveryNDays(days) if *days > MAX_INTERVAL_DAYS => Err(format!(
+                "A recurrence interval can be at most {} days",
+                MAX_INTERVAL_DAYS
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // The first occurrence strictly after `after`, or `after` itself when there is none left
+    // before the end of the calendar
+    pub fn next_after(&self, after: NaiveDateTime) -> NaiveDateTime {
+        let time = after.time();
+        let date = after.date();
+        match self {
+            Recurrence::Daily => after.checked_add_signed(TimeDelta::days(1)),
+            Recurrence::EveryNDays(days) => {
+                after.checked_add_signed(TimeDelta::days(i64::from(*days).max(1)))
+            }
+            Recurrence::Weekly(days) => (1..=7)
+                .filter_map(|offset| date.checked_add_days(Days::new(offset)))
+                .find(|date| days.contains(&Weekday::from(date.weekday())))
+                .or_else(|| date.checked_add_days(Days::new(7)))
+                .map(|date| date.and_time(time)),
+            Recurrence::Monthly(day) => (0..=12)
+                .filter_map(|offset| date.with_day(1)?.checked_add_months(Months::new(offset)))
+                .map(|month_start| day_in_month(month_start, *day).and_time(time))
+                .find(|candidate| *candidate > after),
+        }
+        .unwrap_or(after)
+    }
+
+    // Every occurrence after `first`, which itself comes first. Ends at the end of the calendar.
+    pub fn occurrences(&self, first: NaiveDateTime) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        std::iter::successors(Some(first), |previous| {
+            Some(self.next_after(*previous)).filter(|next| next > previous)
+        })
+    }
+
+    // Deadline of the instance that follows one due at `due_by`. Occurrences that already
+    // passed are skipped, so finishing late doesn't leave a trail of overdue instances.
+    pub fn next_due(&self, due_by: NaiveDateTime, now: NaiveDateTime) -> NaiveDateTime {
+        self.occurrences(due_by)
+            .skip(1)
+            .find(|due| *due > now)
+            .unwrap_or(due_by)
+    }
+
+    // Short description for people, e.g. "Every week on Mon, Thu"
+    pub fn describe(&self) -> String {
+        match self {
+            Recurrence::Daily => "Every day".to_string(),
+            Recurrence::Weekly(days) => {
+                let days: Vec<&str> = days.iter().map(|day| day.short_name()).collect();
+                format!("Every week on {}", days.join(", "))
+            }
+            Recurrence::Monthly(day) => format!("Every month on day {}", day),
+            Recurrence::EveryNDays(days) => format!("Every {} days", days),
+        }
+    }
+}
+
+// Clamps `day` to the length of the month that `month_start` is in
+fn day_in_month(month_start: NaiveDate, day: u32) -> NaiveDate {
+    (1..=day)
+        .rev()
+        .find_map(|day| month_start.with_day(day))
+        .unwrap_or(month_start)
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "FREQ=DAILY"),
+            Recurrence::Weekly(days) => {
+                let codes: Vec<&str> = days.iter().map(|day| day.rrule_code()).collect();
+                write!(f, "FREQ=WEEKLY;BYDAY={}", codes.join(","))
+            }
+            Recurrence::Monthly(day) => write!(f, "FREQ=MONTHLY;BYMONTHDAY={}", day),
+            Recurrence::EveryNDays(days) => write!(f, "FREQ=DAILY;INTERVAL={}", days),
+        }
+    }
+}
+
+// Accepts the subset of RRULE that `Display` writes, with or without an `RRULE:` prefix.
+// Parts may come in any order and in any case.
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let rule = rule.trim();
+        let rule = rule
+            .get(..6)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("RRULE:"))
+            .map_or(rule, |_| &rule[6..]);
+        let (mut freq, mut interval, mut by_day, mut by_month_day) = (None, None, None, None);
+        for part in rule
+            .split(';')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid recurrence part '{}'", part))?;
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => freq = Some(value.trim().to_ascii_uppercase()),
+                "INTERVAL" => interval = Some(parse_number(value, "INTERVAL")?),
+                "BYDAY" => {
+                    let days = value
+                        .split(',')
+                        .map(|code| {
+                            Weekday::from_rrule_code(code.trim())
+                                .ok_or_else(|| format!("Unknown weekday '{}'", code.trim()))
+                        })
+                        .collect::<Result<Vec<Weekday>, String>>()?;
+                    by_day = Some(days);
+                }
+                "BYMONTHDAY" => by_month_day = Some(parse_number(value, "BYMONTHDAY")?),
+                other => return Err(format!("Unsupported recurrence part '{}'", other)),
+            }
+        }
+        let recurrence = match (freq.as_deref(), interval.unwrap_or(1), by_day, by_month_day) {
+            (Some("DAILY"), 1, None, None) => Recurrence::Daily,
+            (Some("DAILY"), days, None, None) => Recurrence::EveryNDays(days),
+            (Some("WEEKLY"), 1, Some(days), None) => Recurrence::weekly(days),
+            (Some("MONTHLY"), 1, None, Some(day)) => Recurrence::Monthly(day),
+            (None, ..) => return Err("Recurrence is missing FREQ".to_string()),
+            _ => return Err(format!("Unsupported recurrence '{}'", rule)),
+        };
+        recurrence.validate()?;
+        Ok(recurrence)
+    }
+}
+
+fn parse_number(value: &str, key: &str) -> Result<u32, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{} must be a whole number, got '{}'", key, value.trim()))
+}
+
+impl Serialize for Recurrence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Recurrence {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rule = String::deserialize(deserializer)?;
+        rule.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TaskPatch;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn parses_every_supported_rule() {
+        assert_eq!("FREQ=DAILY".parse(), Ok(Recurrence::Daily));
+        assert_eq!(
+            "FREQ=DAILY;INTERVAL=3".parse(),
+            Ok(Recurrence::EveryNDays(3))
+        );
+        assert_eq!(
+            "FREQ=WEEKLY;BYDAY=TH,MO".parse(),
+            Ok(Recurrence::Weekly(vec![Weekday::Monday, Weekday::Thursday]))
+        );
+        assert_eq!(
+            "FREQ=MONTHLY;BYMONTHDAY=15".parse(),
+            Ok(Recurrence::Monthly(15))
+        );
+    }
+
+    #[test]
+    fn parsing_is_lenient_about_case_order_and_prefix() {
+        assert_eq!(
+            "RRULE:byday=we;freq=weekly".parse(),
+            Ok(Recurrence::Weekly(vec![Weekday::Wednesday]))
+        );
+        assert_eq!(" freq=daily;interval=1; ".parse(), Ok(Recurrence::Daily));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for rule in [
+            "",
+            "INTERVAL=2",
+            "FREQ=YEARLY",
+            "FREQ=WEEKLY",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=MONTHLY;BYMONTHDAY=32",
+            "FREQ=MONTHLY;BYMONTHDAY=0",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;INTERVAL=3651",
+            "FREQ=DAILY;INTERVAL=4294967295",
+            "FREQ=DAILY;INTERVAL=two",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO",
+            "FREQ=DAILY;COUNT=3",
+            "FREQ",
+        ] {
+            assert!(
+                rule.parse::<Recurrence>().is_err(),
+                "{} should not parse",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for recurrence in [
+            Recurrence::Daily,
+            Recurrence::EveryNDays(10),
+            Recurrence::weekly([Weekday::Saturday, Weekday::Sunday]),
+            Recurrence::Monthly(31),
+        ] {
+            assert_eq!(recurrence.to_string().parse(), Ok(recurrence));
+        }
+        assert_eq!(
+            Recurrence::weekly([Weekday::Friday, Weekday::Monday]).to_string(),
+            "FREQ=WEEKLY;BYDAY=MO,FR"
+        );
+    }
+
+    #[test]
+    fn travels_as_an_rrule_string() {
+        let json = serde_json::to_string(&Recurrence::Monthly(1)).unwrap();
+        assert_eq!(json, "\"FREQ=MONTHLY;BYMONTHDAY=1\"");
+        assert!(serde_json::from_str::<Recurrence>("\"FREQ=HOURLY\"").is_err());
+
+        let patch: TaskPatch = serde_json::from_str(r#"{"recurrence": null}"#).unwrap();
+        assert_eq!(patch.recurrence, Some(None));
+        let patch: TaskPatch = serde_json::from_str(r#"{"name": "Report"}"#).unwrap();
+        assert_eq!(patch.recurrence, None);
+    }
+
+    #[test]
+    fn daily_and_interval_keep_the_time_of_day() {
+        let due = at("2025-03-30", "17:45");
+        assert_eq!(Recurrence::Daily.next_after(due), at("2025-03-31", "17:45"));
+        assert_eq!(
+            Recurrence::EveryNDays(3).next_after(due),
+            at("2025-04-02", "17:45")
+        );
+    }
+
+    #[test]
+    fn weekly_moves_to_the_next_listed_day() {
+        // 2025-01-06 is a Monday
+        let rule = Recurrence::weekly([Weekday::Monday, Weekday::Thursday]);
+        let occurrences: Vec<NaiveDateTime> = rule
+            .occurrences(at("2025-01-06", "09:00"))
+            .take(4)
+            .collect();
+        assert_eq!(
+            occurrences,
+            vec![
+                at("2025-01-06", "09:00"),
+                at("2025-01-09", "09:00"),
+                at("2025-01-13", "09:00"),
+                at("2025-01-16", "09:00"),
+            ]
+        );
+        let single = Recurrence::weekly([Weekday::Monday]);
+        assert_eq!(
+            single.next_after(at("2025-01-06", "09:00")),
+            at("2025-01-13", "09:00")
+        );
+    }
+
+    #[test]
+    fn monthly_clamps_to_short_months() {
+        let rule = Recurrence::Monthly(31);
+        let occurrences: Vec<NaiveDateTime> = rule
+            .occurrences(at("2024-01-31", "12:00"))
+            .take(4)
+            .collect();
+        assert_eq!(
+            occurrences,
+            vec![
+                at("2024-01-31", "12:00"),
+                at("2024-02-29", "12:00"),
+                at("2024-03-31", "12:00"),
+                at("2024-04-30", "12:00"),
+            ]
+        );
+        // A first deadline before the rule's day still lands on it in the same month
+        assert_eq!(
+            Recurrence::Monthly(15).next_after(at("2025-12-03", "08:00")),
+            at("2025-12-15", "08:00")
+        );
+        assert_eq!(
+            Recurrence::Monthly(15).next_after(at("2025-12-15", "08:00")),
+            at("2026-01-15", "08:00")
+        );
+    }
+
+    #[test]
+    fn next_due_skips_occurrences_that_already_passed() {
+        let due = at("2025-01-01", "10:00");
+        assert_eq!(
+            Recurrence::Daily.next_due(due, at("2024-12-31", "00:00")),
+            at("2025-01-02", "10:00")
+        );
+        assert_eq!(
+            Recurrence::Daily.next_due(due, at("2025-01-05", "12:00")),
+            at("2025-01-06", "10:00")
+        );
+    }
+
+    #[test]
+    fn the_longest_interval_is_allowed() {
+        assert_eq!(
+            "FREQ=DAILY;INTERVAL=3650".parse(),
+            Ok(Recurrence::EveryNDays(MAX_INTERVAL_DAYS))
+        );
+    }
+
+    #[test]
+    fn occurrences_end_with_the_calendar() {
+        let last_day = NaiveDate::MAX.and_hms_opt(10, 0, 0).unwrap();
+        for recurrence in [
+            Recurrence::Daily,
+            Recurrence::EveryNDays(MAX_INTERVAL_DAYS),
+            Recurrence::EveryNDays(u32::MAX),
+            Recurrence::weekly([Weekday::Monday]),
+            Recurrence::Monthly(31),
+        ] {
+            assert_eq!(recurrence.next_after(last_day), last_day, "{}", recurrence);
+            assert_eq!(
+                recurrence.occurrences(last_day).count(),
+                1,
+                "{}",
+                recurrence
+            );
+            assert_eq!(
+                recurrence.next_due(last_day, last_day),
+                last_day,
+                "{}",
+                recurrence
+            );
+        }
+    }
+}
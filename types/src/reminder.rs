@@ -0,0 +1,146 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+// Furthest ahead a reminder can be, a week before
+pub const MAX_REMINDER_MINUTES: u32 = 7 * 24 * 60;
+pub const MAX_REMINDERS: usize = 5;
+// A reminder that came due while the backend was down still fires if it is back within this
+// long, later ones are dropped
+pub const REMINDER_GRACE_MINUTES: i64 = 60;
+
+// When to be reminded of a task's due time or a routine's start, 0 minutes is at that moment
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Reminder {
+    pub minutes_before: u32,
+}
+
+impl Reminder {
+    pub const AT_START: Reminder = Reminder { minutes_before: 0 };
+
+    pub fn before(minutes_before: u32) -> Self {
+        Self { minutes_before }
+    }
+
+    pub fn remind_at(self, at: NaiveDateTime) -> NaiveDateTime {
+        at - TimeDelta::minutes(self.minutes_before.into())
+    }
+
+    pub fn is_due(self, at: NaiveDateTime, now: NaiveDateTime) -> bool {
+        let remind_at = self.remind_at(at);
+        remind_at <= now && now < remind_at + TimeDelta::minutes(REMINDER_GRACE_MINUTES)
+    }
+
+    // "at start", "30 min before", "2 h before", "1 day before"
+    pub fn label(self) -> String {
+        match self.minutes_before {
+            0 => "at start".to_string(),
+            minutes if minutes % (24 * 60) == 0 => match minutes / (24 * 60) {
+                1 => "1 day before".to_string(),
+                days => format!("{} days before", days),
+            },
+            minutes if minutes % 60 == 0 => format!("{} h before", minutes / 60),
+            minutes => format!("{} min before", minutes),
+        }
+    }
+}
+
+pub fn validate_reminders(reminders: &[Reminder]) -> Result<(), String> {
+    if reminders.len() > MAX_REMINDERS {
+        return Err(format!("At most {} reminders are allowed", MAX_REMINDERS));
+    }
+    if reminders
+        .iter()
+        .any(|reminder| reminder.minutes_before > MAX_REMINDER_MINUTES)
+    {
+        return Err("Reminders can be at most a week ahead".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    TaskDue,
+    RoutineStart,
+}
+
+// A reminder that fired, kept until the user reads it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: Option<String>,
+    pub kind: NotificationKind,
+    // Id of the task or routine
+    pub source: String,
+    pub title: String,
+    pub message: String,
+    // Due time of the task or start of the routine
+    pub at: NaiveDateTime,
+    pub remind_at: NaiveDateTime,
+    pub read: bool,
+}
+
+impl Notification {
+    // "Due in 30 min", "Starts now"
+    pub fn describe(kind: NotificationKind, reminder: Reminder) -> String {
+        let lead = match reminder.minutes_before {
+            0 => "now".to_string(),
+            _ => format!("in {}", reminder.label().trim_end_matches(" before")),
+        };
+        match kind {
+            NotificationKind::TaskDue => format!("Due {}", lead),
+            NotificationKind::RoutineStart => format!("Starts {}", lead),
+        }
+    }
+}
+
+// Query string of the notification list
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NotificationQuery {
+    // Only the ones not read yet
+    #[serde(default)]
+    pub unread: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn reminders_are_due_from_their_moment_for_a_grace_period() {
+        let due = at("2025-03-04 10:00");
+        let half_hour = Reminder::before(30);
+        assert_eq!(half_hour.remind_at(due), at("2025-03-04 09:30"));
+        assert!(!half_hour.is_due(due, at("2025-03-04 09:29")));
+        assert!(half_hour.is_due(due, at("2025-03-04 09:30")));
+        assert!(half_hour.is_due(due, at("2025-03-04 10:29")));
+        assert!(!half_hour.is_due(due, at("2025-03-04 10:30")));
+        assert!(Reminder::AT_START.is_due(due, due));
+    }
+
+    #[test]
+    fn labels_and_messages() {
+        assert_eq!(Reminder::AT_START.label(), "at start");
+        assert_eq!(Reminder::before(30).label(), "30 min before");
+        assert_eq!(Reminder::before(120).label(), "2 h before");
+        assert_eq!(Reminder::before(24 * 60).label(), "1 day before");
+        assert_eq!(
+            Notification::describe(NotificationKind::TaskDue, Reminder::before(30)),
+            "Due in 30 min"
+        );
+        assert_eq!(
+            Notification::describe(NotificationKind::RoutineStart, Reminder::AT_START),
+            "Starts now"
+        );
+    }
+
+    #[test]
+    fn reminder_limits() {
+        assert!(validate_reminders(&[Reminder::AT_START, Reminder::before(60)]).is_ok());
+        assert!(validate_reminders(&[Reminder::before(MAX_REMINDER_MINUTES + 1)]).is_err());
+        assert!(validate_reminders(&[Reminder::AT_START; MAX_REMINDERS + 1]).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Deserializer, Serialize, de::Error};
+
+use crate::reminder::{Reminder, validate_reminders};
+use crate::task::validate_imp_lvl;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Weekday {
+    Sunday,
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Sunday,
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+    ];
+
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Weekday::Sunday => "Sun",
+            Weekday::Monday => "Mon",
+            Weekday::Tuesday => "Tue",
+            Weekday::Wednesday => "Wed",
+            Weekday::Thursday => "Thu",
+            Weekday::Friday => "Fri",
+            Weekday::Saturday => "Sat",
+        }
+    }
+
+    // Two letter code used by iCalendar recurrence rules
+    pub fn rrule_code(self) -> &'static str {
+        match self {
+            Weekday::Sunday => "SU",
+            Weekday::Monday => "MO",
+            Weekday::Tuesday => "TU",
+            Weekday::Wednesday => "WE",
+            Weekday::Thursday => "TH",
+            Weekday::Friday => "FR",
+            Weekday::Saturday => "SA",
+        }
+    }
+
+    pub fn from_rrule_code(code: &str) -> Option<Self> {
+        Weekday::ALL
+            .into_iter()
+            .find(|day| day.rrule_code().eq_ignore_ascii_case(code))
+    }
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(day: chrono::Weekday) -> Self {
+        match day {
+            chrono::Weekday::Sun => Weekday::Sunday,
+            chrono::Weekday::Mon => Weekday::Monday,
+            chrono::Weekday::Tue => Weekday::Tuesday,
+            chrono::Weekday::Wed => Weekday::Wednesday,
+            chrono::Weekday::Thu => Weekday::Thursday,
+            chrono::Weekday::Fri => Weekday::Friday,
+            chrono::Weekday::Sat => Weekday::Saturday,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Routine {
+    pub id: Option<String>,
+    pub title: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    // Clients from before it was a number send it as a string
+    #[serde(deserialize_with = "number_or_string")]
+    pub imp: u8,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub weekdays: Vec<Weekday>,
+    // When to be notified before each time the routine starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reminders: Vec<Reminder>,
+}
+
+impl Routine {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.title.trim().is_empty() {
+            return Err("Routine title cannot be empty".to_string());
+        }
+        validate_imp_lvl(self.imp)?;
+        if self.end_date < self.start_date {
+            return Err("End date cannot be before the start date".to_string());
+        }
+        if self.end_time <= self.start_time {
+            return Err("End time must be after the start time".to_string());
+        }
+        if self.weekdays.is_empty() {
+            return Err("At least one weekday must be selected".to_string());
+        }
+        validate_reminders(&self.reminders)
+    }
+
+    // Whether the routine takes place on the given date, both range and weekday have to match
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        (self.start_date..=self.end_date).contains(&date)
+            && self.weekdays.contains(&Weekday::from(date.weekday()))
+    }
+
+    // Every time the routine starts from `from` to `to`, both included
+    pub fn starts_between(&self, from: NaiveDateTime, to: NaiveDateTime) -> Vec<NaiveDateTime> {
+        from.date()
+            .iter_days()
+            .take_while(|date| *date <= to.date())
+            .filter(|date| self.occurs_on(*date))
+            .map(|date| date.and_time(self.start_time))
+            .filter(|start| (from..=to).contains(start))
+            .collect()
+    }
+}
+
+fn number_or_string<'de, D>(deserializer: D) -> Result<u8, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Importance {
+        Number(u8),
+        Text(String),
+    }
+    match Importance::deserialize(deserializer)? {
+        Importance::Number(imp) => Ok(imp),
+        Importance::Text(imp) => imp
+            .trim()
+            .parse()
+            .map_err(|_| D::Error::custom(format!("'{}' is not an importance", imp))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn importance_as_number_or_string() {
+        let routine = |imp: &str| {
+            serde_json::from_str::<Routine>(&format!(
+                r#"{{"id":null,"title":"Gym","start_date":"2025-03-03","end_date":"2025-03-31",
+                    "imp":{},"start_time":"18:00:00","end_time":"19:00:00","weekdays":["Monday"]}}"#,
+                imp
+            ))
+        };
+        assert_eq!(routine("7").unwrap().imp, 7);
+        assert_eq!(routine(r#""7""#).unwrap().imp, 7);
+        assert!(routine(r#""high""#).is_err());
+        let mut invalid = routine("7").unwrap();
+        invalid.imp = 11;
+        assert!(invalid.validate().is_err());
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn routine(start_date: &str, end_date: &str, weekdays: Vec<Weekday>) -> Routine {
+        Routine {
+            id: None,
+            title: "Routine".to_string(),
+            start_date: date(start_date),
+            end_date: date(end_date),
+            imp: 5,
+            start_time: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+            weekdays,
+            reminders: Vec::new(),
+        }
+    }
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn occurs_across_week_boundaries() {
+        // Starts on Thursday the 6th and runs over Sunday the 9th into Monday the 10th
+        let weekend_and_monday = routine(
+            "2025-03-06",
+            "2025-03-12",
+            vec![Weekday::Saturday, Weekday::Sunday, Weekday::Monday],
+        );
+        let days: Vec<NaiveDate> = date("2025-03-01")
+            .iter_days()
+            .take(21)
+            .filter(|day| weekend_and_monday.occurs_on(*day))
+            .collect();
+        assert_eq!(
+            days,
+            vec![date("2025-03-08"), date("2025-03-09"), date("2025-03-10")]
+        );
+        // Monday the 3rd matches the weekday but is before the range starts
+        assert!(!weekend_and_monday.occurs_on(date("2025-03-03")));
+        assert_eq!(
+            weekend_and_monday.starts_between(at("2025-03-07 12:00"), at("2025-03-12 12:00")),
+            vec![
+                at("2025-03-08 09:00"),
+                at("2025-03-09 09:00"),
+                at("2025-03-10 09:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn occurs_on_the_last_day_of_its_range() {
+        // The range ends on a Sunday
+        let sundays = routine("2025-03-05", "2025-03-16", vec![Weekday::Sunday]);
+        assert!(sundays.occurs_on(date("2025-03-09")));
+        assert!(sundays.occurs_on(date("2025-03-16")));
+        assert!(!sundays.occurs_on(date("2025-03-23")));
+        assert_eq!(
+            sundays.starts_between(at("2025-03-14 00:00"), at("2025-03-23 23:59")),
+            vec![at("2025-03-16 09:00")]
+        );
+        // A range of a single day only matches when that day is one of the weekdays
+        let one_day = routine("2025-03-10", "2025-03-10", vec![Weekday::Monday]);
+        assert!(one_day.occurs_on(date("2025-03-10")));
+        let wrong_day = routine("2025-03-10", "2025-03-10", vec![Weekday::Tuesday]);
+        assert!(!wrong_day.occurs_on(date("2025-03-10")));
+    }
+
+    #[test]
+    fn starts_within_a_range() {
+        let gym = Routine {
+            id: None,
+            title: "Gym".to_string(),
+            start_date: NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2025, 3, 31).unwrap(),
+            imp: 5,
+            start_time: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+            weekdays: vec![Weekday::Monday, Weekday::Wednesday],
+            reminders: Vec::new(),
+        };
+        // Monday the 3rd starts before `from` and Monday the 10th after `to`
+        assert_eq!(
+            gym.starts_between(at("2025-03-03 18:30"), at("2025-03-10 17:59")),
+            vec![at("2025-03-05 18:00")]
+        );
+        assert!(
+            gym.starts_between(at("2025-03-31 18:01"), at("2025-04-07 18:00"))
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::StrategyKind;
+use crate::tag::split_ids;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlockKind {
+    Routine,
+    Task,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchedBlock {
+    pub id: Option<String>,
+    pub date: NaiveDate,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub title: String,
+    pub kind: BlockKind,
+    // Routine or task this block was generated from
+    pub source: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DayPlan {
+    pub date: NaiveDate,
+    // False while the plan is only a preview that nothing has been booked for
+    pub committed: bool,
+    pub blocks: Vec<SchedBlock>,
+    // Projects or contexts the day was limited to, empty when every task was considered
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+}
+
+// Options for planning a day
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleQuery {
+    #[serde(default)]
+    pub strategy: StrategyKind,
+    // Comma separated project or context ids. When set, only tasks filed under at least one of
+    // them are booked, e.g. just the @office tasks on a workday. A committed plan remembers it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub only: Option<String>,
+}
+
+impl ScheduleQuery {
+    pub fn only_ids(&self) -> Vec<&str> {
+        split_ids(self.only.as_deref())
+    }
+}
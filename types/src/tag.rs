@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+// Tags are free-form labels, a task belongs to at most one project, and contexts name the place
+// or tool a task needs, like @office or @phone
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagKind {
+    #[default]
+    Tag,
+    Project,
+    Context,
+}
+
+impl TagKind {
+    pub const ALL: [TagKind; 3] = [TagKind::Tag, TagKind::Project, TagKind::Context];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TagKind::Tag => "tag",
+            TagKind::Project => "project",
+            TagKind::Context => "context",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TagKind::Tag => "Tag",
+            TagKind::Project => "Project",
+            TagKind::Context => "Context",
+        }
+    }
+
+    // Written in front of the name wherever a tag is shown
+    pub fn prefix(self) -> &'static str {
+        match self {
+            TagKind::Tag => "#",
+            TagKind::Project => "",
+            TagKind::Context => "@",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub kind: TagKind,
+    // CSS hex color like #14b8a6
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+impl Tag {
+    // Names are stored without the prefix, so "@office" and "office" are the same context
+    pub fn normalized(mut self) -> Self {
+        let name = self.name.trim();
+        self.name = name
+            .strip_prefix(self.kind.prefix())
+            .unwrap_or(name)
+            .trim()
+            .to_string();
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Tag name cannot be empty".to_string());
+        }
+        if self.name.chars().count() > 50 {
+            return Err("Tag name can be at most 50 characters long".to_string());
+        }
+        if let Some(color) = &self.color {
+            let hex = color.strip_prefix('#').unwrap_or_default();
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("'{}' is not a color like #14b8a6", color));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn display_name(&self) -> String {
+        format!("{}{}", self.kind.prefix(), self.name)
+    }
+}
+
+// Query parameters take several ids as one comma separated list
+pub(crate) fn split_ids(list: Option<&str>) -> Vec<&str> {
+    list.into_iter()
+        .flat_map(|list| list.split(','))
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str, kind: TagKind) -> Tag {
+        Tag {
+            id: None,
+            name: name.to_string(),
+            kind,
+            color: None,
+        }
+    }
+
+    #[test]
+    fn normalizing_strips_the_prefix_of_the_kind_only() {
+        assert_eq!(
+            tag(" @office ", TagKind::Context).normalized().name,
+            "office"
+        );
+        assert_eq!(tag("#urgent", TagKind::Tag).normalized().name, "urgent");
+        assert_eq!(tag("@home", TagKind::Tag).normalized().name, "@home");
+        assert_eq!(
+            tag("office", TagKind::Context).normalized().display_name(),
+            "@office"
+        );
+    }
+
+    #[test]
+    fn colors_have_to_be_six_digit_hex() {
+        let mut colored = tag("Thesis", TagKind::Project);
+        colored.color = Some("#14b8a6".to_string());
+        assert!(colored.validate().is_ok());
+        colored.color = Some("teal".to_string());
+        assert!(colored.validate().is_err());
+        assert!(tag("  ", TagKind::Tag).validate().is_err());
+    }
+
+    #[test]
+    fn id_lists_skip_blanks() {
+        assert_eq!(split_ids(Some("tag:a, tag:b,,")), vec!["tag:a", "tag:b"]);
+        assert!(split_ids(None).is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+use chrono::{NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::Recurrence;
+use crate::reminder::{Reminder, validate_reminders};
+use crate::tag::split_ids;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    pub id: Option<String>,
+    pub name: String,
+    pub description: String,
+    pub due_by: NaiveDateTime,
+    pub imp_lvl: u8,
+    pub req_time: NaiveTime,
+    pub time_alloted: NaiveTime,
+    pub is_done: bool,
+    // Mark this task done once all of its subtasks are, and reopen it when one is reopened
+    #[serde(default)]
+    pub auto_complete: bool,
+    // Completing a recurring task creates its next instance, which takes the rule over
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    // Ids of the tags, project and contexts the task is filed under, set through its tag endpoint
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // When to be notified before the task is due
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reminders: Vec<Reminder>,
+}
+
+// Partial update payload, only the fields that are present get merged into the record
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskPatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_by: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imp_lvl: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub req_time: Option<NaiveTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_done: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_complete: Option<bool>,
+    // `Some(None)`, sent as `null`, stops the task from recurring
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recurrence: Option<Option<Recurrence>>,
+    // Replaces all reminders, an empty list removes them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminders: Option<Vec<Reminder>>,
+}
+
+// Tells a field that was sent as null apart from one that was left out
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+// A step under an existing task. Anything left out is taken from the parent, and without a
+// required time the subtask is a plain checklist item that the scheduler never books.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NewSubtask {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_by: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imp_lvl: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub req_time: Option<NaiveTime>,
+}
+
+// How many of the subtasks below a task, at any depth, are done
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskProgress {
+    pub done: u32,
+    pub total: u32,
+}
+
+impl TaskProgress {
+    pub fn percent(&self) -> u32 {
+        (self.done * 100).checked_div(self.total).unwrap_or(100)
+    }
+}
+
+// A task together with everything below it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TaskTree {
+    pub task: Task,
+    // None for tasks without subtasks
+    pub progress: Option<TaskProgress>,
+    pub subtasks: Vec<TaskTree>,
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Task name cannot be empty".to_string());
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_imp_lvl(imp_lvl: u8) -> Result<(), String> {
+    if !(1..=10).contains(&imp_lvl) {
+        return Err("Importance level must be between 1 and 10".to_string());
+    }
+    Ok(())
+}
+
+fn validate_req_time(req_time: NaiveTime) -> Result<(), String> {
+    if req_time == NaiveTime::MIN {
+        return Err("Required time must be greater than zero".to_string());
+    }
+    Ok(())
+}
+
+impl Task {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name)?;
+        validate_imp_lvl(self.imp_lvl)?;
+        validate_req_time(self.req_time)?;
+        if let Some(recurrence) = &self.recurrence {
+            recurrence.validate()?;
+        }
+        validate_reminders(&self.reminders)?;
+        Ok(())
+    }
+}
+
+impl NewSubtask {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name)?;
+        if let Some(imp_lvl) = self.imp_lvl {
+            validate_imp_lvl(imp_lvl)?;
+        }
+        if let Some(req_time) = self.req_time {
+            validate_req_time(req_time)?;
+        }
+        Ok(())
+    }
+}
+
+impl TaskPatch {
+    pub fn validate(&self) -> Result<(), String> {
+        if *self == TaskPatch::default() {
+            return Err("Nothing to update".to_string());
+        }
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        if let Some(imp_lvl) = self.imp_lvl {
+            validate_imp_lvl(imp_lvl)?;
+        }
+        if let Some(req_time) = self.req_time {
+            validate_req_time(req_time)?;
+        }
+        if let Some(Some(recurrence)) = &self.recurrence {
+            recurrence.validate()?;
+        }
+        if let Some(reminders) = &self.reminders {
+            validate_reminders(reminders)?;
+        }
+        Ok(())
+    }
+}
+
+// What a task waits for and what waits for it. Blocked while any prerequisite is still open.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TaskDependencies {
+    pub prerequisites: Vec<Task>,
+    pub dependents: Vec<Task>,
+    pub blocked: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlockedTask {
+    pub task: Task,
+    // The prerequisites that are not done yet
+    pub waiting_on: Vec<Task>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSort {
+    #[default]
+    DueBy,
+    ImpLvl,
+    Name,
+    ReqTime,
+}
+
+impl TaskSort {
+    // Name of the Tasks field this sorts on
+    pub fn field(self) -> &'static str {
+        match self {
+            TaskSort::DueBy => "due_by",
+            TaskSort::ImpLvl => "imp_lvl",
+            TaskSort::Name => "name",
+            TaskSort::ReqTime => "req_time",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+// Query string of the task list, every filter is optional
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_done: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_after: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_before: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_imp: Option<u8>,
+    // Case-insensitive match against name and description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    // `true` leaves out subtasks, `false` lists only subtasks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_level: Option<bool>,
+    // Comma separated tag ids, only tasks carrying all of them are listed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    #[serde(default)]
+    pub sort: TaskSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    // `next_cursor` of the previous page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl TaskQuery {
+    pub fn tag_ids(&self) -> Vec<&str> {
+        split_ids(self.tags.as_deref())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskPage {
+    pub items: Vec<Task>,
+    // Set when there are more tasks, pass it back as `cursor` to get them
+    pub next_cursor: Option<String>,
+}
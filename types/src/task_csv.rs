@@ -0,0 +1,337 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::Task;
+
+// A column of the task CSV files, the name doubles as the header the export writes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskColumn {
+    Id,
+    Name,
+    Description,
+    DueBy,
+    ImpLvl,
+    ReqTime,
+    TimeAlloted,
+    IsDone,
+    Recurrence,
+    Tags,
+}
+
+impl TaskColumn {
+    pub const ALL: [TaskColumn; 10] = [
+        TaskColumn::Id,
+        TaskColumn::Name,
+        TaskColumn::Description,
+        TaskColumn::DueBy,
+        TaskColumn::ImpLvl,
+        TaskColumn::ReqTime,
+        TaskColumn::TimeAlloted,
+        TaskColumn::IsDone,
+        TaskColumn::Recurrence,
+        TaskColumn::Tags,
+    ];
+
+    // Written when the export isn't told which columns to use
+    pub const DEFAULT_EXPORT: [TaskColumn; 6] = [
+        TaskColumn::Name,
+        TaskColumn::Description,
+        TaskColumn::DueBy,
+        TaskColumn::ImpLvl,
+        TaskColumn::ReqTime,
+        TaskColumn::IsDone,
+    ];
+
+    // The only columns the import reads, everything else of a row is ignored
+    pub const IMPORTED: [TaskColumn; 5] = [
+        TaskColumn::Name,
+        TaskColumn::Description,
+        TaskColumn::DueBy,
+        TaskColumn::ImpLvl,
+        TaskColumn::ReqTime,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskColumn::Id => "id",
+            TaskColumn::Name => "name",
+            TaskColumn::Description => "description",
+            TaskColumn::DueBy => "due_by",
+            TaskColumn::ImpLvl => "imp_lvl",
+            TaskColumn::ReqTime => "req_time",
+            TaskColumn::TimeAlloted => "time_alloted",
+            TaskColumn::IsDone => "is_done",
+            TaskColumn::Recurrence => "recurrence",
+            TaskColumn::Tags => "tags",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|column| column.as_str() == name.trim())
+    }
+
+    // Headers of an imported file, our own names or the usual spreadsheet wording for them
+    pub fn from_header(header: &str) -> Option<Self> {
+        let header = header.trim().to_lowercase().replace([' ', '-'], "_");
+        let column = match header.as_str() {
+            "name" | "title" | "task" | "task_name" | "summary" => TaskColumn::Name,
+            "description" | "details" | "notes" | "note" => TaskColumn::Description,
+            "due_by" | "due" | "due_date" | "deadline" | "due_at" => TaskColumn::DueBy,
+            "imp_lvl" | "importance" | "priority" | "prio" => TaskColumn::ImpLvl,
+            "req_time" | "required_time" | "estimate" | "duration" | "effort" => {
+                TaskColumn::ReqTime
+            }
+            _ => return None,
+        };
+        Some(column)
+    }
+}
+
+// Comma separated column names, e.g. `name,due_by,tags`
+fn parse_columns(columns: &str) -> Result<Vec<TaskColumn>, String> {
+    columns
+        .split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| {
+            TaskColumn::parse(name).ok_or_else(|| format!("Unknown column '{}'", name.trim()))
+        })
+        .collect()
+}
+
+// Query string of the CSV export
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CsvExportQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<String>,
+}
+
+impl CsvExportQuery {
+    pub fn columns(&self) -> Result<Vec<TaskColumn>, String> {
+        let columns = match self.columns.as_deref() {
+            Some(columns) => parse_columns(columns)?,
+            None => Vec::new(),
+        };
+        if columns.is_empty() {
+            return Ok(TaskColumn::DEFAULT_EXPORT.to_vec());
+        }
+        Ok(columns)
+    }
+}
+
+// Query string of the CSV import
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CsvImportQuery {
+    // Only report what would be imported
+    #[serde(default)]
+    pub preview: bool,
+    // Read dates like 03/04/2025 as March 4th instead of April 3rd
+    #[serde(default)]
+    pub month_first: bool,
+    // Headers the import doesn't recognize by itself, e.g. `Wann:due_by,Aufwand:req_time`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map: Option<String>,
+}
+
+impl CsvImportQuery {
+    pub fn mapping(&self) -> Result<Vec<(String, TaskColumn)>, String> {
+        let Some(map) = self.map.as_deref() else {
+            return Ok(Vec::new());
+        };
+        map.split(',')
+            .filter(|pair| !pair.trim().is_empty())
+            .map(|pair| {
+                let (header, column) = pair
+                    .rsplit_once(':')
+                    .ok_or_else(|| format!("'{}' is not of the form header:column", pair))?;
+                let column = TaskColumn::parse(column)
+                    .filter(|column| TaskColumn::IMPORTED.contains(column))
+                    .ok_or_else(|| format!("'{}' is not a column the import reads", column))?;
+                Ok((header.trim().to_string(), column))
+            })
+            .collect()
+    }
+}
+
+// Which column each header of an imported file was read into
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CsvColumn {
+    pub header: String,
+    // None for headers that were ignored
+    pub column: Option<TaskColumn>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RejectedRow {
+    // Line of the file, the header is line 1
+    pub line: u64,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CsvImportReport {
+    // True when nothing was written
+    pub preview: bool,
+    pub columns: Vec<CsvColumn>,
+    // The tasks read from the file, with their ids once they are imported
+    pub tasks: Vec<Task>,
+    pub rejected: Vec<RejectedRow>,
+}
+
+const DATE_TIME_FORMATS: [&str; 6] = [
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%d.%m.%Y %H:%M",
+    "%d.%m.%Y %H:%M:%S",
+];
+
+// Due dates as spreadsheets write them: ISO dates with or without a time or an offset,
+// 31.12.2025, and 31/12/2025 (12/31/2025 with `month_first`). A date without a time means the
+// end of that day.
+pub fn parse_due_by(value: &str, month_first: bool) -> Result<NaiveDateTime, String> {
+    let value = value.trim();
+    if let Ok(with_offset) = DateTime::parse_from_rfc3339(value) {
+        return Ok(with_offset.with_timezone(&Local).naive_local());
+    }
+    let slash = if month_first { "%m/%d/%Y" } else { "%d/%m/%Y" };
+    let with_time = DATE_TIME_FORMATS
+        .into_iter()
+        .map(String::from)
+        .chain([format!("{} %H:%M", slash), format!("{} %H:%M:%S", slash)]);
+    for format in with_time {
+        if let Ok(due_by) = NaiveDateTime::parse_from_str(value, &format) {
+            return Ok(due_by);
+        }
+    }
+    let end_of_day = NaiveTime::from_hms_opt(23, 59, 0).unwrap();
+    for format in ["%Y-%m-%d", "%d.%m.%Y", slash] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Ok(date.and_time(end_of_day));
+        }
+    }
+    Err(format!("'{}' is not a date this import understands", value))
+}
+
+// Required time as "1:30", "01:30:00", "90" minutes or "1h 30m"
+pub fn parse_req_time(value: &str) -> Result<NaiveTime, String> {
+    let value = value.trim();
+    let invalid = || format!("'{}' is not a duration like 1:30, 90 or 1h 30m", value);
+    for format in ["%H:%M:%S", "%H:%M"] {
+        if let Ok(time) = NaiveTime::parse_from_str(value, format) {
+            return Ok(time);
+        }
+    }
+    let minutes = match value.parse::<u32>() {
+        Ok(minutes) => minutes,
+        Err(_) => {
+            let compact = value.to_lowercase().replace(' ', "");
+            let (hours, rest) = match compact.split_once('h') {
+                Some((hours, rest)) => (hours.parse::<u32>().map_err(|_| invalid())?, rest),
+                None => (0, compact.as_str()),
+            };
+            let rest = rest
+                .strip_suffix("min")
+                .or(rest.strip_suffix('m'))
+                .unwrap_or(rest);
+            let minutes = match rest {
+                "" => 0,
+                rest => rest.parse::<u32>().map_err(|_| invalid())?,
+            };
+            hours * 60 + minutes
+        }
+    };
+    NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn due_dates_in_several_formats() {
+        for value in [
+            "2025-03-04T09:30",
+            "2025-03-04 09:30:00",
+            "04.03.2025 09:30",
+            "04/03/2025 09:30",
+        ] {
+            assert_eq!(
+                parse_due_by(value, false),
+                Ok(at("2025-03-04 09:30")),
+                "{}",
+                value
+            );
+        }
+        assert_eq!(
+            parse_due_by("03/04/2025 09:30", true),
+            Ok(at("2025-03-04 09:30"))
+        );
+        assert_eq!(
+            parse_due_by("2025-03-04", false),
+            Ok(at("2025-03-04 23:59"))
+        );
+        assert!(parse_due_by("next friday", false).is_err());
+    }
+
+    #[test]
+    fn required_times_in_several_formats() {
+        let ninety = NaiveTime::from_hms_opt(1, 30, 0).unwrap();
+        for value in ["1:30", "01:30:00", "90", "1h 30m", "1h30min"] {
+            assert_eq!(parse_req_time(value), Ok(ninety), "{}", value);
+        }
+        assert_eq!(
+            parse_req_time("2h"),
+            Ok(NaiveTime::from_hms_opt(2, 0, 0).unwrap())
+        );
+        assert!(parse_req_time("a while").is_err());
+        assert!(parse_req_time("1500").is_err());
+    }
+
+    #[test]
+    fn headers_columns_and_mappings() {
+        assert_eq!(
+            TaskColumn::from_header(" Due Date "),
+            Some(TaskColumn::DueBy)
+        );
+        assert_eq!(
+            TaskColumn::from_header("Priority"),
+            Some(TaskColumn::ImpLvl)
+        );
+        assert_eq!(TaskColumn::from_header("Owner"), None);
+
+        let export = CsvExportQuery::default();
+        assert_eq!(export.columns(), Ok(TaskColumn::DEFAULT_EXPORT.to_vec()));
+        let export = CsvExportQuery {
+            columns: Some("name, tags".to_string()),
+        };
+        assert_eq!(
+            export.columns(),
+            Ok(vec![TaskColumn::Name, TaskColumn::Tags])
+        );
+
+        let import = CsvImportQuery {
+            map: Some("Wann:due_by, Aufwand:req_time".to_string()),
+            ..CsvImportQuery::default()
+        };
+        assert_eq!(
+            import.mapping(),
+            Ok(vec![
+                ("Wann".to_string(), TaskColumn::DueBy),
+                ("Aufwand".to_string(), TaskColumn::ReqTime),
+            ])
+        );
+        let import = CsvImportQuery {
+            map: Some("Done:is_done".to_string()),
+            ..CsvImportQuery::default()
+        };
+        assert!(import.mapping().is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+// Body of both signup and login. No Debug so the password can't end up in a log line.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub id: String,
+    pub username: String,
+}
+
+// Returned by signup and login, `token` goes into `Authorization: Bearer <token>`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuthSession {
+    pub token: String,
+    pub user: User,
+    pub expires_at: NaiveDateTime,
+}
+
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+impl Credentials {
+    pub fn validate(&self) -> Result<(), String> {
+        let username_len = self.username.chars().count();
+        if !(3..=32).contains(&username_len) {
+            return Err("Username must be between 3 and 32 characters".to_string());
+        }
+        if !self
+            .username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            return Err("Username may only contain letters, digits, '_', '-' and '.'".to_string());
+        }
+        if self.password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(format!(
+                "Password must be at least {} characters",
+                MIN_PASSWORD_LEN
+            ));
+        }
+        Ok(())
+    }
+}